@@ -3,6 +3,7 @@
 //
 // This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2020 Datadog, Inc.
 //
+use crate::HugePageSize;
 use std::{
     fmt::{self, Debug},
     io,
@@ -140,6 +141,16 @@ pub enum BuilderErrorKind {
     /// for threads that panicked.  The contained error is forwarded from
     /// [`JoinHandle`](std::thread::JoinHandle).
     ThreadPanic(Box<dyn std::any::Any + Send + Sync>),
+    /// Error type for requesting
+    /// [`io_memory_hugepages`](crate::LocalExecutorBuilder::io_memory_hugepages)
+    /// when there aren't enough hugepages of the requested size available.
+    HugePagesUnavailable {
+        /// The size of the hugepages requested.
+        page_size: HugePageSize,
+
+        /// The error returned when mapping the hugepages.
+        source: io::Error,
+    },
 }
 
 impl fmt::Display for BuilderErrorKind {
@@ -157,6 +168,11 @@ impl fmt::Display for BuilderErrorKind {
                 "requested {minimum} shards but a minimum of {shards} is required"
             ),
             Self::ThreadPanic(_) => write!(f, "thread panicked"),
+            Self::HugePagesUnavailable { page_size, source } => write!(
+                f,
+                "failed to map I/O memory with hugepages of {} bytes: {source}",
+                page_size.bytes()
+            ),
         }
     }
 }
@@ -458,6 +474,10 @@ impl<T> Debug for GlommioError<T> {
                     write!(f, "NrShards {{ minimum: {minimum}, shards: {shards} }}")
                 }
                 BuilderErrorKind::ThreadPanic(_) => write!(f, "Thread panicked {{ .. }}"),
+                BuilderErrorKind::HugePagesUnavailable { page_size, source } => write!(
+                    f,
+                    "HugePagesUnavailable {{ page_size: {page_size:?}, source: {source:?} }}"
+                ),
             },
             GlommioError::EnhancedIoError {
                 source,
@@ -512,6 +532,12 @@ impl<T> From<GlommioError<T>> for io::Error {
                 io::ErrorKind::Other,
                 format!("Executor builder error: {display_err}"),
             ),
+            GlommioError::BuilderError(BuilderErrorKind::HugePagesUnavailable {
+                source, ..
+            }) => io::Error::new(
+                source.kind(),
+                format!("Executor builder error: {display_err}"),
+            ),
             GlommioError::EnhancedIoError { source, .. } => {
                 io::Error::new(source.kind(), display_err)
             }
@@ -239,6 +239,7 @@ pub struct ExecutorStats {
     total_runtime: Duration,
     scheduler_runs: u64,
     tasks_executed: u64,
+    io_memory_page_size: usize,
 }
 
 impl ExecutorStats {
@@ -248,6 +249,7 @@ impl ExecutorStats {
             total_runtime: Duration::from_nanos(0),
             scheduler_runs: 0,
             tasks_executed: 0,
+            io_memory_page_size: 0,
         }
     }
 
@@ -278,6 +280,34 @@ impl ExecutorStats {
     pub fn tasks_executed(&self) -> u64 {
         self.tasks_executed
     }
+
+    /// Returns the size of the pages backing the memory reserved for storage
+    /// I/O: 4096 bytes, unless hugepages were requested with
+    /// [`LocalExecutorBuilder::io_memory_hugepages`] and taken from the ones
+    /// reserved by the system.
+    pub fn io_memory_page_size(&self) -> usize {
+        self.io_memory_page_size
+    }
+}
+
+/// The size of the hugepages backing the memory reserved for storage I/O. See
+/// [`LocalExecutorBuilder::io_memory_hugepages`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HugePageSize {
+    /// 2 MiB hugepages
+    Size2M,
+    /// 1 GiB hugepages
+    Size1G,
+}
+
+impl HugePageSize {
+    /// The size of a hugepage, in bytes.
+    pub fn bytes(&self) -> usize {
+        match self {
+            HugePageSize::Size2M => 2 << 20,
+            HugePageSize::Size1G => 1 << 30,
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -463,6 +493,8 @@ pub struct LocalExecutorBuilder {
     /// that, but it will come from the standard allocator and performance
     /// will suffer. Defaults to 10 MiB.
     io_memory: usize,
+    /// The size of the hugepages backing the I/O memory, if any
+    io_memory_hugepages: Option<HugePageSize>,
     /// The depth of the IO rings to create. This influences the level of IO
     /// concurrency. A higher ring depth allows a shard to submit a
     /// greater number of IO requests to the kernel at once.
@@ -493,6 +525,7 @@ impl LocalExecutorBuilder {
             spin_before_park: None,
             name: String::from(DEFAULT_EXECUTOR_NAME),
             io_memory: DEFAULT_IO_MEMORY,
+            io_memory_hugepages: None,
             ring_depth: DEFAULT_RING_SUBMISSION_DEPTH,
             preempt_timer_duration: DEFAULT_PREEMPT_TIMER,
             record_io_latencies: false,
@@ -529,6 +562,36 @@ impl LocalExecutorBuilder {
         self
     }
 
+    /// Reserves `io_memory` bytes for storage I/O, like
+    /// [`LocalExecutorBuilder::io_memory`], backed by hugepages of
+    /// `page_size`. The amount is rounded up to a multiple of the page size.
+    ///
+    /// Hugepages cut the TLB misses of the code filling and reading the
+    /// buffers, and the number of pages the kernel has to pin when the memory
+    /// is registered with the rings. The memory is taken from the hugepages
+    /// reserved by the system (see
+    /// `/proc/sys/vm/nr_hugepages`). If there aren't enough of them, 2 MiB
+    /// pages fall back to transparent hugepages, if they are enabled.
+    /// Otherwise, creating the executor fails with
+    /// [`BuilderErrorKind::HugePagesUnavailable`].
+    ///
+    /// The page size in use is reported by
+    /// [`ExecutorStats::io_memory_page_size`]. Transparent hugepages are only
+    /// used where the kernel manages to assemble them, so the base page size is
+    /// reported after falling back to them.
+    ///
+    /// [`BuilderErrorKind::HugePagesUnavailable`]: crate::BuilderErrorKind::HugePagesUnavailable
+    #[must_use = "The builder must be built to be useful"]
+    pub fn io_memory_hugepages(
+        mut self,
+        io_memory: usize,
+        page_size: HugePageSize,
+    ) -> LocalExecutorBuilder {
+        self.io_memory = io_memory;
+        self.io_memory_hugepages = Some(page_size);
+        self
+    }
+
     /// The depth of the IO rings to create. This influences the level of IO
     /// concurrency. A higher ring depth allows a shard to submit a
     /// greater number of IO requests to the kernel at once.
@@ -617,6 +680,7 @@ impl LocalExecutorBuilder {
             cpu_set_gen.next().cpu_binding(),
             LocalExecutorConfig {
                 io_memory: self.io_memory,
+                io_memory_hugepages: self.io_memory_hugepages,
                 ring_depth: self.ring_depth,
                 preempt_timer: self.preempt_timer_duration,
                 record_io_latencies: self.record_io_latencies,
@@ -683,6 +747,7 @@ impl LocalExecutorBuilder {
         let name = format!("{}-{}", self.name, notifier.id());
         let mut cpu_set_gen = placement::CpuSetGenerator::one(self.placement)?;
         let io_memory = self.io_memory;
+        let io_memory_hugepages = self.io_memory_hugepages;
         let ring_depth = self.ring_depth;
         let preempt_timer_duration = self.preempt_timer_duration;
         let spin_before_park = self.spin_before_park;
@@ -698,6 +763,7 @@ impl LocalExecutorBuilder {
                     cpu_set_gen.next().cpu_binding(),
                     LocalExecutorConfig {
                         io_memory,
+                        io_memory_hugepages,
                         ring_depth,
                         preempt_timer: preempt_timer_duration,
                         record_io_latencies,
@@ -754,6 +820,8 @@ pub struct LocalExecutorPoolBuilder {
     /// that, but it will come from the standard allocator and performance
     /// will suffer. Defaults to 10 MiB.
     io_memory: usize,
+    /// The size of the hugepages backing the I/O memory, if any
+    io_memory_hugepages: Option<HugePageSize>,
     /// The depth of the IO rings to create. This influences the level of IO
     /// concurrency. A higher ring depth allows a shard to submit a
     /// greater number of IO requests to the kernel at once.
@@ -780,6 +848,7 @@ impl fmt::Debug for LocalExecutorPoolBuilder {
             .field("spin_before_park", &self.spin_before_park)
             .field("name", &self.name)
             .field("io_memory", &self.io_memory)
+            .field("io_memory_hugepages", &self.io_memory_hugepages)
             .field("ring_depth", &self.ring_depth)
             .field("preempt_timer_duration", &self.preempt_timer_duration)
             .field("record_io_latencies", &self.record_io_latencies)
@@ -802,6 +871,7 @@ impl LocalExecutorPoolBuilder {
             spin_before_park: None,
             name: String::from(DEFAULT_EXECUTOR_NAME),
             io_memory: DEFAULT_IO_MEMORY,
+            io_memory_hugepages: None,
             ring_depth: DEFAULT_RING_SUBMISSION_DEPTH,
             preempt_timer_duration: DEFAULT_PREEMPT_TIMER,
             placement: placement.clone(),
@@ -839,6 +909,16 @@ impl LocalExecutorPoolBuilder {
         self
     }
 
+    /// Please see documentation under
+    /// [`LocalExecutorBuilder::io_memory_hugepages`] for details.  The setting
+    /// is applied to all executors in the pool.
+    #[must_use = "The builder must be built to be useful"]
+    pub fn io_memory_hugepages(mut self, io_memory: usize, page_size: HugePageSize) -> Self {
+        self.io_memory = io_memory;
+        self.io_memory_hugepages = Some(page_size);
+        self
+    }
+
     /// Please see documentation under [`LocalExecutorBuilder::ring_depth`] for
     /// details.  The setting is applied to all executors in the pool.
     #[must_use = "The builder must be built to be useful"]
@@ -964,6 +1044,7 @@ impl LocalExecutorPoolBuilder {
         let name = format!("{}-{}", self.name, notifier.id());
         let handle = Builder::new().name(name).spawn({
             let io_memory = self.io_memory;
+            let io_memory_hugepages = self.io_memory_hugepages;
             let ring_depth = self.ring_depth;
             let preempt_timer_duration = self.preempt_timer_duration;
             let spin_before_park = self.spin_before_park;
@@ -981,6 +1062,7 @@ impl LocalExecutorPoolBuilder {
                         cpu_binding,
                         LocalExecutorConfig {
                             io_memory,
+                            io_memory_hugepages,
                             ring_depth,
                             preempt_timer: preempt_timer_duration,
                             record_io_latencies,
@@ -1076,6 +1158,7 @@ pub(crate) fn maybe_activate(tq: Rc<RefCell<TaskQueue>>) {
 
 pub struct LocalExecutorConfig {
     pub io_memory: usize,
+    pub io_memory_hugepages: Option<HugePageSize>,
     pub ring_depth: usize,
     pub preempt_timer: Duration,
     pub record_io_latencies: bool,
@@ -1152,6 +1235,19 @@ impl LocalExecutor {
             Some(cpu_set) => bind_to_cpu_set(cpu_set)?,
             None => config.spin_before_park = None,
         }
+        let io_arena = config
+            .io_memory_hugepages
+            .map(|page_size| {
+                sys::UringBufferAllocator::with_hugepages(config.io_memory, page_size).map_err(
+                    |source| {
+                        GlommioError::BuilderError(BuilderErrorKind::HugePagesUnavailable {
+                            page_size,
+                            source,
+                        })
+                    },
+                )
+            })
+            .transpose()?;
         let p = parking::Parker::new();
         let queues = ExecutorQueues::new(config.preempt_timer, config.spin_before_park);
         let id = notifier.id();
@@ -1163,6 +1259,7 @@ impl LocalExecutor {
             reactor: Rc::new(reactor::Reactor::new(
                 notifier,
                 config.io_memory,
+                io_arena,
                 config.ring_depth,
                 config.record_io_latencies,
                 config.thread_pool_placement,
@@ -2481,10 +2578,11 @@ impl ExecutorProxy {
     /// [`ExecutorStats`]: struct.ExecutorStats.html
     pub fn executor_stats(&self) -> ExecutorStats {
         #[cfg(not(feature = "native-tls"))]
-        return LOCAL_EX.with(|local_ex| std::mem::take(&mut local_ex.queues.borrow_mut().stats));
+        let mut stats =
+            LOCAL_EX.with(|local_ex| std::mem::take(&mut local_ex.queues.borrow_mut().stats));
 
         #[cfg(feature = "native-tls")]
-        return std::mem::take(unsafe {
+        let mut stats = std::mem::take(unsafe {
             &mut LOCAL_EX
                 .as_ref()
                 .expect("this thread doesn't have a LocalExecutor running")
@@ -2492,6 +2590,9 @@ impl ExecutorProxy {
                 .borrow_mut()
                 .stats
         });
+
+        stats.io_memory_page_size = self.reactor().io_memory_page_size();
+        stats
     }
 
     /// Returns an [`IoStats`] struct with information about IO performed by
@@ -2898,6 +2999,35 @@ mod test {
         }
     }
 
+    #[test]
+    fn create_with_hugepages() {
+        let local_ex = LocalExecutor::default();
+        local_ex.run(async {
+            assert_eq!(
+                crate::executor().executor_stats().io_memory_page_size(),
+                4096
+            );
+        });
+
+        // Hugepages may not be available on the host, in which case building
+        // has to fail cleanly
+        match LocalExecutorBuilder::default()
+            .io_memory_hugepages(4 << 20, HugePageSize::Size2M)
+            .make()
+        {
+            Ok(local_ex) => local_ex.run(async {
+                // 4096 after falling back to transparent hugepages
+                let page_size = crate::executor().executor_stats().io_memory_page_size();
+                assert!(page_size == 2 << 20 || page_size == 4096);
+            }),
+            Err(GlommioError::BuilderError(BuilderErrorKind::HugePagesUnavailable {
+                page_size,
+                ..
+            })) => assert_eq!(page_size, HugePageSize::Size2M),
+            Err(x) => panic!("got error {:?}", x),
+        }
+    }
+
     #[test]
     #[should_panic]
     fn spawn_without_executor() {
@@ -0,0 +1,191 @@
+// Unless explicitly stated otherwise all files in this repository are licensed
+// under the MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021 Datadog, Inc.
+//
+use crate::{reactor::Reactor, GlommioError};
+use std::{
+    io,
+    os::unix::io::{AsRawFd, RawFd},
+    rc::{Rc, Weak},
+};
+
+type Result<T> = crate::Result<T, ()>;
+
+/// Wraps an object with a file descriptor so its readiness can be awaited on
+/// the reactor.
+///
+/// This is the way to integrate libraries that do their own I/O on file
+/// descriptors and only need to be told when to retry, like database client
+/// libraries or DNS resolvers. The file descriptor should be in non-blocking
+/// mode: operations are attempted as usual, and [`readable`] or [`writable`]
+/// is awaited whenever they fail with [`io::ErrorKind::WouldBlock`].
+///
+/// Readiness is polled with a `PollAdd` request issued on behalf of the task
+/// queue that awaits it, so the request follows the latency requirements of
+/// that queue like any other I/O it issues.
+///
+/// # Examples
+///
+/// ```no_run
+/// use glommio::{io::AsyncFd, LocalExecutor};
+/// use std::{io::Read, os::unix::net::UnixStream};
+///
+/// let ex = LocalExecutor::default();
+/// ex.run(async {
+///     let (stream, _other) = UnixStream::pair().unwrap();
+///     stream.set_nonblocking(true).unwrap();
+///     let stream = AsyncFd::new(stream).unwrap();
+///     let mut buf = [0u8; 1024];
+///     let read = stream.read_with(|mut s| s.read(&mut buf)).await.unwrap();
+/// });
+/// ```
+///
+/// [`readable`]: AsyncFd::readable
+/// [`writable`]: AsyncFd::writable
+#[derive(Debug)]
+pub struct AsyncFd<T: AsRawFd> {
+    inner: T,
+    reactor: Weak<Reactor>,
+}
+
+impl<T: AsRawFd> AsyncFd<T> {
+    /// Wraps `inner`. Must be called from within an executor, whose reactor
+    /// then polls the file descriptor.
+    pub fn new(inner: T) -> Result<AsyncFd<T>> {
+        Ok(AsyncFd {
+            inner,
+            reactor: Rc::downgrade(&crate::executor().reactor()),
+        })
+    }
+
+    /// Returns a reference to the wrapped object.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped object.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Unwraps the object.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Waits until the file descriptor is readable, or in an error or hang-up
+    /// state.
+    pub async fn readable(&self) -> Result<()> {
+        let source = self.reactor().poll_read_ready(self.as_raw_fd());
+        source.collect_rw().await.map_err(|source| {
+            GlommioError::create_enhanced::<&str>(
+                source,
+                "Polling for read readiness",
+                None,
+                Some(self.as_raw_fd()),
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Waits until the file descriptor is writable, or in an error or hang-up
+    /// state.
+    pub async fn writable(&self) -> Result<()> {
+        let source = self.reactor().poll_write_ready(self.as_raw_fd());
+        source.collect_rw().await.map_err(|source| {
+            GlommioError::create_enhanced::<&str>(
+                source,
+                "Polling for write readiness",
+                None,
+                Some(self.as_raw_fd()),
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Attempts an operation on the wrapped object, without waiting.
+    ///
+    /// Returns `None` if the operation failed with
+    /// [`io::ErrorKind::WouldBlock`], in which case it should be retried
+    /// after awaiting [`readable`] or [`writable`].
+    ///
+    /// [`readable`]: AsyncFd::readable
+    /// [`writable`]: AsyncFd::writable
+    pub fn try_io<R>(&self, op: impl FnOnce(&T) -> io::Result<R>) -> Option<io::Result<R>> {
+        match op(&self.inner) {
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => None,
+            res => Some(res),
+        }
+    }
+
+    /// Runs a reading operation until it doesn't fail with
+    /// [`io::ErrorKind::WouldBlock`], waiting for the file descriptor to be
+    /// readable in between.
+    pub async fn read_with<R>(&self, mut op: impl FnMut(&T) -> io::Result<R>) -> Result<R> {
+        loop {
+            if let Some(res) = self.try_io(&mut op) {
+                return res.map_err(Into::into);
+            }
+            self.readable().await?;
+        }
+    }
+
+    /// Runs a writing operation until it doesn't fail with
+    /// [`io::ErrorKind::WouldBlock`], waiting for the file descriptor to be
+    /// writable in between.
+    pub async fn write_with<R>(&self, mut op: impl FnMut(&T) -> io::Result<R>) -> Result<R> {
+        loop {
+            if let Some(res) = self.try_io(&mut op) {
+                return res.map_err(Into::into);
+            }
+            self.writable().await?;
+        }
+    }
+
+    fn reactor(&self) -> Rc<Reactor> {
+        self.reactor.upgrade().unwrap()
+    }
+}
+
+impl<T: AsRawFd> AsRawFd for AsyncFd<T> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::timer::sleep;
+    use std::{
+        io::{Read, Write},
+        os::unix::net::UnixStream,
+        time::Duration,
+    };
+
+    #[test]
+    fn async_fd_readiness() {
+        test_executor!(async move {
+            let (ours, theirs) = UnixStream::pair().unwrap();
+            ours.set_nonblocking(true).unwrap();
+            let ours = AsyncFd::new(ours).unwrap();
+            ours.writable().await.unwrap();
+
+            let mut buf = [0u8; 16];
+            assert!(ours.try_io(|mut s| s.read(&mut buf)).is_none());
+
+            let writer = crate::spawn_local(async move {
+                sleep(Duration::from_millis(10)).await;
+                (&theirs).write_all(b"hello").unwrap();
+                theirs
+            });
+            let read = ours.read_with(|mut s| s.read(&mut buf)).await.unwrap();
+            assert_eq!(&buf[..read], b"hello");
+            drop(writer.await);
+
+            ours.readable().await.unwrap();
+            assert_eq!(ours.try_io(|mut s| s.read(&mut buf)).unwrap().unwrap(), 0);
+        });
+    }
+}
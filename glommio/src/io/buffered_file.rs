@@ -5,7 +5,12 @@
 //
 
 use crate::{
-    io::{glommio_file::GlommioFile, read_result::ReadResult, OpenOptions},
+    io::{
+        glommio_file::{Advice, GlommioFile, SyncRangeFlags},
+        read_result::ReadResult,
+        OpenOptions,
+    },
+    sys::{DirectIo, IoBuffer, PollableStatus},
     GlommioError,
 };
 use std::{
@@ -159,6 +164,82 @@ impl BufferedFile {
         })
     }
 
+    /// Writes the buffers in `bufs` back to back to this `BufferedFile`,
+    /// starting at the specified position, with a single I/O request.
+    ///
+    /// Like with [`BufferedFile::write_at`], it is legal to write fewer bytes
+    /// than the buffers hold in total. At most `IOV_MAX` (usually 1024)
+    /// buffers can be written at once.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use glommio::{io::BufferedFile, LocalExecutor};
+    ///
+    /// let ex = LocalExecutor::default();
+    /// ex.run(async {
+    ///     let file = BufferedFile::create("test.txt").await.unwrap();
+    ///
+    ///     let bufs = vec![b"hello ".to_vec(), b"world".to_vec()];
+    ///     file.write_vectored_at(bufs, 0).await.unwrap();
+    ///     file.close().await.unwrap();
+    /// });
+    /// ```
+    pub async fn write_vectored_at(&self, bufs: Vec<Vec<u8>>, pos: u64) -> Result<usize> {
+        let bufs = bufs.into_iter().map(IoBuffer::Buffered).collect();
+        let source = self.file.reactor.upgrade().unwrap().write_vectored(
+            self.as_raw_fd(),
+            bufs,
+            pos,
+            PollableStatus::NonPollable(DirectIo::Disabled),
+        );
+        source.collect_rw().await.map_err(|source| {
+            GlommioError::create_enhanced(
+                source,
+                "Writing",
+                self.file.path.borrow().as_ref(),
+                Some(self.as_raw_fd()),
+            )
+        })
+    }
+
+    /// Reads the data at the specified position into the buffers in `bufs`,
+    /// filling them one after the other with a single I/O request, and hands
+    /// them back along with the number of bytes read.
+    ///
+    /// The whole length of every buffer is read into, regardless of its
+    /// capacity. The read stops short at the end of the file, so the buffers
+    /// past the number of bytes read keep their previous contents.
+    pub async fn read_vectored_at(
+        &self,
+        bufs: Vec<Vec<u8>>,
+        pos: u64,
+    ) -> Result<(usize, Vec<Vec<u8>>)> {
+        let bufs = bufs.into_iter().map(IoBuffer::Buffered).collect();
+        let source = self.file.reactor.upgrade().unwrap().read_vectored(
+            self.as_raw_fd(),
+            bufs,
+            pos,
+            PollableStatus::NonPollable(DirectIo::Disabled),
+        );
+        let read_size = source.collect_rw().await.map_err(|source| {
+            GlommioError::create_enhanced(
+                source,
+                "Reading",
+                self.file.path.borrow().as_ref(),
+                Some(self.as_raw_fd()),
+            )
+        })?;
+        let bufs = source
+            .extract_buffers()
+            .into_iter()
+            .map(|buf| match buf {
+                IoBuffer::Buffered(buf) => buf,
+                _ => unreachable!(),
+            })
+            .collect();
+        Ok((read_size, bufs))
+    }
+
     /// Reads data at the specified position into a buffer allocated by this
     /// library.
     ///
@@ -193,6 +274,54 @@ impl BufferedFile {
         self.file.fdatasync().await.map_err(Into::into)
     }
 
+    /// Issues `fsync` for the underlying file. Unlike [`fdatasync`], this
+    /// also flushes all metadata changes, like timestamps, that aren't needed
+    /// to read the data back.
+    ///
+    /// [`fdatasync`]: BufferedFile::fdatasync
+    pub async fn fsync(&self) -> Result<()> {
+        self.file.fsync().await
+    }
+
+    /// Issues `sync_file_range` for `len` bytes of the file starting at
+    /// `offset`. A `len` of zero extends the range to the end of the file.
+    ///
+    /// Writing a range back with [`SyncRangeFlags::WRITE`] as soon as it's
+    /// complete keeps dirty pages from piling up in the page cache, and
+    /// shortens the [`fdatasync`] that makes them durable later.
+    ///
+    /// [`fdatasync`]: BufferedFile::fdatasync
+    pub async fn sync_range(&self, offset: u64, len: u64, flags: SyncRangeFlags) -> Result<()> {
+        self.file.sync_range(offset, len, flags).await
+    }
+
+    /// Tells the OS how `len` bytes of the file starting at `offset` are going
+    /// to be accessed. A `len` of zero extends the range to the end of the
+    /// file.
+    ///
+    /// This is only a hint: it never changes the contents of the file, and
+    /// the OS is free to ignore it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use glommio::{
+    ///     io::{Advice, BufferedFile},
+    ///     LocalExecutor,
+    /// };
+    ///
+    /// let ex = LocalExecutor::default();
+    /// ex.run(async {
+    ///     let file = BufferedFile::open("myfile.txt").await.unwrap();
+    ///     file.advise(0, 0, Advice::WillNeed).await.unwrap();
+    ///     // ... scan the file ...
+    ///     file.advise(0, 0, Advice::DontNeed).await.unwrap();
+    /// });
+    /// ```
+    pub async fn advise(&self, offset: u64, len: u64, advice: Advice) -> Result<()> {
+        self.file.advise(offset, len, advice).await
+    }
+
     /// Erases a range from the file without changing the size. Check the man
     /// page for [`fallocate`] for a list of the supported filesystems.
     /// Partial blocks are zeroed while whole blocks are simply unmapped
@@ -335,6 +464,31 @@ mod test {
         std::assert!(!path.join("testfile").exists());
     });
 
+    buffered_file_test!(vectored_io, path, _k, {
+        let file = BufferedFile::create(path.join("testfile")).await.unwrap();
+        let bufs = vec![b"hello".to_vec(), b", ".to_vec(), b"world".to_vec()];
+        assert_eq!(file.write_vectored_at(bufs, 2).await.unwrap(), 12);
+
+        let bufs = vec![vec![0; 4], vec![0; 6], vec![0; 8]];
+        let (read, bufs) = file.read_vectored_at(bufs, 0).await.unwrap();
+        assert_eq!(read, 14);
+        assert_eq!(bufs[0], b"\0\0he");
+        assert_eq!(bufs[1], b"llo, w");
+        assert_eq!(bufs[2], b"orld\0\0\0\0");
+        file.close().await.unwrap();
+    });
+
+    buffered_file_test!(advise_range, path, _k, {
+        let file = BufferedFile::create(path.join("testfile")).await.unwrap();
+        file.write_at(vec![7; 8192], 0).await.unwrap();
+
+        file.advise(0, 0, Advice::WillNeed).await.unwrap();
+        let rb = file.read_at(0, 8192).await.unwrap();
+        assert!(rb.iter().all(|x| *x == 7));
+        file.advise(0, 8192, Advice::DontNeed).await.unwrap();
+        file.close().await.unwrap();
+    });
+
     buffered_file_test!(random_io, path, _k, {
         let writer = BufferedFile::create(path.join("testfile")).await.unwrap();
 
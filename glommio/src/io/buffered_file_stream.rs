@@ -4,7 +4,7 @@
 // This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2020 Datadog, Inc.
 
 use crate::{
-    io::{BufferedFile, ScheduledSource},
+    io::{glommio_file::Advice, BufferedFile, ScheduledSource},
     reactor::Reactor,
     sys::{IoBuffer, Source, Statx},
 };
@@ -43,6 +43,9 @@ pin_project! {
         io_source: Option<ScheduledSource>,
         seek_source: Option<Source>,
         buffer: Buffer,
+        sequential_hints: bool,
+        advised_pos: u64,
+        advise_source: Option<Source>,
         reactor: Weak<Reactor>,
     }
 }
@@ -120,6 +123,7 @@ pub struct StreamReaderBuilder {
     start: u64,
     end: u64,
     buffer_size: usize,
+    sequential_hints: bool,
     file: BufferedFile,
 }
 
@@ -208,6 +212,19 @@ impl StreamReader {
     }
 
     fn new(builder: StreamReaderBuilder) -> StreamReader {
+        let reactor = crate::executor().reactor();
+        let advise_source = builder.sequential_hints.then(|| {
+            let len = match builder.end {
+                u64::MAX => 0,
+                end => end.saturating_sub(builder.start),
+            };
+            reactor.fadvise(
+                builder.file.as_raw_fd(),
+                builder.start,
+                len,
+                Advice::Sequential.into(),
+            )
+        });
         StreamReader {
             file: builder.file,
             file_pos: builder.start,
@@ -215,8 +232,31 @@ impl StreamReader {
             io_source: None,
             seek_source: None,
             buffer: Buffer::new(builder.buffer_size),
-            reactor: Rc::downgrade(&crate::executor().reactor()),
+            sequential_hints: builder.sequential_hints,
+            advised_pos: builder.start,
+            advise_source,
+            reactor: Rc::downgrade(&reactor),
+        }
+    }
+
+    /// Drops what was read since the last call from the page cache, unless the
+    /// previous request to do so is still in flight.
+    fn drop_behind(&mut self) {
+        if !self.sequential_hints || self.file_pos <= self.advised_pos {
+            return;
+        }
+        if let Some(source) = &self.advise_source {
+            if source.result().is_none() {
+                return;
+            }
         }
+        self.advise_source = Some(self.reactor.upgrade().unwrap().fadvise(
+            self.file.as_raw_fd(),
+            self.advised_pos,
+            self.file_pos - self.advised_pos,
+            Advice::DontNeed.into(),
+        ));
+        self.advised_pos = self.file_pos;
     }
 }
 
@@ -251,6 +291,7 @@ impl StreamReaderBuilder {
             start: 0,
             end: u64::MAX,
             buffer_size: 4 << 10,
+            sequential_hints: false,
             file,
         }
     }
@@ -287,6 +328,21 @@ impl StreamReaderBuilder {
         self
     }
 
+    /// Makes the [`StreamReader`] tell the OS how it reads the file.
+    ///
+    /// When enabled, the range to read is advised as sequential when the
+    /// [`StreamReader`] is built, so the OS reads ahead more aggressively, and
+    /// the data already read is dropped from the page cache as the reader
+    /// advances. This keeps a scan of a large file from evicting more useful
+    /// data from the page cache. Disabled by default.
+    ///
+    /// [`StreamReader`]: struct.StreamReader.html
+    #[must_use = "The builder must be built to be useful"]
+    pub fn with_sequential_hints(mut self, enabled: bool) -> Self {
+        self.sequential_hints = enabled;
+        self
+    }
+
     /// Builds a [`StreamReader`] with the properties defined by this
     /// [`StreamReaderBuilder`]
     ///
@@ -582,6 +638,7 @@ impl AsyncBufRead for StreamReader {
                     let this = self.project();
                     Poll::Ready(Ok(this.buffer.unconsumed_bytes()))
                 } else {
+                    self.as_mut().get_mut().drop_behind();
                     let file_pos = self.file_pos;
                     let fd = self.file.as_raw_fd();
                     let source = self.reactor.upgrade().unwrap().read_buffered(
@@ -807,6 +864,19 @@ mod test {
         reader.close().await.unwrap();
     });
 
+    read_test!(read_to_end_with_hints, path, _k, file, file_size: 65536, {
+        let mut reader = StreamReaderBuilder::new(file)
+            .with_buffer_size(4096)
+            .with_sequential_hints(true)
+            .build();
+
+        let mut buf = Vec::new();
+        let x = reader.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(x, file_size);
+        check_contents!(buf, 0);
+        reader.close().await.unwrap();
+    });
+
     read_test!(read_slice, path, _k, file, _file_size: 4096, {
         let mut reader = StreamReaderBuilder::new(file)
             .with_start_pos(2)
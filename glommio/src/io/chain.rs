@@ -0,0 +1,359 @@
+// Unless explicitly stated otherwise all files in this repository are licensed
+// under the MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021 Datadog, Inc.
+//
+use crate::{
+    io::{glommio_file::GlommioFile, BufferedFile, DmaFile},
+    iou::sqe::SubmissionFlags,
+    sys::{DmaBuffer, DmaSource},
+    GlommioError,
+};
+use std::{io, os::unix::io::AsRawFd, time::Duration};
+
+type Result<T> = crate::Result<T, ()>;
+
+#[derive(Debug)]
+enum Op<'a> {
+    DmaWrite(&'a DmaFile, DmaBuffer, u64),
+    BufferedWrite(&'a BufferedFile, Vec<u8>, u64),
+    DmaSync(&'a DmaFile),
+    BufferedSync(&'a BufferedFile),
+}
+
+impl<'a> Op<'a> {
+    fn file(&self) -> &'a GlommioFile {
+        match self {
+            Op::DmaWrite(file, ..) | Op::DmaSync(file) => &file.file,
+            Op::BufferedWrite(file, ..) | Op::BufferedSync(file) => &file.file,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Op::DmaWrite(..) | Op::BufferedWrite(..) => "Writing",
+            Op::DmaSync(_) | Op::BufferedSync(_) => "Syncing",
+        }
+    }
+}
+
+/// A sequence of I/O requests that are linked together and submitted to the
+/// kernel at once.
+///
+/// The requests in a chain are executed in order, each one starting only
+/// after the previous one completed. If a request fails, the requests after
+/// it are canceled and complete with `ECANCELED`, unless the chain was built
+/// with [`with_hard_links`], in which case they run regardless.
+///
+/// This is useful to express "write this, then make it durable" in a single
+/// trip to the kernel, without waiting for the write to come back before
+/// issuing the sync.
+///
+/// All requests of a chain must fit in the io_uring submission queue at once,
+/// so chains should be kept short. A request with a timeout takes two entries
+/// of the queue. Chains that don't fit fail as a whole, without being
+/// submitted, and all of their requests complete with `EINVAL`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use glommio::{
+///     io::{DmaFile, IoChain},
+///     LocalExecutor,
+/// };
+///
+/// let ex = LocalExecutor::default();
+/// ex.run(async {
+///     let file = DmaFile::create("test.txt").await.unwrap();
+///     let buf = file.alloc_dma_buffer(4096);
+///
+///     let results = IoChain::new()
+///         .write_at(&file, buf, 0)
+///         .fdatasync(&file)
+///         .submit()
+///         .await;
+///     for res in results {
+///         res.unwrap();
+///     }
+///     file.close().await.unwrap();
+/// });
+/// ```
+///
+/// [`with_hard_links`]: IoChain::with_hard_links
+#[derive(Debug, Default)]
+pub struct IoChain<'a> {
+    steps: Vec<(Op<'a>, Option<Duration>)>,
+    hard_links: bool,
+}
+
+impl<'a> IoChain<'a> {
+    /// Creates an empty chain.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a failing request should let the rest of the chain run.
+    ///
+    /// Defaults to `false`: the requests after a failed one are canceled.
+    pub fn with_hard_links(mut self, hard_links: bool) -> Self {
+        self.hard_links = hard_links;
+        self
+    }
+
+    /// Appends a write of `buf` at `pos` to a [`DmaFile`]. The same
+    /// alignment rules as [`DmaFile::write_at`] apply.
+    pub fn write_at(self, file: &'a DmaFile, buf: DmaBuffer, pos: u64) -> Self {
+        self.push(Op::DmaWrite(file, buf, pos))
+    }
+
+    /// Appends a write of `buf` at `pos` to a [`BufferedFile`].
+    pub fn write_buffered_at(self, file: &'a BufferedFile, buf: Vec<u8>, pos: u64) -> Self {
+        self.push(Op::BufferedWrite(file, buf, pos))
+    }
+
+    /// Appends a call to `fdatasync` on a [`DmaFile`].
+    pub fn fdatasync(self, file: &'a DmaFile) -> Self {
+        self.push(Op::DmaSync(file))
+    }
+
+    /// Appends a call to `fdatasync` on a [`BufferedFile`].
+    pub fn fdatasync_buffered(self, file: &'a BufferedFile) -> Self {
+        self.push(Op::BufferedSync(file))
+    }
+
+    /// Bounds how long the last request added to the chain may take. If it
+    /// does not complete in time it fails with [`std::io::ErrorKind::TimedOut`]
+    /// and the requests after it are canceled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the chain is empty.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        let (_, t) = self
+            .steps
+            .last_mut()
+            .expect("with_timeout called on an empty chain");
+        *t = Some(timeout);
+        self
+    }
+
+    /// Returns the number of requests in the chain.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns true if the chain holds no requests.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Submits the chain and waits for all of its requests to complete.
+    ///
+    /// Returns the result of each request, in the order they were added.
+    pub async fn submit(self) -> Vec<Result<usize>> {
+        let link = if self.hard_links {
+            SubmissionFlags::IO_HARDLINK
+        } else {
+            SubmissionFlags::IO_LINK
+        };
+
+        let reactor = crate::executor().reactor();
+        let count = self.steps.len();
+        let entries = count + self.steps.iter().filter(|(_, t)| t.is_some()).count();
+        if entries > reactor.ring_depth() {
+            return self
+                .steps
+                .iter()
+                .map(|(op, _)| {
+                    let file = op.file();
+                    Err(GlommioError::create_enhanced(
+                        io::Error::from_raw_os_error(libc::EINVAL),
+                        op.name(),
+                        file.path.borrow().as_ref(),
+                        Some(file.as_raw_fd()),
+                    ))
+                })
+                .collect();
+        }
+        let mut sources = Vec::with_capacity(count);
+        reactor.sys.set_chaining(true);
+        for (idx, (op, timeout)) in self.steps.into_iter().enumerate() {
+            let file = op.file();
+            let name = op.name();
+            let source = match op {
+                Op::DmaWrite(file, buf, pos) => {
+                    reactor.write_dma(file.as_raw_fd(), DmaSource::Owned(buf), pos, file.pollable)
+                }
+                Op::BufferedWrite(file, buf, pos) => {
+                    reactor.write_buffered(file.as_raw_fd(), buf, pos)
+                }
+                Op::DmaSync(file) => reactor.fdatasync(file.as_raw_fd()),
+                Op::BufferedSync(file) => reactor.fdatasync(file.as_raw_fd()),
+            };
+            let next = if idx + 1 < count {
+                link
+            } else {
+                SubmissionFlags::empty()
+            };
+            reactor.sys.link_chained(&source, timeout, next);
+            sources.push((source, file, name));
+        }
+        reactor.sys.set_chaining(false);
+
+        let mut results = Vec::with_capacity(count);
+        let mut failed = false;
+        for (source, file, name) in sources {
+            let res = source.collect_rw().await.map_err(|source| {
+                // A request with a timeout reports being canceled as timing out,
+                // but here it was canceled because an earlier request failed
+                let source =
+                    if failed && !self.hard_links && source.raw_os_error() == Some(libc::ETIMEDOUT)
+                    {
+                        io::Error::from_raw_os_error(libc::ECANCELED)
+                    } else {
+                        source
+                    };
+                GlommioError::create_enhanced(
+                    source,
+                    name,
+                    file.path.borrow().as_ref(),
+                    Some(file.as_raw_fd()),
+                )
+            });
+            failed |= res.is_err();
+            results.push(res);
+        }
+        results
+    }
+
+    fn push(mut self, op: Op<'a>) -> Self {
+        self.steps.push((op, None));
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{io::OpenOptions, test_utils::make_tmp_test_directory};
+
+    #[test]
+    fn chain_write_and_sync() {
+        test_executor!(async move {
+            let dir = make_tmp_test_directory("chain_write_and_sync");
+            let file = DmaFile::create(dir.path.join("dma")).await.unwrap();
+            let buffered = BufferedFile::create(dir.path.join("buffered"))
+                .await
+                .unwrap();
+
+            let mut buf = file.alloc_dma_buffer(4096);
+            buf.as_bytes_mut().fill(42);
+            let results = IoChain::new()
+                .write_at(&file, buf, 0)
+                .fdatasync(&file)
+                .write_buffered_at(&buffered, vec![7; 100], 0)
+                .fdatasync_buffered(&buffered)
+                .submit()
+                .await;
+            let results: Vec<usize> = results.into_iter().map(Result::unwrap).collect();
+            assert_eq!(results, vec![4096, 0, 100, 0]);
+
+            let read = file.read_at_aligned(0, 4096).await.unwrap();
+            assert!(read.iter().all(|x| *x == 42));
+            let read = buffered.read_at(0, 100).await.unwrap();
+            assert!(read.iter().all(|x| *x == 7));
+
+            file.close().await.unwrap();
+            buffered.close().await.unwrap();
+        });
+    }
+
+    #[test]
+    fn chain_failure_cancels_the_rest() {
+        test_executor!(async move {
+            let dir = make_tmp_test_directory("chain_failure_cancels_the_rest");
+            let path = dir.path.join("file");
+            BufferedFile::create(&path)
+                .await
+                .unwrap()
+                .close()
+                .await
+                .unwrap();
+            let rdonly = BufferedFile::open(&path).await.unwrap();
+            let file = OpenOptions::new()
+                .write(true)
+                .buffered_open(&path)
+                .await
+                .unwrap();
+
+            let results = IoChain::new()
+                .write_buffered_at(&rdonly, vec![1; 10], 0)
+                .write_buffered_at(&file, vec![2; 10], 0)
+                .submit()
+                .await;
+            assert_eq!(
+                results[0].as_ref().unwrap_err().raw_os_error(),
+                Some(libc::EBADF)
+            );
+            assert_eq!(
+                results[1].as_ref().unwrap_err().raw_os_error(),
+                Some(libc::ECANCELED)
+            );
+            assert_eq!(file.file_size().await.unwrap(), 0);
+
+            let results = IoChain::new()
+                .write_buffered_at(&rdonly, vec![1; 10], 0)
+                .write_buffered_at(&file, vec![2; 10], 0)
+                .with_timeout(Duration::from_secs(10))
+                .submit()
+                .await;
+            assert_eq!(
+                results[1].as_ref().unwrap_err().raw_os_error(),
+                Some(libc::ECANCELED)
+            );
+
+            let results = IoChain::new()
+                .with_hard_links(true)
+                .write_buffered_at(&rdonly, vec![1; 10], 0)
+                .write_buffered_at(&file, vec![2; 10], 0)
+                .submit()
+                .await;
+            assert!(results[0].is_err());
+            assert_eq!(*results[1].as_ref().unwrap(), 10);
+
+            rdonly.close().await.unwrap();
+            file.close().await.unwrap();
+        });
+    }
+
+    #[test]
+    fn chain_longer_than_the_ring() {
+        test_executor!(async move {
+            let dir = make_tmp_test_directory("chain_longer_than_the_ring");
+            let file = BufferedFile::create(dir.path.join("file")).await.unwrap();
+
+            let depth = crate::executor().reactor().ring_depth();
+            let mut chain = IoChain::new();
+            for _ in 0..depth {
+                chain = chain.fdatasync_buffered(&file);
+            }
+            let results = chain.submit().await;
+            assert!(results.iter().all(Result::is_ok));
+
+            // The timeout takes one more entry than the ring has
+            let mut chain = IoChain::new()
+                .fdatasync_buffered(&file)
+                .with_timeout(Duration::from_secs(1));
+            for _ in 1..depth {
+                chain = chain.fdatasync_buffered(&file);
+            }
+            let results = chain.submit().await;
+            assert_eq!(results.len(), depth);
+            for res in results {
+                assert_eq!(res.unwrap_err().raw_os_error(), Some(libc::EINVAL));
+            }
+
+            file.close().await.unwrap();
+        });
+    }
+}
@@ -4,18 +4,231 @@
 // This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2020 Datadog, Inc.
 //
 use crate::{
-    io::{dma_file::DmaFile, glommio_file::GlommioFile},
-    sys, GlommioError,
+    io::{dma_file::DmaFile, glommio_file::GlommioFile, OpenOptions, RenameFlags, Stat},
+    sys::{self, SourceType, Statx},
+    GlommioError, ReactorErrorKind,
 };
+use futures_lite::stream::{self, Stream};
 use std::{
     cell::Ref,
+    collections::VecDeque,
+    convert::TryInto,
+    ffi::{OsStr, OsString},
     io,
-    os::unix::io::{AsRawFd, FromRawFd, RawFd},
-    path::Path,
+    os::unix::{
+        ffi::OsStrExt,
+        io::{AsRawFd, FromRawFd, RawFd},
+    },
+    path::{Path, PathBuf},
 };
 
 type Result<T> = crate::Result<T, ()>;
 
+/// How many bytes worth of directory entries are fetched from the kernel at a
+/// time by [`Directory::read_dir`]
+const READ_DIR_CHUNK_SIZE: usize = 32 << 10;
+
+/// The type of a directory entry, as reported by the filesystem when listing
+/// a directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileType {
+    /// A regular file
+    File,
+    /// A directory
+    Dir,
+    /// A symbolic link
+    Symlink,
+    /// A block device
+    BlockDevice,
+    /// A character device
+    CharDevice,
+    /// A named pipe (FIFO)
+    Fifo,
+    /// A Unix domain socket
+    Socket,
+    /// The filesystem does not report entry types when listing directories.
+    /// The type has to be found through a separate `stat` call.
+    Unknown,
+}
+
+impl FileType {
+    fn from_dirent(d_type: u8) -> FileType {
+        match d_type {
+            libc::DT_REG => FileType::File,
+            libc::DT_DIR => FileType::Dir,
+            libc::DT_LNK => FileType::Symlink,
+            libc::DT_BLK => FileType::BlockDevice,
+            libc::DT_CHR => FileType::CharDevice,
+            libc::DT_FIFO => FileType::Fifo,
+            libc::DT_SOCK => FileType::Socket,
+            _ => FileType::Unknown,
+        }
+    }
+}
+
+/// An entry returned by [`Directory::read_dir`]
+#[derive(Debug, Clone)]
+pub struct DirEntry {
+    name: OsString,
+    file_type: FileType,
+    inode: u64,
+}
+
+impl DirEntry {
+    /// The name of this entry, relative to the directory it was listed from
+    pub fn name(&self) -> &OsStr {
+        &self.name
+    }
+
+    /// The type of this entry
+    pub fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
+    /// The inode number of this entry
+    pub fn inode(&self) -> u64 {
+        self.inode
+    }
+}
+
+/// An entry returned by [`Directory::walk`]
+#[derive(Debug, Clone)]
+pub struct WalkEntry {
+    path: PathBuf,
+    depth: usize,
+    entry: DirEntry,
+}
+
+impl WalkEntry {
+    /// The path of this entry, relative to the directory being walked
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The name of this entry, that is, the last component of its path
+    pub fn name(&self) -> &OsStr {
+        self.entry.name()
+    }
+
+    /// The type of this entry
+    pub fn file_type(&self) -> FileType {
+        self.entry.file_type()
+    }
+
+    /// The inode number of this entry
+    pub fn inode(&self) -> u64 {
+        self.entry.inode()
+    }
+
+    /// How deep in the tree this entry is. Entries directly under the
+    /// directory being walked have a depth of 1.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+// Layout of `struct linux_dirent64`, see getdents64(2)
+const DIRENT_INO: usize = 0;
+const DIRENT_RECLEN: usize = 16;
+const DIRENT_TYPE: usize = 18;
+const DIRENT_NAME: usize = 19;
+
+fn parse_dirents(buf: &[u8], entries: &mut VecDeque<DirEntry>) {
+    let mut pos = 0;
+    while pos + DIRENT_NAME < buf.len() {
+        let record = &buf[pos..];
+        let reclen =
+            u16::from_ne_bytes(record[DIRENT_RECLEN..DIRENT_RECLEN + 2].try_into().unwrap());
+        let name = &record[DIRENT_NAME..reclen as usize];
+        let name = &name[..name.iter().position(|x| *x == 0).unwrap_or(name.len())];
+        pos += reclen as usize;
+
+        if name == b"." || name == b".." {
+            continue;
+        }
+        entries.push_back(DirEntry {
+            name: OsStr::from_bytes(name).to_owned(),
+            file_type: FileType::from_dirent(record[DIRENT_TYPE]),
+            inode: u64::from_ne_bytes(record[DIRENT_INO..DIRENT_INO + 8].try_into().unwrap()),
+        });
+    }
+}
+
+/// An open handle to a directory being listed. It has its own file
+/// description (and therefore its own offset) so many listings of the same
+/// directory can proceed concurrently.
+#[derive(Debug)]
+struct DirReader {
+    file: Option<GlommioFile>,
+    entries: VecDeque<DirEntry>,
+}
+
+impl DirReader {
+    /// Opens `relative` under `dir` for listing. Symbolic links are not
+    /// followed. `path` is only used to give context to errors.
+    async fn open(dir: RawFd, relative: &Path, path: Option<PathBuf>) -> Result<DirReader> {
+        let flags = libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_RDONLY | libc::O_CLOEXEC;
+        let source = crate::executor().reactor().open_at(dir, relative, flags, 0);
+        let fd = source.collect_rw().await.map_err(|source| {
+            GlommioError::create_enhanced(source, "Opening directory", path.as_ref(), Some(dir))
+        })?;
+        let file = unsafe { GlommioFile::from_raw_fd(fd as _) }.with_path(path);
+        Ok(DirReader {
+            file: Some(file),
+            entries: VecDeque::new(),
+        })
+    }
+
+    /// The file descriptor of the directory, which is open until the reader
+    /// is exhausted.
+    fn raw_fd(&self) -> RawFd {
+        self.file
+            .as_ref()
+            .expect("directory reader was exhausted")
+            .as_raw_fd()
+    }
+
+    /// Returns the next entry, or `None` once the directory is exhausted. The
+    /// underlying file is closed as soon as the end of the directory is
+    /// reached.
+    async fn next_entry(&mut self) -> Option<Result<DirEntry>> {
+        while self.entries.is_empty() {
+            let file = self.file.as_ref()?;
+            let source = file
+                .reactor
+                .upgrade()
+                .unwrap()
+                .read_dir(file.as_raw_fd(), READ_DIR_CHUNK_SIZE)
+                .await;
+            let read = match enhanced_try!(source.collect_rw().await, "Reading a directory", file) {
+                Ok(read) => read,
+                Err(err) => {
+                    self.file = None;
+                    return Some(Err(err));
+                }
+            };
+
+            if read == 0 {
+                return match self.file.take().unwrap().close().await {
+                    Ok(_) => None,
+                    Err(err) => Some(Err(err)),
+                };
+            }
+
+            match source.extract_source_type() {
+                SourceType::ReadDir(buf) => parse_dirents(&buf, &mut self.entries),
+                src => {
+                    self.file = None;
+                    return Some(Err(GlommioError::ReactorError(
+                        ReactorErrorKind::IncorrectSourceType(format!("{src:?}")),
+                    )));
+                }
+            }
+        }
+        self.entries.pop_front().map(Ok)
+    }
+}
+
 #[derive(Debug)]
 /// A directory representation where asynchronous operations can be issued
 pub struct Directory {
@@ -72,8 +285,25 @@ impl Directory {
             .into());
         };
 
-        let path = self.file.path_required("open file")?.join(path.as_ref());
-        DmaFile::open(path).await
+        self.open_with(OpenOptions::new().read(true), path).await
+    }
+
+    /// Opens a file relative to this directory with the options specified by
+    /// `opts`, returns a DMA file
+    ///
+    /// The file is opened through `openat(2)` on the directory file
+    /// descriptor, so this is not affected by concurrent renames of this
+    /// directory or of its parents.
+    pub async fn open_with<P: AsRef<Path>>(&self, opts: &OpenOptions, path: P) -> Result<DmaFile> {
+        let path = path.as_ref();
+        let opdesc = if opts.create || opts.create_new {
+            "Creating"
+        } else {
+            "Opening"
+        };
+        let file = DmaFile::open_with_options(self.as_raw_fd(), path, opdesc, opts).await?;
+        file.set_path(self.file.path().map(|dir| dir.join(path)));
+        Ok(file)
     }
 
     /// Similar to create() in the standard library, but returns a DMA file
@@ -110,17 +340,307 @@ impl Directory {
             .into());
         }
 
-        let path = self.file.path_required("create file")?.join(path.as_ref());
-        DmaFile::create(path).await
+        self.open_with(
+            OpenOptions::new().write(true).create(true).truncate(true),
+            path,
+        )
+        .await
+    }
+
+    /// Creates a directory relative to this directory and returns it.
+    ///
+    /// Like [`Directory::create`], it is not an error if the directory
+    /// already exists.
+    pub async fn create_dir<P: AsRef<Path>>(&self, path: P) -> Result<Directory> {
+        let fd = self.as_raw_fd();
+        let path = path.as_ref();
+        let reactor = crate::executor().reactor();
+        let source = reactor.mkdir_at(fd, path, 0o777).await;
+        match source.collect_rw().await {
+            Ok(_) => {}
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(err) => {
+                return Err(GlommioError::create_enhanced(
+                    err,
+                    "Creating directory",
+                    Some(self.child(path)),
+                    Some(fd),
+                ));
+            }
+        }
+
+        let source = reactor.open_at(fd, path, libc::O_DIRECTORY | libc::O_CLOEXEC, 0);
+        let dir = source.collect_rw().await.map_err(|source| {
+            GlommioError::create_enhanced(
+                source,
+                "Opening directory",
+                Some(self.child(path)),
+                Some(fd),
+            )
+        })?;
+        let file = unsafe { GlommioFile::from_raw_fd(dir as _) }
+            .with_path(self.file.path().map(|dir| dir.join(path)));
+        Ok(Directory { file })
+    }
+
+    /// Removes a file relative to this directory
+    pub async fn remove_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let fd = self.as_raw_fd();
+        let source = crate::executor()
+            .reactor()
+            .unlink_at(fd, path.as_ref(), 0)
+            .await;
+        source.collect_rw().await.map_err(|source| {
+            GlommioError::create_enhanced(source, "Removing file", Some(self.child(path)), Some(fd))
+        })?;
+        Ok(())
+    }
+
+    /// Renames `from` to `to`, both relative to this directory, replacing
+    /// `to` if it already exists.
+    pub async fn rename_file<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> Result<()> {
+        self.rename_with_flags(from, to, RenameFlags::empty()).await
+    }
+
+    /// Returns the metadata of a file relative to this directory. Symbolic
+    /// links are followed.
+    pub async fn stat_at<P: AsRef<Path>>(&self, path: P) -> Result<Stat> {
+        let fd = self.as_raw_fd();
+        let source = crate::executor().reactor().statx(fd, path.as_ref());
+        source.collect_rw().await.map_err(|source| {
+            GlommioError::create_enhanced(
+                source,
+                "getting file metadata",
+                Some(self.child(path)),
+                Some(fd),
+            )
+        })?;
+        let stat: Statx = source.extract_source_type().try_into()?;
+        Ok(stat.into())
+    }
+
+    /// Renames `from` to `to`, both relative to this directory, with `flags`
+    /// controlling what happens if `to` already exists.
+    ///
+    /// See [`crate::io::rename_with_flags`]
+    pub async fn rename_with_flags<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        from: P,
+        to: Q,
+        flags: RenameFlags,
+    ) -> Result<()> {
+        let fd = self.as_raw_fd();
+        let source = crate::executor()
+            .reactor()
+            .rename_at(fd, from.as_ref(), fd, to.as_ref(), flags.bits())
+            .await;
+        source.collect_rw().await.map_err(|source| {
+            GlommioError::create_enhanced(source, "Renaming", Some(self.child(from)), Some(fd))
+        })?;
+        Ok(())
+    }
+
+    /// Creates a hard link `link` pointing to `original`, both relative to
+    /// this directory.
+    ///
+    /// See [`crate::io::hard_link`]
+    pub async fn hard_link<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        original: P,
+        link: Q,
+    ) -> Result<()> {
+        let fd = self.as_raw_fd();
+        let source = crate::executor()
+            .reactor()
+            .link_at(fd, original.as_ref(), fd, link.as_ref(), 0)
+            .await;
+        source.collect_rw().await.map_err(|source| {
+            GlommioError::create_enhanced(
+                source,
+                "Creating hard link",
+                Some(self.child(link)),
+                Some(fd),
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Creates a symbolic link `link`, relative to this directory, pointing to
+    /// `original`.
+    ///
+    /// `original` is stored as is. See [`crate::io::symlink`]
+    pub async fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        original: P,
+        link: Q,
+    ) -> Result<()> {
+        let fd = self.as_raw_fd();
+        let source = crate::executor()
+            .reactor()
+            .symlink_at(original.as_ref(), fd, link.as_ref())
+            .await;
+        source.collect_rw().await.map_err(|source| {
+            GlommioError::create_enhanced(
+                source,
+                "Creating symlink",
+                Some(self.child(link)),
+                Some(fd),
+            )
+        })?;
+        Ok(())
+    }
+
+    /// The path of `name` under this directory, for error reporting. Falls
+    /// back to `name` alone if the path of this directory is unknown.
+    fn child<P: AsRef<Path>>(&self, name: P) -> PathBuf {
+        match self.file.path() {
+            Some(path) => path.join(name),
+            None => name.as_ref().to_owned(),
+        }
     }
 
     /// Returns an iterator to the contents of this directory
+    ///
+    /// This blocks the executor thread while the directory is read. Prefer
+    /// [`Directory::read_dir`] for directories that may be large.
     pub fn sync_read_dir(&self) -> Result<std::fs::ReadDir> {
         let path = self.file.path_required("read directory")?;
         enhanced_try!(std::fs::read_dir(&*path), "Reading a directory", self.file)
             .map_err(Into::into)
     }
 
+    /// Returns a stream of the entries in this directory.
+    ///
+    /// The entries `.` and `..` are skipped. Entries are fetched from the
+    /// kernel in chunks through `getdents64(2)`, which runs on the blocking
+    /// thread pool so the executor keeps serving other tasks while very large
+    /// directories are enumerated.
+    ///
+    /// The order in which entries are returned is filesystem specific.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use futures_lite::stream::StreamExt;
+    /// use glommio::{io::Directory, LocalExecutor};
+    ///
+    /// let ex = LocalExecutor::default();
+    /// ex.run(async {
+    ///     let dir = Directory::open("/tmp").await.unwrap();
+    ///     let mut entries = dir.read_dir();
+    ///     while let Some(entry) = entries.next().await {
+    ///         let entry = entry.unwrap();
+    ///         println!("{:?} ({:?})", entry.name(), entry.file_type());
+    ///     }
+    /// });
+    /// ```
+    pub fn read_dir(&self) -> impl Stream<Item = Result<DirEntry>> + Unpin + '_ {
+        enum State<'a> {
+            Start(&'a Directory),
+            Reading(DirReader),
+            Done,
+        }
+
+        Box::pin(stream::unfold(State::Start(self), |state| async move {
+            let mut reader = match state {
+                State::Start(dir) => {
+                    let path = dir.file.path.borrow().clone();
+                    match DirReader::open(dir.as_raw_fd(), Path::new("."), path).await {
+                        Ok(reader) => reader,
+                        Err(err) => return Some((Err(err), State::Done)),
+                    }
+                }
+                State::Reading(reader) => reader,
+                State::Done => return None,
+            };
+            match reader.next_entry().await {
+                Some(Ok(entry)) => Some((Ok(entry), State::Reading(reader))),
+                Some(Err(err)) => Some((Err(err), State::Done)),
+                None => None,
+            }
+        }))
+    }
+
+    /// Returns a stream that recursively walks the tree under this directory.
+    ///
+    /// Entries are returned in pre-order: a directory is always returned
+    /// before its contents. Their paths are relative to this directory.
+    /// Symbolic links are returned, but never followed.
+    ///
+    /// If a subdirectory can't be listed, an error carrying its path is
+    /// returned in place of its entry and the walk carries on with the
+    /// remaining entries.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use futures_lite::stream::StreamExt;
+    /// use glommio::{io::Directory, LocalExecutor};
+    ///
+    /// let ex = LocalExecutor::default();
+    /// ex.run(async {
+    ///     let dir = Directory::open("/tmp").await.unwrap();
+    ///     let mut entries = dir.walk();
+    ///     while let Some(entry) = entries.next().await {
+    ///         let entry = entry.unwrap();
+    ///         println!("{:?} ({:?})", entry.path(), entry.file_type());
+    ///     }
+    /// });
+    /// ```
+    pub fn walk(&self) -> impl Stream<Item = Result<WalkEntry>> + Unpin + '_ {
+        enum State<'a> {
+            Start(&'a Directory),
+            Walking(&'a Directory, Vec<(DirReader, PathBuf)>),
+            Done,
+        }
+
+        Box::pin(stream::unfold(State::Start(self), |state| async move {
+            let (dir, mut stack) = match state {
+                State::Start(dir) => {
+                    let path = dir.file.path.borrow().clone();
+                    match DirReader::open(dir.as_raw_fd(), Path::new("."), path).await {
+                        Ok(reader) => (dir, vec![(reader, PathBuf::new())]),
+                        Err(err) => return Some((Err(err), State::Done)),
+                    }
+                }
+                State::Walking(dir, stack) => (dir, stack),
+                State::Done => return None,
+            };
+
+            loop {
+                let depth = stack.len();
+                let (reader, prefix) = stack.last_mut()?;
+                let mut entry = match reader.next_entry().await {
+                    Some(Ok(entry)) => WalkEntry {
+                        path: prefix.join(&entry.name),
+                        depth,
+                        entry,
+                    },
+                    Some(Err(err)) => return Some((Err(err), State::Walking(dir, stack))),
+                    None => {
+                        stack.pop();
+                        continue;
+                    }
+                };
+
+                // Filesystems that don't report entry types force us to try
+                // opening every entry to find out whether it is a directory
+                if matches!(entry.file_type(), FileType::Dir | FileType::Unknown) {
+                    let path = dir.file.path().map(|p| p.join(&entry.path));
+                    match DirReader::open(dir.as_raw_fd(), &entry.path, path).await {
+                        Ok(reader) => {
+                            entry.entry.file_type = FileType::Dir;
+                            stack.push((reader, entry.path.clone()));
+                        }
+                        Err(err) if entry.file_type() == FileType::Unknown && is_not_dir(&err) => {}
+                        Err(err) => return Some((Err(err), State::Walking(dir, stack))),
+                    }
+                }
+                return Some((Ok(entry), State::Walking(dir, stack)));
+            }
+        }))
+    }
+
     /// Issues fdatasync into the underlying file.
     pub async fn sync(&self) -> Result<()> {
         let source = self
@@ -145,6 +665,100 @@ impl Directory {
     }
 }
 
+/// Removes the directory at `path` and everything under it. If `path` is a
+/// symbolic link, the link itself is removed.
+///
+/// Every directory of the tree is opened relative to its parent without
+/// following symbolic links, and entries are removed relative to the
+/// directory they are in. So replacing a directory of the tree with a link
+/// while it is being removed can't make this remove anything out of the tree.
+pub(super) async fn remove_dir_all(path: &Path) -> Result<()> {
+    let reactor = crate::executor().reactor();
+    let root = match DirReader::open(libc::AT_FDCWD, path, Some(path.to_owned())).await {
+        Ok(root) => root,
+        Err(err) if is_not_dir(&err) => {
+            let p = path.to_owned();
+            let is_symlink = crate::executor()
+                .spawn_blocking(move || std::fs::symlink_metadata(p))
+                .await
+                .map_or(false, |m| m.file_type().is_symlink());
+            if !is_symlink {
+                return Err(err);
+            }
+            let source = reactor.remove_file(path).await;
+            return source.collect_rw().await.map(|_| ()).map_err(|source| {
+                GlommioError::create_enhanced(source, "Removing symlink", Some(path), None)
+            });
+        }
+        Err(err) => return Err(err),
+    };
+
+    // Depth-first, so each directory is empty by the time its reader is done.
+    // A reader keeps its directory open until it is exhausted, so the
+    // directory at the top of the stack is always open, and so is its parent.
+    let mut stack = vec![(root, path.to_owned())];
+    while let Some((reader, dir)) = stack.last_mut() {
+        let entry = match reader.next_entry().await {
+            Some(entry) => entry?,
+            None => {
+                let (_, dir) = stack.pop().unwrap();
+                let source = match stack.last() {
+                    Some((parent, _)) => {
+                        let name = Path::new(dir.file_name().unwrap());
+                        reactor
+                            .unlink_at(parent.raw_fd(), name, libc::AT_REMOVEDIR)
+                            .await
+                    }
+                    None => reactor.remove_dir(&dir).await,
+                };
+                source.collect_rw().await.map_err(|source| {
+                    GlommioError::create_enhanced(source, "Removing directory", Some(&dir), None)
+                })?;
+                continue;
+            }
+        };
+
+        let fd = reader.raw_fd();
+        let child = dir.join(&entry.name);
+        let name = Path::new(&entry.name);
+        if entry.file_type != FileType::Dir {
+            let source = reactor.unlink_at(fd, name, 0).await;
+            match source.collect_rw().await {
+                Ok(_) => continue,
+                Err(err)
+                    if entry.file_type == FileType::Unknown
+                        && err.raw_os_error() == Some(libc::EISDIR) => {}
+                Err(err) => {
+                    return Err(GlommioError::create_enhanced(
+                        err,
+                        "Removing file",
+                        Some(&child),
+                        Some(fd),
+                    ));
+                }
+            }
+        }
+        match DirReader::open(fd, name, Some(child.clone())).await {
+            Ok(reader) => stack.push((reader, child)),
+            // Not a directory anymore, so it can be removed like a file
+            Err(err) if is_not_dir(&err) => {
+                let source = reactor.unlink_at(fd, name, 0).await;
+                source.collect_rw().await.map_err(|source| {
+                    GlommioError::create_enhanced(source, "Removing file", Some(&child), Some(fd))
+                })?;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+/// Whether opening a directory failed because the path is not one, including
+/// when it is a symbolic link that wasn't followed.
+fn is_not_dir(err: &GlommioError<()>) -> bool {
+    matches!(err.raw_os_error(), Some(libc::ENOTDIR) | Some(libc::ELOOP))
+}
+
 fn contains_dir(path: &Path) -> bool {
     let mut iter = path.components();
     match iter.next() {
@@ -152,3 +766,173 @@ fn contains_dir(path: &Path) -> bool {
         _ => true,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::make_tmp_test_directory;
+    use futures_lite::StreamExt;
+    use std::collections::HashMap;
+
+    #[test]
+    fn read_dir_lists_entries() {
+        let dir = make_tmp_test_directory("read_dir_lists_entries");
+        let path = dir.path.clone();
+        std::fs::create_dir(path.join("subdir")).unwrap();
+        for i in 0..2000 {
+            std::fs::File::create(path.join(format!("file-{i}"))).unwrap();
+        }
+
+        test_executor!(async move {
+            let dir = Directory::open(&path).await.unwrap();
+            let entries: HashMap<OsString, DirEntry> = dir
+                .read_dir()
+                .map(|x| x.unwrap())
+                .map(|x| (x.name().to_owned(), x))
+                .collect()
+                .await;
+            assert_eq!(entries.len(), 2001);
+            let subdir = &entries[OsStr::new("subdir")];
+            assert!(matches!(
+                subdir.file_type(),
+                FileType::Dir | FileType::Unknown
+            ));
+            let file = &entries[OsStr::new("file-1999")];
+            assert!(matches!(
+                file.file_type(),
+                FileType::File | FileType::Unknown
+            ));
+            assert_eq!(
+                file.inode(),
+                std::os::unix::fs::MetadataExt::ino(
+                    &std::fs::metadata(path.join("file-1999")).unwrap()
+                )
+            );
+            dir.close().await.unwrap();
+        });
+    }
+
+    #[test]
+    fn read_dir_concurrent_listings() {
+        let dir = make_tmp_test_directory("read_dir_concurrent_listings");
+        let path = dir.path.clone();
+        for i in 0..100 {
+            std::fs::File::create(path.join(format!("file-{i}"))).unwrap();
+        }
+
+        test_executor!(async move {
+            let dir = Directory::open(&path).await.unwrap();
+            let mut first = dir.read_dir();
+            let mut second = dir.read_dir();
+            let mut count = 0;
+            while let (Some(a), Some(b)) = (first.next().await, second.next().await) {
+                assert_eq!(a.unwrap().name(), b.unwrap().name());
+                count += 1;
+            }
+            assert_eq!(count, 100);
+        });
+    }
+
+    #[test]
+    fn dir_relative_links() {
+        let dir = make_tmp_test_directory("dir_relative_links");
+        let path = dir.path.clone();
+        std::fs::write(path.join("segment"), b"data").unwrap();
+
+        test_executor!(async move {
+            let dir = Directory::open(&path).await.unwrap();
+            dir.hard_link("segment", "snapshot").await.unwrap();
+            dir.symlink("segment", "current").await.unwrap();
+            assert_eq!(std::fs::read(path.join("current")).unwrap(), b"data");
+
+            dir.rename_with_flags("snapshot", "renamed", RenameFlags::NOREPLACE)
+                .await
+                .unwrap();
+            assert_eq!(std::fs::read(path.join("renamed")).unwrap(), b"data");
+
+            match dir
+                .rename_with_flags("renamed", "segment", RenameFlags::NOREPLACE)
+                .await
+                .unwrap_err()
+            {
+                GlommioError::EnhancedIoError {
+                    source, path: p, ..
+                } => {
+                    assert_eq!(source.raw_os_error(), Some(libc::EEXIST));
+                    assert_eq!(p, Some(path.join("renamed")));
+                }
+                err => panic!("unexpected error {err:?}"),
+            }
+            dir.close().await.unwrap();
+        });
+    }
+
+    #[test]
+    fn dir_relative_file_ops() {
+        let dir = make_tmp_test_directory("dir_relative_file_ops");
+        let path = dir.path.clone();
+        std::fs::create_dir(path.join("shard")).unwrap();
+
+        test_executor!(async move {
+            let shard = Directory::open(path.join("shard")).await.unwrap();
+            // Operations go through the directory fd, so they keep working
+            // after the directory is moved elsewhere
+            std::fs::rename(path.join("shard"), path.join("moved")).unwrap();
+
+            let sub = shard.create_dir("sub").await.unwrap();
+            assert!(path.join("moved/sub").is_dir());
+            shard.create_dir("sub").await.unwrap();
+            sub.close().await.unwrap();
+
+            let file = shard
+                .open_with(OpenOptions::new().write(true).create_new(true), "data")
+                .await
+                .unwrap();
+            file.close().await.unwrap();
+            assert!(shard
+                .open_with(OpenOptions::new().write(true).create_new(true), "data")
+                .await
+                .is_err());
+            std::fs::write(path.join("moved/data"), [0u8; 100]).unwrap();
+            assert_eq!(shard.stat_at("data").await.unwrap().file_size, 100);
+
+            shard.rename_file("data", "renamed").await.unwrap();
+            assert!(path.join("moved/renamed").exists());
+            shard.remove_file("renamed").await.unwrap();
+            assert!(!path.join("moved/renamed").exists());
+
+            let err = shard.stat_at("renamed").await.unwrap_err();
+            assert_eq!(err.raw_os_error(), Some(libc::ENOENT));
+            shard.close().await.unwrap();
+        });
+    }
+
+    #[test]
+    fn walk_tree() {
+        let dir = make_tmp_test_directory("walk_tree");
+        let path = dir.path.clone();
+        std::fs::create_dir_all(path.join("a/b")).unwrap();
+        std::fs::create_dir(path.join("c")).unwrap();
+        std::fs::File::create(path.join("a/b/file")).unwrap();
+        std::fs::File::create(path.join("c/file")).unwrap();
+        std::os::unix::fs::symlink(&path, path.join("c/link")).unwrap();
+
+        test_executor!(async move {
+            let dir = Directory::open(&path).await.unwrap();
+            let entries: Vec<WalkEntry> = dir.walk().map(|x| x.unwrap()).collect().await;
+            let paths: Vec<&Path> = entries.iter().map(|x| x.path()).collect();
+            assert_eq!(entries.len(), 6);
+
+            let pos = |p: &str| paths.iter().position(|x| *x == Path::new(p)).unwrap();
+            assert!(pos("a") < pos("a/b"));
+            assert!(pos("a/b") < pos("a/b/file"));
+            assert!(pos("c") < pos("c/link"));
+
+            let file = &entries[pos("a/b/file")];
+            assert_eq!(file.depth(), 3);
+            assert_eq!(file.name(), OsStr::new("file"));
+            assert_eq!(entries[pos("a/b")].file_type(), FileType::Dir);
+            dir.close().await.unwrap();
+        });
+    }
+}
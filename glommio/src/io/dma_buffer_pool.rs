@@ -0,0 +1,251 @@
+// Unless explicitly stated otherwise all files in this repository are licensed
+// under the MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021 Datadog, Inc.
+//
+use crate::{
+    io::dma_file::align_up,
+    reactor::Reactor,
+    sys::{DmaBuffer, UringBufferAllocator},
+};
+use std::{
+    cell::RefCell,
+    ptr::NonNull,
+    rc::{Rc, Weak},
+};
+
+type Result<T> = crate::Result<T, ()>;
+
+/// Memory registered with the rings of an executor, from which [`DmaBuffer`]s
+/// can be allocated.
+///
+/// The kernel doesn't have to map the memory of a buffer that comes from
+/// registered memory on every request, so I/O using them is cheaper. Buffers
+/// allocated by [`DmaFile::alloc_dma_buffer`] come from a registered arena
+/// whose size is set by [`LocalExecutorBuilder::io_memory`], but they are
+/// silently allocated from the heap once that arena is exhausted (see
+/// [`IoStats::dma_buffer_fallbacks`]). A `DmaBufferPool` is registered memory
+/// reserved for the buffers allocated from it instead: [`alloc`] fails rather
+/// than falling back, which guarantees fixed-buffer I/O to the code using it.
+///
+/// Pools are usually sized for, and dedicated to, the hot path of a single
+/// task queue, so that other work can't exhaust them. A pool can be grown
+/// with more memory, either allocated by the pool or provided by the user,
+/// like hugepages. Each time memory is added, it takes one of the 64 entries
+/// of the buffer tables of the rings, shared by all the pools of the executor
+/// and its default arena, so pools should be grown in large steps. The entry
+/// is given back once the pool and all of the buffers allocated from it are
+/// dropped.
+///
+/// Registering memory while the executor runs needs Linux 5.13 or newer.
+/// Older kernels can't do it without waiting for the rings to be idle, so
+/// creating or growing a pool fails with `EOPNOTSUPP` on them.
+///
+/// A pool can only be created from within an executor, and its buffers can
+/// only be used on that executor. The memory of a pool is released once the
+/// pool and all the buffers allocated from it are dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use glommio::{
+///     io::{DmaBufferPool, DmaFile},
+///     LocalExecutor,
+/// };
+///
+/// let ex = LocalExecutor::default();
+/// ex.run(async {
+///     let pool = DmaBufferPool::new(4 << 20).unwrap();
+///     let file = DmaFile::create("myfile").await.unwrap();
+///
+///     let mut buf = pool.alloc(4096).expect("pool exhausted");
+///     buf.as_bytes_mut().copy_from_slice(&[42; 4096]);
+///     file.write_at(buf, 0).await.unwrap();
+///     file.close().await.unwrap();
+/// });
+/// ```
+///
+/// [`DmaFile::alloc_dma_buffer`]: crate::io::DmaFile::alloc_dma_buffer
+/// [`LocalExecutorBuilder::io_memory`]: crate::LocalExecutorBuilder::io_memory
+/// [`IoStats::dma_buffer_fallbacks`]: crate::IoStats::dma_buffer_fallbacks
+/// [`alloc`]: DmaBufferPool::alloc
+#[derive(Debug)]
+pub struct DmaBufferPool {
+    arenas: RefCell<Vec<Rc<UringBufferAllocator>>>,
+    reactor: Weak<Reactor>,
+}
+
+impl DmaBufferPool {
+    /// Creates a pool of `size` bytes of registered memory, rounded up to
+    /// the page size.
+    ///
+    /// Fails if the memory can't be registered, for instance because it
+    /// exceeds the limit of locked memory of the process (`RLIMIT_MEMLOCK`),
+    /// or because the kernel is older than 5.13.
+    pub fn new(size: usize) -> Result<DmaBufferPool> {
+        let pool = Self::empty();
+        pool.grow(size)?;
+        Ok(pool)
+    }
+
+    /// Creates a pool from memory provided by the user, like a mapping of
+    /// hugepages. `memory` is dropped once the pool and all of the buffers
+    /// allocated from it are.
+    ///
+    /// The memory must be aligned to, and its size a multiple of, 4096 bytes.
+    ///
+    /// # Safety
+    ///
+    /// The bytes `memory` refers to must not move when `memory` does, which
+    /// is the case of heap allocations and memory mappings, and must not be
+    /// accessed through anything but the pool while it's alive.
+    pub unsafe fn from_memory<M: AsMut<[u8]> + 'static>(memory: M) -> Result<DmaBufferPool> {
+        let pool = Self::empty();
+        pool.grow_with_memory(memory)?;
+        Ok(pool)
+    }
+
+    fn empty() -> DmaBufferPool {
+        DmaBufferPool {
+            arenas: RefCell::new(Vec::new()),
+            reactor: Rc::downgrade(&crate::executor().reactor()),
+        }
+    }
+
+    /// Adds `size` bytes of registered memory to the pool, rounded up to the
+    /// page size.
+    pub fn grow(&self, size: usize) -> Result<()> {
+        let size = align_up(std::cmp::max(size, 1) as u64, 4096);
+        self.add_arena(UringBufferAllocator::new(size as usize))
+    }
+
+    /// Adds memory provided by the user to the pool, with the same
+    /// requirements as [`from_memory`].
+    ///
+    /// # Safety
+    ///
+    /// See [`from_memory`].
+    ///
+    /// [`from_memory`]: DmaBufferPool::from_memory
+    pub unsafe fn grow_with_memory<M: AsMut<[u8]> + 'static>(&self, mut memory: M) -> Result<()> {
+        let bytes = memory.as_mut();
+        let size = bytes.len();
+        let data = NonNull::new(bytes.as_mut_ptr()).unwrap();
+        self.add_arena(UringBufferAllocator::from_memory(
+            data,
+            size,
+            Box::new(memory),
+        )?)
+    }
+
+    fn add_arena(&self, arena: UringBufferAllocator) -> Result<()> {
+        let arena = Rc::new(arena);
+        self.reactor
+            .upgrade()
+            .unwrap()
+            .register_buffer_arena(&arena)?;
+        self.arenas.borrow_mut().push(arena);
+        Ok(())
+    }
+
+    /// Allocates a buffer of `size` bytes from the pool, or returns `None` if
+    /// there isn't enough free memory left in it.
+    ///
+    /// Buffers are carved out of the pool with a buddy allocator, so each
+    /// buffer takes the next power of two of its size, and at least 4096
+    /// bytes.
+    pub fn alloc(&self, size: usize) -> Option<DmaBuffer> {
+        self.arenas
+            .borrow()
+            .iter()
+            .find_map(|arena| arena.try_new_buffer(size))
+    }
+
+    /// The total amount of memory in the pool, in bytes.
+    ///
+    /// Part of it is used by the allocator for its own bookkeeping, so the
+    /// buffers allocated from the pool can't add up to all of it.
+    pub fn capacity(&self) -> usize {
+        self.arenas.borrow().iter().map(|x| x.size()).sum()
+    }
+
+    /// The amount of memory in the buffers currently allocated from the pool,
+    /// in bytes.
+    pub fn in_use(&self) -> usize {
+        self.arenas.borrow().iter().map(|x| x.in_use()).sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::io::DmaFile;
+
+    #[test]
+    fn pool_allocations() {
+        test_executor!(async move {
+            let pool = DmaBufferPool::new(1 << 16).unwrap();
+            assert_eq!(pool.capacity(), 1 << 16);
+
+            let mut bufs = Vec::new();
+            while let Some(buf) = pool.alloc(4096) {
+                assert!(buf.uring_buffer_id().is_some());
+                bufs.push(buf);
+            }
+            assert!(!bufs.is_empty());
+            assert_eq!(pool.in_use(), bufs.len() * 4096);
+
+            pool.grow(1 << 16).unwrap();
+            let buf = pool.alloc(4096).unwrap();
+            assert!(buf.uring_buffer_id().is_some());
+            drop(bufs);
+            assert_eq!(pool.in_use(), 4096);
+        });
+    }
+
+    #[test]
+    fn dropped_pools_release_their_slot() {
+        test_executor!(async move {
+            // more pools than the buffer tables of the rings have entries
+            for _ in 0..200 {
+                let pool = DmaBufferPool::new(4096).unwrap();
+                let buf = pool.alloc(4096).unwrap();
+                assert!(buf.uring_buffer_id().is_some());
+            }
+        });
+    }
+
+    #[test]
+    fn pool_buffer_io() {
+        test_executor!(async move {
+            let dir = crate::test_utils::make_tmp_test_directory("pool_buffer_io");
+            let file = DmaFile::create(dir.path.join("testfile")).await.unwrap();
+            let pool = DmaBufferPool::new(1 << 16).unwrap();
+
+            let mut buf = pool.alloc(4096).unwrap();
+            buf.as_bytes_mut().copy_from_slice(&[7; 4096]);
+            assert_eq!(file.write_at(buf, 0).await.unwrap(), 4096);
+
+            let bufs = vec![pool.alloc(4096).unwrap()];
+            let (read, bufs) = file.read_vectored_at(bufs, 0).await.unwrap();
+            assert_eq!(read, 4096);
+            assert!(bufs[0].as_bytes().iter().all(|x| *x == 7));
+            file.close().await.unwrap();
+        });
+    }
+
+    #[test]
+    fn fallback_allocations_are_reported() {
+        test_executor!(async move {
+            let _ = crate::executor().io_stats();
+            let dir = crate::test_utils::make_tmp_test_directory("fallback_allocations");
+            let file = DmaFile::create(dir.path.join("testfile")).await.unwrap();
+            let buf = file.alloc_dma_buffer(32 << 20);
+            assert!(buf.uring_buffer_id().is_none());
+
+            let stats = crate::executor().io_stats();
+            assert_eq!(stats.dma_buffer_fallbacks(), (1, 32 << 20));
+            file.close().await.unwrap();
+        });
+    }
+}
@@ -9,12 +9,12 @@ use crate::{
             CoalescedReads, IoVec, MergedBufferLimit, OrderedBulkIo, ReadAmplificationLimit,
             ReadManyArgs, ReadManyResult,
         },
-        glommio_file::GlommioFile,
+        glommio_file::{Advice, GlommioFile, SyncRangeFlags},
         open_options::OpenOptions,
         read_result::ReadResult,
         ScheduledSource,
     },
-    sys::{self, sysfs, DirectIo, DmaBuffer, DmaSource, PollableStatus},
+    sys::{self, sysfs, DirectIo, DmaBuffer, DmaSource, IoBuffer, PollableStatus},
 };
 use futures_lite::{Stream, StreamExt};
 use nix::sys::statfs::*;
@@ -22,7 +22,7 @@ use std::{
     cell::Ref,
     io,
     os::unix::io::{AsRawFd, RawFd},
-    path::Path,
+    path::{Path, PathBuf},
     rc::Rc,
 };
 
@@ -61,11 +61,11 @@ pub(crate) fn align_down(v: u64, align: u64) -> u64 {
 /// See the module-level [documentation](index.html) for more details and
 /// examples.
 pub struct DmaFile {
-    file: GlommioFile,
+    pub(super) file: GlommioFile,
     o_direct_alignment: u64,
     max_sectors_size: usize,
     max_segment_size: usize,
-    pollable: PollableStatus,
+    pub(super) pollable: PollableStatus,
 }
 
 impl DmaFile {
@@ -121,6 +121,38 @@ impl DmaFile {
         self.file.is_same(&other.file)
     }
 
+    /// Registers the file in the executor's table of fixed files.
+    ///
+    /// Requests issued on a registered file skip the lookup and reference
+    /// counting of its file descriptor that the kernel does otherwise, which
+    /// is measurable for small, frequent reads and writes. From this call on,
+    /// reads, writes and syncs on the file, including those issued by streams
+    /// created from it, use the registered handle. The registration is
+    /// released when the file is closed.
+    ///
+    /// The table is sized after the open files limit of the process and
+    /// shared by all registered files and sockets of the executor. Registering
+    /// a file that is already registered does nothing.
+    /// If the kernel can't set up the table, this does nothing and requests
+    /// keep using the file descriptor.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use glommio::{io::DmaFile, LocalExecutor};
+    ///
+    /// let ex = LocalExecutor::default();
+    /// ex.run(async {
+    ///     let file = DmaFile::open("myfile.txt").await.unwrap();
+    ///     file.register().unwrap();
+    ///     let buf = file.read_at(0, 4096).await.unwrap();
+    ///     file.close().await.unwrap();
+    /// });
+    /// ```
+    pub fn register(&self) -> Result<()> {
+        self.file.register()
+    }
+
     async fn open_at(
         dir: RawFd,
         path: &Path,
@@ -129,7 +161,7 @@ impl DmaFile {
     ) -> io::Result<DmaFile> {
         let file = GlommioFile::open_at(dir, path, flags, mode).await?;
         let (major, minor) = (file.dev_major as usize, file.dev_minor as usize);
-        let buf = statfs(path).unwrap();
+        let buf = fstatfs(&file.as_raw_fd())?;
         let fstype = buf.filesystem_type();
         let max_sectors_size = sysfs::BlockDevice::max_sectors_size(major, minor);
         let max_segment_size = sysfs::BlockDevice::max_segment_size(major, minor);
@@ -181,6 +213,12 @@ impl DmaFile {
         self.file.attach_scheduler()
     }
 
+    /// Files opened relative to a directory only know their path relative to
+    /// it. This lets the directory record the full path instead.
+    pub(super) fn set_path(&self, path: Option<PathBuf>) {
+        self.file.path.replace(path);
+    }
+
     /// Allocates a buffer that is suitable for using to write to this file.
     pub fn alloc_dma_buffer(&self, size: usize) -> DmaBuffer {
         self.file.reactor.upgrade().unwrap().alloc_dma_buffer(size)
@@ -252,6 +290,83 @@ impl DmaFile {
         enhanced_try!(source.collect_rw().await, "Writing", self.file).map_err(Into::into)
     }
 
+    /// Writes the buffers in `bufs` back to back to this file, starting at
+    /// `pos`, with a single I/O request.
+    ///
+    /// This is the way to flush buffers that are scattered in memory but
+    /// destined to a contiguous range of the file without paying for a
+    /// request per buffer. Like with [`DmaFile::write_at`], the position and
+    /// the size of every buffer must be properly aligned for Direct I/O, and
+    /// it is legal to write fewer bytes than the buffers hold in total.
+    ///
+    /// At most `IOV_MAX` (usually 1024) buffers can be written at once.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use glommio::{io::DmaFile, LocalExecutor};
+    ///
+    /// let ex = LocalExecutor::default();
+    /// ex.run(async {
+    ///     let file = DmaFile::create("test.txt").await.unwrap();
+    ///
+    ///     let bufs = (0..4u8)
+    ///         .map(|x| {
+    ///             let mut buf = file.alloc_dma_buffer(4096);
+    ///             buf.memset(x);
+    ///             buf
+    ///         })
+    ///         .collect();
+    ///     file.write_many_at(bufs, 0).await.unwrap();
+    ///     file.close().await.unwrap();
+    /// });
+    /// ```
+    pub async fn write_many_at(&self, bufs: Vec<DmaBuffer>, pos: u64) -> Result<usize> {
+        let bufs = bufs
+            .into_iter()
+            .map(|buf| IoBuffer::DmaSource(DmaSource::Owned(buf)))
+            .collect();
+        let source = self.file.reactor.upgrade().unwrap().write_vectored(
+            self.as_raw_fd(),
+            bufs,
+            pos,
+            self.pollable,
+        );
+        enhanced_try!(source.collect_rw().await, "Writing", self.file).map_err(Into::into)
+    }
+
+    /// Reads the data starting at `pos` into the buffers in `bufs`, filling
+    /// them one after the other with a single I/O request, and hands them
+    /// back along with the number of bytes read.
+    ///
+    /// The position and the size of every buffer must be properly aligned
+    /// for Direct I/O. The read stops short at the end of the file, so the
+    /// buffers past the number of bytes read keep their previous contents.
+    ///
+    /// At most `IOV_MAX` (usually 1024) buffers can be read into at once.
+    pub async fn read_vectored_at(
+        &self,
+        bufs: Vec<DmaBuffer>,
+        pos: u64,
+    ) -> Result<(usize, Vec<DmaBuffer>)> {
+        let bufs = bufs.into_iter().map(IoBuffer::DmaSink).collect();
+        let source = self.file.reactor.upgrade().unwrap().read_vectored(
+            self.as_raw_fd(),
+            bufs,
+            pos,
+            self.pollable,
+        );
+        let read_size = enhanced_try!(source.collect_rw().await, "Reading", self.file)?;
+        let bufs = source
+            .extract_buffers()
+            .into_iter()
+            .map(|buf| match buf {
+                IoBuffer::DmaSink(buf) => buf,
+                _ => unreachable!(),
+            })
+            .collect();
+        Ok((read_size, bufs))
+    }
+
     /// Equivalent to [`DmaFile::write_at`] except that the caller retains
     /// non-mutable ownership of the underlying buffer. This can be useful if
     /// you want to asynchronously process a page concurrently with writing it.
@@ -432,6 +547,43 @@ impl DmaFile {
         self.file.fdatasync().await.map_err(Into::into)
     }
 
+    /// Issues `fsync` for the underlying file. Unlike [`fdatasync`], this
+    /// also flushes all metadata changes, like timestamps, that aren't needed
+    /// to read the data back.
+    ///
+    /// [`fdatasync`]: DmaFile::fdatasync
+    pub async fn fsync(&self) -> Result<()> {
+        self.file.fsync().await
+    }
+
+    /// Issues `sync_file_range` for `len` bytes of the file starting at
+    /// `offset`. A `len` of zero extends the range to the end of the file.
+    ///
+    /// This can start the writeback of a range early, with
+    /// [`SyncRangeFlags::WRITE`], and wait for it later, with
+    /// [`SyncRangeFlags::WAIT_AFTER`]. It doesn't sync metadata nor the
+    /// caches of the drive, so it provides no durability guarantees on its
+    /// own: [`fdatasync`] still has to be issued for that, but has less left
+    /// to do.
+    ///
+    /// [`fdatasync`]: DmaFile::fdatasync
+    pub async fn sync_range(&self, offset: u64, len: u64, flags: SyncRangeFlags) -> Result<()> {
+        self.file.sync_range(offset, len, flags).await
+    }
+
+    /// Tells the OS how `len` bytes of the file starting at `offset` are going
+    /// to be accessed. A `len` of zero extends the range to the end of the
+    /// file.
+    ///
+    /// This is only a hint: it never changes the contents of the file, and
+    /// the OS is free to ignore it.
+    ///
+    /// As a DMA file bypasses the page cache, the hints have little effect on
+    /// its own reads, but they do apply to buffered access to the same file.
+    pub async fn advise(&self, offset: u64, len: u64, advice: Advice) -> Result<()> {
+        self.file.advise(offset, len, advice).await
+    }
+
     /// Erases a range from the file without changing the size. Check the man
     /// page for [`fallocate`] for a list of the supported filesystems.
     /// Partial blocks are zeroed while whole blocks are simply unmapped
@@ -678,6 +830,59 @@ pub(crate) mod test {
         assert_eq!(read[8191], 0);
     });
 
+    dma_file_test!(file_vectored_io, path, _k, {
+        let new_file = DmaFile::create(path.join("testfile"))
+            .await
+            .expect("failed to create file");
+        let bufs = (0..4u8)
+            .map(|x| {
+                let mut buf = new_file.alloc_dma_buffer(4096);
+                buf.memset(x);
+                buf
+            })
+            .collect();
+        let written = new_file.write_many_at(bufs, 4096).await.unwrap();
+        assert_eq!(written, 4 * 4096);
+        assert_eq!(new_file.file_size().await.unwrap(), 5 * 4096);
+
+        let bufs = vec![
+            new_file.alloc_dma_buffer(8192),
+            new_file.alloc_dma_buffer(4096),
+            new_file.alloc_dma_buffer(8192),
+        ];
+        let (read, bufs) = new_file.read_vectored_at(bufs, 4096).await.unwrap();
+        assert_eq!(read, 4 * 4096);
+        assert!(bufs[0].as_bytes()[..4096].iter().all(|x| *x == 0));
+        assert!(bufs[0].as_bytes()[4096..].iter().all(|x| *x == 1));
+        assert!(bufs[1].as_bytes().iter().all(|x| *x == 2));
+        assert!(bufs[2].as_bytes()[..4096].iter().all(|x| *x == 3));
+        new_file.close().await.unwrap();
+    });
+
+    dma_file_test!(file_sync_variants, path, _k, {
+        let new_file = DmaFile::create(path.join("testfile"))
+            .await
+            .expect("failed to create file");
+        let mut buf = new_file.alloc_dma_buffer(8192);
+        buf.memset(42);
+        new_file.write_at(buf, 0).await.unwrap();
+
+        new_file
+            .sync_range(0, 4096, SyncRangeFlags::WRITE)
+            .await
+            .unwrap();
+        new_file
+            .sync_range(
+                4096,
+                0,
+                SyncRangeFlags::WAIT_BEFORE | SyncRangeFlags::WRITE | SyncRangeFlags::WAIT_AFTER,
+            )
+            .await
+            .unwrap();
+        new_file.fsync().await.unwrap();
+        new_file.close().await.unwrap();
+    });
+
     dma_file_test!(file_fallocate_zero, path, _k, {
         let new_file = DmaFile::create(path.join("testfile"))
             .await
@@ -699,6 +904,26 @@ pub(crate) mod test {
         new_file.close().await.expect("failed to close file");
     });
 
+    dma_file_test!(file_registered_readwrite, path, _k, {
+        let new_file = DmaFile::create(path.join("testfile"))
+            .await
+            .expect("failed to create file");
+        new_file.register().expect("failed to register file");
+        // registering twice is fine
+        new_file.register().expect("failed to register file");
+
+        let mut buf = new_file.alloc_dma_buffer(4096);
+        buf.memset(42);
+        let res = new_file.write_at(buf, 0).await.expect("failed to write");
+        assert_eq!(res, 4096);
+        let read_buf = new_file.read_at(0, 4096).await.expect("failed to read");
+        assert!(read_buf.iter().all(|x| *x == 42));
+
+        let fd = new_file.as_raw_fd();
+        new_file.close().await.expect("failed to close file");
+        assert!(crate::executor().reactor().sys.fixed_file(fd).is_none());
+    });
+
     dma_file_test!(file_simple_readwrite, path, _k, {
         let new_file = DmaFile::create(path.join("testfile"))
             .await
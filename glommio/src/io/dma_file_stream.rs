@@ -4,7 +4,7 @@
 // This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2020 Datadog, Inc.
 //
 use crate::{
-    io::{dma_file::align_down, read_result::ReadResult, DmaFile},
+    io::{dma_file::align_down, read_result::ReadResult, DmaFile, SyncRangeFlags},
     sys::DmaBuffer,
     task, ByteSliceMutExt,
 };
@@ -620,6 +620,7 @@ pub struct DmaStreamWriterBuilder {
     buffer_size: usize,
     write_behind: usize,
     sync_on_close: bool,
+    sync_range: Option<SyncRangeFlags>,
     file: Rc<DmaFile>,
 }
 
@@ -654,6 +655,7 @@ impl DmaStreamWriterBuilder {
             buffer_size: 128 << 10,
             write_behind: 4,
             sync_on_close: true,
+            sync_range: None,
             file: Rc::new(file),
         }
     }
@@ -680,6 +682,23 @@ impl DmaStreamWriterBuilder {
         self
     }
 
+    /// Issues a [`sync_range`] with `flags` for every buffer written by the
+    /// [`DmaStreamWriter`], right after its write completes and before its
+    /// room in the write-behind window is released.
+    ///
+    /// Passing [`SyncRangeFlags::WRITE`] starts the writeback of each range as
+    /// soon as possible, so the sync issued on close has less to do. Adding
+    /// [`SyncRangeFlags::WAIT_AFTER`] also waits for it, which throttles the
+    /// writer to the pace of the device.
+    ///
+    /// [`sync_range`]: DmaFile::sync_range
+    /// [`DmaStreamWriter`]: struct.DmaStreamWriter.html
+    #[must_use = "The builder must be built to be useful"]
+    pub fn with_sync_range(mut self, flags: SyncRangeFlags) -> Self {
+        self.sync_range = Some(flags);
+        self
+    }
+
     /// Define the buffer size that will be used by the [`DmaStreamWriter`]
     ///
     /// [`DmaStreamWriter`]: struct.DmaStreamWriter.html
@@ -800,6 +819,7 @@ struct DmaStreamWriterState {
     buffer_pos: usize,
     write_behind: usize,
     sync_on_close: bool,
+    sync_range: Option<SyncRangeFlags>,
 }
 
 macro_rules! already_closed {
@@ -954,8 +974,13 @@ impl DmaStreamWriterState {
     fn flush_one_buffer(&mut self, buffer: DmaBuffer, state: Rc<RefCell<Self>>, file: Rc<DmaFile>) {
         let aligned_pos = self.aligned_pos;
         let flush_pos = self.current_pos();
+        let sync_range = self.sync_range;
         let handle = crate::spawn_local(async move {
-            let res = file.write_at(buffer, aligned_pos).await;
+            let len = buffer.len() as u64;
+            let mut res = file.write_at(buffer, aligned_pos).await.map(|_| ());
+            if let (Ok(()), Some(flags)) = (&res, sync_range) {
+                res = file.sync_range(aligned_pos, len, flags).await;
+            }
             let mut state = state.borrow_mut();
             if !collect_error!(state, res) {
                 state.flush_state.on_complete(flush_pos);
@@ -1024,6 +1049,7 @@ impl DmaStreamWriter {
             buffer_size: builder.buffer_size,
             write_behind: builder.write_behind,
             sync_on_close: builder.sync_on_close,
+            sync_range: builder.sync_range,
             current_buffer: None,
             waker: None,
             flush_state: DmaStreamWriterFlushState::new(builder.write_behind),
@@ -1510,6 +1536,25 @@ mod test {
         assert_eq!(rfile.file_size().await.unwrap(), 1_000_000);
     });
 
+    file_stream_write_test!(write_with_sync_range, path, _k, filename, file, {
+        let mut writer = DmaStreamWriterBuilder::new(file)
+            .with_buffer_size(4096)
+            .with_write_behind(2)
+            .with_sync_range(SyncRangeFlags::WRITE | SyncRangeFlags::WAIT_AFTER)
+            .build();
+
+        for i in 0..100_000 {
+            writer.write_all(&[i as u8]).await.unwrap();
+        }
+        writer.close().await.unwrap();
+
+        let rfile = DmaFile::open(&filename).await.unwrap();
+        assert_eq!(rfile.file_size().await.unwrap(), 100_000);
+        let buf = rfile.read_at(0, 100_000).await.unwrap();
+        check_contents!(*buf, 0);
+        rfile.close().await.unwrap();
+    });
+
     file_stream_read_test!(read_exact_zero_buffer, path, _k, file, _file_size: 131072, {
         let mut reader = DmaStreamReaderBuilder::new(file)
             .with_buffer_size(0)
@@ -0,0 +1,154 @@
+// Unless explicitly stated otherwise all files in this repository are licensed
+// under the MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021 Datadog, Inc.
+//
+use crate::io::PollFd;
+use futures_lite::future::poll_fn;
+use std::{
+    cell::RefCell,
+    fs::File,
+    io,
+    os::unix::io::{AsRawFd, FromRawFd, RawFd},
+};
+
+type Result<T> = crate::Result<T, ()>;
+
+/// An eventfd, a counter that can be used to signal events across threads and
+/// processes.
+///
+/// Writes add to the counter and reads wait until it's nonzero. In the
+/// default mode a read returns the counter and resets it to zero; in
+/// semaphore mode it returns one and decrements the counter instead.
+///
+/// The file descriptor can be handed over, by [`as_raw_fd`], to a thread that
+/// doesn't run an executor or to a C library that signals its events through
+/// an eventfd.
+///
+/// # Examples
+///
+/// ```no_run
+/// use glommio::{io::EventFd, LocalExecutor};
+///
+/// let ex = LocalExecutor::default();
+/// ex.run(async {
+///     let event = EventFd::new(0).unwrap();
+///     event.write(3).await.unwrap();
+///     assert_eq!(event.read().await.unwrap(), 3);
+/// });
+/// ```
+///
+/// [`as_raw_fd`]: AsRawFd::as_raw_fd
+#[derive(Debug)]
+pub struct EventFd {
+    fd: RefCell<PollFd<File>>,
+}
+
+impl EventFd {
+    /// Creates an eventfd whose counter starts at `initial`.
+    pub fn new(initial: u32) -> Result<EventFd> {
+        Self::create(initial, 0)
+    }
+
+    /// Creates an eventfd in semaphore mode, whose counter starts at
+    /// `initial`.
+    pub fn semaphore(initial: u32) -> Result<EventFd> {
+        Self::create(initial, libc::EFD_SEMAPHORE)
+    }
+
+    fn create(initial: u32, flags: libc::c_int) -> Result<EventFd> {
+        let fd = unsafe { libc::eventfd(initial, libc::EFD_CLOEXEC | flags) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        let file = unsafe { File::from_raw_fd(fd) };
+        Ok(EventFd {
+            fd: RefCell::new(PollFd::new(file)?),
+        })
+    }
+
+    /// Waits for the counter to be nonzero and reads it.
+    pub async fn read(&self) -> Result<u64> {
+        let mut value = 0u64;
+        poll_fn(|cx| {
+            self.fd.borrow_mut().poll_io(cx, false, |fd| unsafe {
+                libc::read(fd, &mut value as *mut u64 as *mut libc::c_void, 8)
+            })
+        })
+        .await?;
+        Ok(value)
+    }
+
+    /// Adds `value` to the counter, waiting if that would overflow it.
+    pub async fn write(&self, value: u64) -> Result<()> {
+        poll_fn(|cx| {
+            self.fd.borrow_mut().poll_io(cx, true, |fd| unsafe {
+                libc::write(fd, &value as *const u64 as *const libc::c_void, 8)
+            })
+        })
+        .await?;
+        Ok(())
+    }
+}
+
+impl AsRawFd for EventFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.borrow().inner().as_raw_fd()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn eventfd_counts() {
+        test_executor!(async move {
+            let event = Rc::new(EventFd::new(0).unwrap());
+            let reader = crate::spawn_local(crate::enclose! { (event) async move {
+                event.read().await.unwrap()
+            }});
+            event.write(2).await.unwrap();
+            assert_eq!(reader.await, 2);
+
+            let semaphore = EventFd::semaphore(2).unwrap();
+            assert_eq!(semaphore.read().await.unwrap(), 1);
+            assert_eq!(semaphore.read().await.unwrap(), 1);
+        });
+    }
+
+    #[test]
+    fn eventfd_concurrent_readers() {
+        test_executor!(async move {
+            let event = Rc::new(EventFd::semaphore(0).unwrap());
+            let readers: Vec<_> = (0..2)
+                .map(|_| {
+                    crate::spawn_local(crate::enclose! { (event) async move {
+                        event.read().await.unwrap()
+                    }})
+                })
+                .collect();
+            // let both readers wait on the eventfd before it is written
+            crate::executor().yield_task_queue_now().await;
+            event.write(2).await.unwrap();
+            for reader in readers {
+                assert_eq!(reader.await, 1);
+            }
+        });
+    }
+
+    #[test]
+    fn eventfd_from_another_thread() {
+        test_executor!(async move {
+            let event = EventFd::new(0).unwrap();
+            let fd = event.as_raw_fd();
+            let thread = std::thread::spawn(move || {
+                let value = 7u64;
+                unsafe { libc::write(fd, &value as *const u64 as *const libc::c_void, 8) };
+            });
+            assert_eq!(event.read().await.unwrap(), 7);
+            thread.join().unwrap();
+        });
+    }
+}
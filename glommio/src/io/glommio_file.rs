@@ -5,7 +5,7 @@
 //
 
 use crate::{
-    io::sched::FileScheduler,
+    io::{request_chunks, sched::FileScheduler},
     reactor::Reactor,
     sys::{self, Statx},
     GlommioError,
@@ -22,6 +22,60 @@ use std::{
 
 type Result<T> = crate::Result<T, ()>;
 
+/// Hints about how a range of a file is going to be accessed, so the OS can
+/// adjust its caching and readahead. See `posix_fadvise(2)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Advice {
+    /// No particular access pattern. This is the default.
+    Normal,
+    /// The range is going to be read sequentially, so the OS can read ahead
+    /// more aggressively.
+    Sequential,
+    /// The range is going to be read in random order, so reading ahead is
+    /// pointless.
+    Random,
+    /// The range is going to be read soon. The OS starts reading it into the
+    /// page cache in the background.
+    WillNeed,
+    /// The range isn't going to be read again soon. The OS drops it from the
+    /// page cache, if it isn't dirty.
+    DontNeed,
+    /// The range is going to be read only once.
+    NoReuse,
+}
+
+impl From<Advice> for nix::fcntl::PosixFadviseAdvice {
+    fn from(advice: Advice) -> Self {
+        match advice {
+            Advice::Normal => Self::POSIX_FADV_NORMAL,
+            Advice::Sequential => Self::POSIX_FADV_SEQUENTIAL,
+            Advice::Random => Self::POSIX_FADV_RANDOM,
+            Advice::WillNeed => Self::POSIX_FADV_WILLNEED,
+            Advice::DontNeed => Self::POSIX_FADV_DONTNEED,
+            Advice::NoReuse => Self::POSIX_FADV_NOREUSE,
+        }
+    }
+}
+
+bitflags::bitflags! {
+    /// Flags controlling what a range sync waits for. See
+    /// `sync_file_range(2)`.
+    ///
+    /// A range sync only deals with the data in the page cache: it doesn't
+    /// write out metadata nor flush the device's write cache, so it doesn't
+    /// make the data durable by itself.
+    pub struct SyncRangeFlags: u32 {
+        /// Waits for the writeback of pages in the range that was already in
+        /// progress before starting any more.
+        const WAIT_BEFORE = 1;
+        /// Starts the writeback of all dirty pages in the range that aren't
+        /// already being written back, without waiting for it.
+        const WRITE = 2;
+        /// Waits for the writeback of all pages in the range to complete.
+        const WAIT_AFTER = 4;
+    }
+}
+
 pub(super) type Device = u64;
 pub(super) type Inode = u64;
 pub(super) type Identity = (Device, Inode);
@@ -145,10 +199,25 @@ impl GlommioFile {
     pub(crate) fn discard(mut self) -> (RawFd, Option<PathBuf>) {
         // Destruct `self` signalling to `Drop` that there is no need to async close
         let fd = self.file.take().unwrap();
+        if let Some(r) = self.reactor.upgrade() {
+            r.unregister_file(fd);
+        }
         let path = self.path.take();
         (fd, path)
     }
 
+    pub(crate) fn register(&self) -> Result<()> {
+        let reactor = self.reactor.upgrade().unwrap();
+        reactor.register_file(self.as_raw_fd()).map_err(|source| {
+            GlommioError::create_enhanced(
+                source,
+                "Registering",
+                self.path.borrow().clone(),
+                Some(self.as_raw_fd()),
+            )
+        })
+    }
+
     pub(crate) async fn close(self) -> Result<()> {
         let reactor = self.reactor.upgrade().unwrap();
         // Destruct `self` into components skipping Drop.
@@ -222,6 +291,26 @@ impl GlommioFile {
         Ok(())
     }
 
+    pub(crate) async fn advise(&self, offset: u64, len: u64, advice: Advice) -> Result<()> {
+        for (offset, chunk) in request_chunks(offset, len) {
+            let source = self.reactor.upgrade().unwrap().fadvise(
+                self.as_raw_fd(),
+                offset,
+                chunk,
+                advice.into(),
+            );
+            source.collect_rw().await.map_err(|source| {
+                GlommioError::create_enhanced(
+                    source,
+                    "Advising",
+                    self.path.borrow().as_ref(),
+                    Some(self.as_raw_fd()),
+                )
+            })?;
+        }
+        Ok(())
+    }
+
     pub(crate) async fn hint_extent_size(&self, size: usize) -> Result<i32> {
         match sys::fs_hint_extentsize(self.as_raw_fd(), size) {
             Ok(hint) => Ok(hint),
@@ -268,6 +357,44 @@ impl GlommioFile {
         Ok(())
     }
 
+    pub(crate) async fn fsync(&self) -> Result<()> {
+        let source = self.reactor.upgrade().unwrap().fsync(self.as_raw_fd());
+        source.collect_rw().await.map_err(|source| {
+            GlommioError::create_enhanced(
+                source,
+                "Syncing",
+                self.path.borrow().as_ref(),
+                Some(self.as_raw_fd()),
+            )
+        })?;
+        Ok(())
+    }
+
+    pub(crate) async fn sync_range(
+        &self,
+        offset: u64,
+        len: u64,
+        flags: SyncRangeFlags,
+    ) -> Result<()> {
+        for (offset, chunk) in request_chunks(offset, len) {
+            let source = self.reactor.upgrade().unwrap().sync_file_range(
+                self.as_raw_fd(),
+                offset,
+                chunk as u32,
+                flags.bits(),
+            );
+            source.collect_rw().await.map_err(|source| {
+                GlommioError::create_enhanced(
+                    source,
+                    "Syncing range",
+                    self.path.borrow().as_ref(),
+                    Some(self.as_raw_fd()),
+                )
+            })?;
+        }
+        Ok(())
+    }
+
     pub(crate) async fn remove(&self) -> Result<()> {
         let path = self.path_required("remove")?.to_owned();
         let source = self.reactor.upgrade().unwrap().remove_file(&*path).await;
@@ -283,15 +410,15 @@ impl GlommioFile {
         Ok(())
     }
 
-    // Retrieve file metadata, backed by the statx(2) syscall
+    // Retrieve file metadata, backed by the statx(2) syscall. This looks at
+    // the file descriptor itself, so it works regardless of what happened to
+    // the path the file was opened from.
     pub(crate) async fn statx(&self) -> Result<Statx> {
-        let path = self.path_required("stat")?.to_owned();
-
         let source = self
             .reactor
             .upgrade()
             .unwrap()
-            .statx(self.as_raw_fd(), path.as_ref());
+            .statx(self.as_raw_fd(), Path::new(""));
         source.collect_rw().await.map_err(|source| {
             GlommioError::create_enhanced(
                 source,
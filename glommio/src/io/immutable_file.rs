@@ -5,9 +5,10 @@
 //
 use crate::io::{
     bulk_io::{MergedBufferLimit, ReadAmplificationLimit, ReadManyArgs},
+    glommio_file::Advice,
     open_options::OpenOptions,
-    DmaStreamReaderBuilder, DmaStreamWriter, DmaStreamWriterBuilder, IoVec, ReadManyResult,
-    ReadResult, ScheduledSource,
+    DmaStreamReaderBuilder, DmaStreamWriter, DmaStreamWriterBuilder, IoVec, MappedFile,
+    ReadManyResult, ReadResult, ScheduledSource,
 };
 use futures_lite::{future::poll_fn, io::AsyncWrite, Stream};
 use std::{
@@ -407,6 +408,14 @@ impl ImmutableFile {
             .read_many(iovs, buffer_limit, read_amp_limit)
     }
 
+    /// Tells the OS how `len` bytes of the file starting at `offset` are going
+    /// to be accessed. See [`DmaFile::advise`].
+    ///
+    /// [`DmaFile::advise`]: crate::io::DmaFile::advise
+    pub async fn advise(&self, offset: u64, len: u64, advice: Advice) -> Result<()> {
+        self.stream_builder.file.advise(offset, len, advice).await
+    }
+
     /// Rename this file.
     ///
     /// Note: this syscall might be issued in a background thread depending on
@@ -433,6 +442,14 @@ impl ImmutableFile {
         Ok(())
     }
 
+    /// Maps the file into memory, read-only.
+    ///
+    /// See [`MappedFile`] for how to avoid blocking the executor on page
+    /// faults when accessing it.
+    pub fn mmap(&self) -> Result<MappedFile> {
+        MappedFile::new(self.stream_builder.file.clone(), self.size as usize)
+    }
+
     /// Creates a [`DmaStreamReaderBuilder`] from this `ImmutableFile`.
     ///
     /// The resulting builder can be augmented with any option available to the
@@ -489,6 +506,25 @@ mod test {
             .unwrap_err();
     });
 
+    immutable_file_test!(seal_and_mmap, path, {
+        let fname = path.join("testfile");
+        let mut immutable = ImmutableFileBuilder::new(fname).build_sink().await.unwrap();
+        let data: Vec<u8> = (0..3 << 12).map(|x| x as u8).collect();
+        immutable.write_all(&data).await.unwrap();
+        let stream = immutable.seal().await.unwrap();
+
+        let map = stream.mmap().unwrap();
+        assert_eq!(map.len(), data.len());
+        map.prefetch(4096, 1).await.unwrap();
+        assert_eq!(&map[..], &data[..]);
+        assert!(map.is_resident(0, map.len()));
+        // ranges past the end are clamped
+        map.prefetch(0, 1 << 20).await.unwrap();
+
+        drop(map);
+        stream.close().await.unwrap();
+    });
+
     immutable_file_test!(seal_and_stream, path, {
         let fname = path.join("testfile");
         let mut immutable = ImmutableFileBuilder::new(fname).build_sink().await.unwrap();
@@ -0,0 +1,175 @@
+// Unless explicitly stated otherwise all files in this repository are licensed
+// under the MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021 Datadog, Inc.
+//
+use crate::{
+    io::{request_chunks, DmaFile},
+    GlommioError,
+};
+use nix::sys::mman::MmapAdvise;
+use std::{io, ops::Deref, os::unix::io::AsRawFd, ptr::NonNull, rc::Rc};
+
+type Result<T> = crate::Result<T, ()>;
+
+/// A read-only, memory-mapped view of an [`ImmutableFile`], created by
+/// [`ImmutableFile::mmap`].
+///
+/// The contents of the file are exposed as a `&[u8]` through [`Deref`], so
+/// small and frequently accessed files, like lookup tables, can be read
+/// without issuing any I/O. Accessing a part of the file that isn't in memory
+/// yet page-faults, though, and faults block the executor while the page is
+/// read from the device. Call [`prefetch`] ahead of time for the parts that
+/// are about to be accessed to have them read in the background instead, and
+/// check [`is_resident`] to decide whether accessing a range would fault.
+///
+/// The mapping keeps the file open and is removed when dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use glommio::{io::ImmutableFileBuilder, LocalExecutor};
+///
+/// let ex = LocalExecutor::default();
+/// ex.run(async {
+///     let file = ImmutableFileBuilder::new("table.bin")
+///         .build_existing()
+///         .await
+///         .unwrap();
+///     let map = file.mmap().unwrap();
+///     map.prefetch(0, map.len()).await.unwrap();
+///     let first = map[0];
+/// });
+/// ```
+///
+/// [`ImmutableFile`]: crate::io::ImmutableFile
+/// [`ImmutableFile::mmap`]: crate::io::ImmutableFile::mmap
+/// [`prefetch`]: MappedFile::prefetch
+/// [`is_resident`]: MappedFile::is_resident
+#[derive(Debug)]
+pub struct MappedFile {
+    // Only used to keep the file open
+    _file: Rc<DmaFile>,
+    addr: NonNull<u8>,
+    len: usize,
+}
+
+impl MappedFile {
+    pub(crate) fn new(file: Rc<DmaFile>, len: usize) -> Result<MappedFile> {
+        // Zero-sized mappings are invalid, but there's nothing to map anyway
+        let addr = match len {
+            0 => NonNull::dangling(),
+            len => {
+                let addr = unsafe {
+                    libc::mmap(
+                        std::ptr::null_mut(),
+                        len,
+                        libc::PROT_READ,
+                        libc::MAP_SHARED,
+                        file.as_raw_fd(),
+                        0,
+                    )
+                };
+                if addr == libc::MAP_FAILED {
+                    return Err(GlommioError::create_enhanced(
+                        io::Error::last_os_error(),
+                        "Mapping",
+                        file.path().map(|x| x.to_path_buf()),
+                        Some(file.as_raw_fd()),
+                    ));
+                }
+                NonNull::new(addr as *mut u8).unwrap()
+            }
+        };
+        Ok(MappedFile {
+            _file: file,
+            addr,
+            len,
+        })
+    }
+
+    /// The contents of the file.
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.addr.as_ptr(), self.len) }
+    }
+
+    /// Asks the kernel to read `len` bytes of the file starting at `offset`
+    /// into memory, so accessing them later doesn't block on the device.
+    ///
+    /// The request is issued through `io_uring`, and the read itself happens
+    /// in the background: this returns once it was started, not once it
+    /// completed.
+    pub async fn prefetch(&self, offset: usize, len: usize) -> Result<()> {
+        self.madvise(offset, len, MmapAdvise::MADV_WILLNEED).await
+    }
+
+    /// Returns whether all the pages holding `len` bytes of the file starting
+    /// at `offset` are in memory, which means accessing them won't block on
+    /// the device.
+    pub fn is_resident(&self, offset: usize, len: usize) -> bool {
+        let (addr, len) = match self.page_range(offset, len) {
+            Some(x) => x,
+            None => return true,
+        };
+        let page_size = sys_page_size();
+        let mut pages = vec![0u8; (len + page_size - 1) / page_size];
+        let res = unsafe { libc::mincore(addr as *mut _, len, pages.as_mut_ptr()) };
+        res == 0 && pages.iter().all(|x| x & 1 != 0)
+    }
+
+    pub(crate) async fn madvise(
+        &self,
+        offset: usize,
+        len: usize,
+        advice: MmapAdvise,
+    ) -> Result<()> {
+        let (addr, len) = match self.page_range(offset, len) {
+            Some(x) => x,
+            None => return Ok(()),
+        };
+        let reactor = crate::executor().reactor();
+        for (done, chunk) in request_chunks(0, len as u64) {
+            let addr = unsafe { addr.add(done as usize) };
+            let source = reactor.madvise(addr, chunk as usize, advice);
+            source.collect_rw().await?;
+        }
+        Ok(())
+    }
+
+    /// The page-aligned range of the mapping that holds `len` bytes starting
+    /// at `offset`, clamped to the mapping, if not empty.
+    fn page_range(&self, offset: usize, len: usize) -> Option<(*mut u8, usize)> {
+        let end = std::cmp::min(offset.saturating_add(len), self.len);
+        if offset >= end {
+            return None;
+        }
+        let start = offset - offset % sys_page_size();
+        Some((unsafe { self.addr.as_ptr().add(start) }, end - start))
+    }
+}
+
+fn sys_page_size() -> usize {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+}
+
+impl Deref for MappedFile {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl AsRef<[u8]> for MappedFile {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl Drop for MappedFile {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            unsafe { libc::munmap(self.addr.as_ptr() as *mut _, self.len) };
+        }
+    }
+}
@@ -121,20 +121,37 @@ macro_rules! enhanced_try {
     }};
 }
 
+mod async_fd;
 mod buffered_file;
 mod buffered_file_stream;
 mod bulk_io;
+mod chain;
 mod directory;
+mod dma_buffer_pool;
 mod dma_file;
 mod dma_file_stream;
+mod event_fd;
 mod glommio_file;
 mod immutable_file;
+mod mapped_file;
 mod open_options;
+mod pipe;
+mod poll_fd;
 mod read_result;
 mod sched;
 mod stat;
+mod watcher;
 
-use std::path::Path;
+use crate::{
+    reactor::Reactor,
+    sys::{SourceType, Statx},
+    GlommioError, ReactorErrorKind,
+};
+use std::{
+    ffi::OsString,
+    os::unix::ffi::OsStringExt,
+    path::{Path, PathBuf},
+};
 
 pub(super) type Result<T> = crate::Result<T, ()>;
 
@@ -146,6 +163,99 @@ pub async fn rename<P: AsRef<Path>, Q: AsRef<Path>>(old_path: P, new_path: Q) ->
     Ok(())
 }
 
+bitflags::bitflags! {
+    /// Flags that change the behavior of [`rename_with_flags`] and
+    /// [`Directory::rename_with_flags`].
+    pub struct RenameFlags: libc::c_uint {
+        /// Fail with [`std::io::ErrorKind::AlreadyExists`] instead of
+        /// replacing the destination if it exists.
+        const NOREPLACE = libc::RENAME_NOREPLACE;
+        /// Atomically exchange the source and the destination. Both must
+        /// exist, but may be of different types (a file and a directory, for
+        /// instance).
+        const EXCHANGE = libc::RENAME_EXCHANGE;
+    }
+}
+
+/// rename an existing file, with `flags` controlling what happens if the
+/// destination already exists.
+///
+/// This is `renameat2(2)`. Not every filesystem supports every flag; those
+/// that don't fail the operation with `EINVAL`.
+pub async fn rename_with_flags<P: AsRef<Path>, Q: AsRef<Path>>(
+    old_path: P,
+    new_path: Q,
+    flags: RenameFlags,
+) -> Result<()> {
+    let reactor = crate::executor().reactor();
+    let source = reactor
+        .rename_at(
+            libc::AT_FDCWD,
+            old_path.as_ref(),
+            libc::AT_FDCWD,
+            new_path.as_ref(),
+            flags.bits(),
+        )
+        .await;
+    source.collect_rw().await.map_err(|source| {
+        GlommioError::create_enhanced(source, "Renaming", Some(old_path.as_ref()), None)
+    })?;
+    Ok(())
+}
+
+/// Creates a new hard link on the filesystem.
+///
+/// The `link` path will be a link pointing to the `original` path. If
+/// `original` is a symbolic link, the link itself is linked to, not what it
+/// points to.
+pub async fn hard_link<P: AsRef<Path>, Q: AsRef<Path>>(original: P, link: Q) -> Result<()> {
+    let reactor = crate::executor().reactor();
+    let source = reactor
+        .link_at(
+            libc::AT_FDCWD,
+            original.as_ref(),
+            libc::AT_FDCWD,
+            link.as_ref(),
+            0,
+        )
+        .await;
+    source.collect_rw().await.map_err(|source| {
+        GlommioError::create_enhanced(source, "Creating hard link", Some(link.as_ref()), None)
+    })?;
+    Ok(())
+}
+
+/// Creates a new symbolic link on the filesystem.
+///
+/// The `link` path will be a symbolic link pointing to the `original` path.
+/// `original` is stored as is, so if relative it is resolved relative to the
+/// directory `link` is in.
+pub async fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(original: P, link: Q) -> Result<()> {
+    let reactor = crate::executor().reactor();
+    let source = reactor
+        .symlink_at(original.as_ref(), libc::AT_FDCWD, link.as_ref())
+        .await;
+    source.collect_rw().await.map_err(|source| {
+        GlommioError::create_enhanced(source, "Creating symlink", Some(link.as_ref()), None)
+    })?;
+    Ok(())
+}
+
+/// Reads a symbolic link, returning the path it points to.
+pub async fn read_link<P: AsRef<Path>>(path: P) -> Result<PathBuf> {
+    let reactor = crate::executor().reactor();
+    let source = reactor.read_link(libc::AT_FDCWD, path.as_ref()).await;
+    source.collect_rw().await.map_err(|source| {
+        GlommioError::create_enhanced(source, "Reading symlink", Some(path.as_ref()), None)
+    })?;
+    match source.extract_source_type() {
+        SourceType::ReadLink(buf) => Ok(OsString::from_vec(buf).into()),
+        src => Err(GlommioError::ReactorError(
+            ReactorErrorKind::IncorrectSourceType(format!("{src:?}")),
+        )),
+    }
+}
+
 /// remove an existing file given its name
 pub async fn remove<P: AsRef<Path>>(path: P) -> Result<()> {
     let reactor = crate::executor().reactor();
@@ -154,29 +264,137 @@ pub async fn remove<P: AsRef<Path>>(path: P) -> Result<()> {
     Ok(())
 }
 
-pub(crate) use self::sched::{FileScheduler, IoScheduler, ScheduledSource};
+/// Recursively create a directory and all of its missing parents.
+///
+/// It is not an error if the directory already exists, but it is if `path`
+/// or one of its parents exists and is not a directory.
+pub async fn create_dir_all<P: AsRef<Path>>(path: P) -> Result<()> {
+    let reactor = crate::executor().reactor();
+
+    // Walk up the tree until we find a directory that exists, then create the
+    // missing ones back down
+    let mut missing = vec![];
+    for dir in path.as_ref().ancestors() {
+        if dir.as_os_str().is_empty() {
+            break;
+        }
+        let source = reactor.create_dir(dir, 0o777).await;
+        match source.collect_rw().await {
+            Ok(_) => break,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => missing.push(dir),
+            Err(err) => {
+                if err.kind() == std::io::ErrorKind::AlreadyExists && is_dir(&reactor, dir).await {
+                    break;
+                }
+                return Err(GlommioError::create_enhanced(
+                    err,
+                    "Creating directory",
+                    Some(dir),
+                    None,
+                ));
+            }
+        }
+    }
+
+    for dir in missing.into_iter().rev() {
+        let source = reactor.create_dir(dir, 0o777).await;
+        match source.collect_rw().await {
+            Ok(_) => {}
+            Err(err) => {
+                if err.kind() == std::io::ErrorKind::AlreadyExists && is_dir(&reactor, dir).await {
+                    continue;
+                }
+                return Err(GlommioError::create_enhanced(
+                    err,
+                    "Creating directory",
+                    Some(dir),
+                    None,
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether `path` is a directory, following symbolic links. Paths that can't
+/// be stat'ed are not.
+async fn is_dir(reactor: &Reactor, path: &Path) -> bool {
+    let source = reactor.statx(libc::AT_FDCWD, path);
+    if source.collect_rw().await.is_err() {
+        return false;
+    }
+    match Statx::try_from(source.extract_source_type()) {
+        Ok(stat) => u32::from(stat.stx_mode) & libc::S_IFMT == libc::S_IFDIR,
+        Err(_) => false,
+    }
+}
+
+/// remove an existing, empty directory given its name
+pub async fn remove_dir<P: AsRef<Path>>(path: P) -> Result<()> {
+    let reactor = crate::executor().reactor();
+    let source = reactor.remove_dir(path.as_ref()).await;
+    source.collect_rw().await.map_err(|source| {
+        GlommioError::create_enhanced(source, "Removing directory", Some(path.as_ref()), None)
+    })?;
+    Ok(())
+}
+
+/// Remove a directory and all of its contents.
+///
+/// Symbolic links found in the tree are removed, never followed. If `path`
+/// itself is a symbolic link, only the link is removed.
+pub async fn remove_dir_all<P: AsRef<Path>>(path: P) -> Result<()> {
+    directory::remove_dir_all(path.as_ref()).await
+}
+
+/// Splits the `len` bytes starting at `offset` into `(offset, len)` chunks
+/// that fit the 32-bit length of an `io_uring` request. A zero `len` is a
+/// single empty chunk, as some requests take it to mean "up to the end".
+fn request_chunks(offset: u64, len: u64) -> impl Iterator<Item = (u64, u64)> {
+    let end = offset.saturating_add(len);
+    let mut next = Some(offset);
+    std::iter::from_fn(move || {
+        let start = next?;
+        let chunk = std::cmp::min(end - start, 1 << 31);
+        next = Some(start + chunk).filter(|x| *x < end);
+        Some((start, chunk))
+    })
+}
+
 pub use self::{
+    async_fd::AsyncFd,
     buffered_file::BufferedFile,
     buffered_file_stream::{
         stdin, StreamReader, StreamReaderBuilder, StreamWriter, StreamWriterBuilder,
     },
     bulk_io::{IoVec, MergedBufferLimit, ReadAmplificationLimit, ReadManyResult},
-    directory::Directory,
+    chain::IoChain,
+    directory::{DirEntry, Directory, FileType, WalkEntry},
+    dma_buffer_pool::DmaBufferPool,
     dma_file::{CloseResult, DmaFile},
     dma_file_stream::{
         DmaStreamReader, DmaStreamReaderBuilder, DmaStreamWriter, DmaStreamWriterBuilder,
     },
+    event_fd::EventFd,
+    glommio_file::{Advice, SyncRangeFlags},
     immutable_file::{ImmutableFile, ImmutableFileBuilder, ImmutableFilePreSealSink},
+    mapped_file::MappedFile,
     open_options::OpenOptions,
+    pipe::{pipe, sendfile, Pipe, PipeReader, PipeWriter},
     read_result::ReadResult,
     stat::Stat,
+    watcher::{WatchDescriptor, WatchEvent, WatchEventKind, WatchEvents, WatchMask, Watcher},
+};
+pub(crate) use self::{
+    poll_fd::PollFd,
+    sched::{FileScheduler, IoScheduler, ScheduledSource},
 };
 pub use crate::sys::DmaBuffer;
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::LocalExecutor;
+    use crate::{test_utils::make_tmp_test_directory, LocalExecutor};
 
     #[test]
     fn remove_nonexistent() {
@@ -186,4 +404,93 @@ mod test {
             assert_eq!(x.unwrap_err().raw_os_error().unwrap(), libc::ENOENT);
         });
     }
+
+    #[test]
+    fn request_chunks_cover_the_range() {
+        let chunks: Vec<_> = request_chunks(10, 0).collect();
+        assert_eq!(chunks, vec![(10, 0)]);
+        let chunks: Vec<_> = request_chunks(10, 5 << 30).collect();
+        assert_eq!(
+            chunks,
+            vec![
+                (10, 2 << 30),
+                (10 + (2 << 30), 2 << 30),
+                (10 + (4 << 30), 1 << 30)
+            ]
+        );
+        // the end of the range saturates instead of overflowing
+        let chunks: Vec<_> = request_chunks(u64::MAX - 10, 100).collect();
+        assert_eq!(chunks, vec![(u64::MAX - 10, 10)]);
+    }
+
+    #[test]
+    fn create_and_remove_dir_tree() {
+        let dir = make_tmp_test_directory("create_and_remove_dir_tree");
+        let path = dir.path.clone();
+
+        test_executor!(async move {
+            let nested = path.join("a/b/c");
+            create_dir_all(&nested).await.unwrap();
+            assert!(nested.is_dir());
+            create_dir_all(&nested).await.unwrap();
+
+            std::fs::File::create(nested.join("file")).unwrap();
+            let err = create_dir_all(nested.join("file")).await.unwrap_err();
+            assert_eq!(err.raw_os_error(), Some(libc::EEXIST));
+            std::os::unix::fs::symlink(&path, path.join("a/b/link")).unwrap();
+
+            match remove_dir(path.join("a")).await.unwrap_err() {
+                GlommioError::EnhancedIoError {
+                    source, path: p, ..
+                } => {
+                    assert_eq!(source.raw_os_error(), Some(libc::ENOTEMPTY));
+                    assert_eq!(p, Some(path.join("a")));
+                }
+                err => panic!("unexpected error {err:?}"),
+            }
+
+            remove_dir_all(path.join("a")).await.unwrap();
+            assert!(!path.join("a").exists());
+            assert!(path.exists());
+
+            remove_dir(&path).await.unwrap();
+            assert!(!path.exists());
+        });
+    }
+
+    #[test]
+    fn links_and_rename_flags() {
+        let dir = make_tmp_test_directory("links_and_rename_flags");
+        let path = dir.path.clone();
+        std::fs::write(path.join("a"), b"a").unwrap();
+        std::fs::write(path.join("b"), b"b").unwrap();
+
+        test_executor!(async move {
+            hard_link(path.join("a"), path.join("a-link"))
+                .await
+                .unwrap();
+            assert_eq!(std::fs::read(path.join("a-link")).unwrap(), b"a");
+            let err = hard_link(path.join("a"), path.join("b")).await.unwrap_err();
+            assert_eq!(err.raw_os_error(), Some(libc::EEXIST));
+
+            symlink("a", path.join("current")).await.unwrap();
+            assert_eq!(
+                read_link(path.join("current")).await.unwrap(),
+                Path::new("a")
+            );
+            let err = read_link(path.join("a")).await.unwrap_err();
+            assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+
+            let err = rename_with_flags(path.join("a"), path.join("b"), RenameFlags::NOREPLACE)
+                .await
+                .unwrap_err();
+            assert_eq!(err.raw_os_error(), Some(libc::EEXIST));
+
+            rename_with_flags(path.join("a"), path.join("b"), RenameFlags::EXCHANGE)
+                .await
+                .unwrap();
+            assert_eq!(std::fs::read(path.join("a")).unwrap(), b"b");
+            assert_eq!(std::fs::read(path.join("b")).unwrap(), b"a");
+        });
+    }
 }
@@ -21,8 +21,8 @@ pub struct OpenOptions {
     pub(super) write: bool,
     append: bool,
     truncate: bool,
-    create: bool,
-    create_new: bool,
+    pub(super) create: bool,
+    pub(super) create_new: bool,
     // system-specific
     pub(super) custom_flags: libc::c_int,
     pub(super) mode: libc::mode_t,
@@ -0,0 +1,451 @@
+// Unless explicitly stated otherwise all files in this repository are licensed
+// under the MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021 Datadog, Inc.
+//
+use crate::{
+    io::{BufferedFile, PollFd},
+    GlommioError,
+};
+use futures_lite::{AsyncRead, AsyncWrite};
+use std::{
+    convert::TryInto,
+    fs::File,
+    io,
+    os::unix::io::{AsRawFd, FromRawFd, RawFd},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+type Result<T> = crate::Result<T, ()>;
+
+/// A kernel pipe used to move data between file descriptors without copying
+/// it into user space.
+///
+/// Data is moved into the pipe with [`splice_from`] and out of it with
+/// [`splice_to`]. Either side of those transfers can be a file or a socket,
+/// so a `Pipe` is the building block for zero-copy forwarding between
+/// sockets and files. The data in a pipe can also be duplicated into
+/// another pipe with [`tee`], without consuming it.
+///
+/// All transfers are issued through `io_uring` with `IORING_OP_SPLICE` and
+/// `IORING_OP_TEE`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use glommio::{
+///     io::{BufferedFile, Pipe},
+///     LocalExecutor,
+/// };
+///
+/// let ex = LocalExecutor::default();
+/// ex.run(async {
+///     let from = BufferedFile::open("from.txt").await.unwrap();
+///     let to = BufferedFile::create("to.txt").await.unwrap();
+///     let pipe = Pipe::new().unwrap();
+///
+///     let read = pipe.splice_from(&from, Some(0), 4096).await.unwrap();
+///     pipe.splice_to(&to, Some(0), read).await.unwrap();
+/// });
+/// ```
+///
+/// [`splice_from`]: Pipe::splice_from
+/// [`splice_to`]: Pipe::splice_to
+/// [`tee`]: Pipe::tee
+#[derive(Debug)]
+pub struct Pipe {
+    reader: RawFd,
+    writer: RawFd,
+}
+
+impl Pipe {
+    /// Creates a new pipe with the system's default capacity.
+    pub fn new() -> Result<Pipe> {
+        let (reader, writer) = nix::unistd::pipe2(nix::fcntl::OFlag::O_CLOEXEC)
+            .map_err(|err| io::Error::from_raw_os_error(err as i32))?;
+        Ok(Pipe { reader, writer })
+    }
+
+    /// Creates a new pipe that can hold at least `capacity` bytes.
+    ///
+    /// The kernel rounds the capacity up to a power-of-two number of pages,
+    /// and unprivileged users may not go above `/proc/sys/fs/pipe-max-size`.
+    pub fn with_capacity(capacity: usize) -> Result<Pipe> {
+        let pipe = Self::new()?;
+        let capacity: libc::c_int = capacity.try_into().map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "pipe capacity is too large")
+        })?;
+        if unsafe { libc::fcntl(pipe.writer, libc::F_SETPIPE_SZ, capacity) } < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        Ok(pipe)
+    }
+
+    /// Returns how many bytes the pipe can hold.
+    pub fn capacity(&self) -> Result<usize> {
+        match unsafe { libc::fcntl(self.writer, libc::F_GETPIPE_SZ) } {
+            x if x < 0 => Err(io::Error::last_os_error().into()),
+            x => Ok(x as usize),
+        }
+    }
+
+    /// Moves up to `len` bytes from `from` into the pipe.
+    ///
+    /// If `from` is a file, `offset` is the position to read from. Sockets
+    /// and pipes have no position, so `offset` must be `None` for them, in
+    /// which case files are read from, and advance, their current position.
+    ///
+    /// Returns how many bytes were moved, which is zero at end of file.
+    pub async fn splice_from<F: AsRawFd>(
+        &self,
+        from: &F,
+        offset: Option<u64>,
+        len: usize,
+    ) -> Result<usize> {
+        self.splice(from.as_raw_fd(), offset, self.writer, None, len)
+            .await
+    }
+
+    /// Moves up to `len` bytes from the pipe into `to`.
+    ///
+    /// If `to` is a file, `offset` is the position to write at. See
+    /// [`splice_from`] for how `None` is handled.
+    ///
+    /// Returns how many bytes were moved.
+    ///
+    /// [`splice_from`]: Pipe::splice_from
+    pub async fn splice_to<F: AsRawFd>(
+        &self,
+        to: &F,
+        offset: Option<u64>,
+        len: usize,
+    ) -> Result<usize> {
+        self.splice(self.reader, None, to.as_raw_fd(), offset, len)
+            .await
+    }
+
+    /// Copies up to `len` bytes from this pipe into `other`, leaving them
+    /// in this pipe to be consumed.
+    ///
+    /// Returns how many bytes were copied.
+    pub async fn tee(&self, other: &Pipe, len: usize) -> Result<usize> {
+        let source = crate::executor()
+            .reactor()
+            .tee(self.reader, other.writer, clamp(len));
+        source.collect_rw().await.map_err(|source| {
+            GlommioError::create_enhanced::<&str>(source, "Tee", None, Some(self.reader))
+        })
+    }
+
+    /// Moves up to `len` bytes from `from` into `to` by way of the pipe,
+    /// stopping early only if `from` reaches end of file.
+    ///
+    /// The pipe must be empty. Returns how many bytes were moved.
+    pub(crate) async fn forward(
+        &self,
+        from: RawFd,
+        mut offset: Option<u64>,
+        to: RawFd,
+        len: usize,
+    ) -> Result<usize> {
+        let mut moved = 0;
+        while moved < len {
+            let read = self
+                .splice(from, offset, self.writer, None, len - moved)
+                .await?;
+            if read == 0 {
+                break;
+            }
+            if let Some(offset) = offset.as_mut() {
+                *offset += read as u64;
+            }
+
+            let mut written = 0;
+            while written < read {
+                let n = self
+                    .splice(self.reader, None, to, None, read - written)
+                    .await?;
+                if n == 0 {
+                    return Err(GlommioError::create_enhanced::<&str>(
+                        io::Error::from(io::ErrorKind::WriteZero),
+                        "Splicing",
+                        None,
+                        Some(to),
+                    ));
+                }
+                written += n;
+            }
+            moved += read;
+        }
+        Ok(moved)
+    }
+
+    async fn splice(
+        &self,
+        from: RawFd,
+        off_in: Option<u64>,
+        to: RawFd,
+        off_out: Option<u64>,
+        len: usize,
+    ) -> Result<usize> {
+        let source = crate::executor()
+            .reactor()
+            .splice(from, off_in, to, off_out, clamp(len));
+        source.collect_rw().await.map_err(|source| {
+            GlommioError::create_enhanced::<&str>(source, "Splicing", None, Some(from))
+        })
+    }
+}
+
+impl Drop for Pipe {
+    fn drop(&mut self) {
+        let _ = nix::unistd::close(self.reader);
+        let _ = nix::unistd::close(self.writer);
+    }
+}
+
+fn clamp(len: usize) -> u32 {
+    len.min(u32::MAX as usize) as u32
+}
+
+/// Sends up to `len` bytes of `file`, starting at `offset`, to `to` without
+/// copying them into user space.
+///
+/// This is the equivalent of `sendfile(2)`, and `to` is usually a socket
+/// like a [`TcpStream`]. The transfer goes through a [`Pipe`] that is created
+/// for the duration of the call. It stops early only if the end of the file
+/// is reached, and returns how many bytes were sent.
+///
+/// # Examples
+///
+/// ```no_run
+/// use glommio::{io::BufferedFile, net::TcpStream, LocalExecutor};
+///
+/// let ex = LocalExecutor::default();
+/// ex.run(async {
+///     let file = BufferedFile::open("index.html").await.unwrap();
+///     let stream = TcpStream::connect("127.0.0.1:10000").await.unwrap();
+///     let size = file.file_size().await.unwrap() as usize;
+///     glommio::io::sendfile(&file, 0, size, &stream).await.unwrap();
+/// });
+/// ```
+///
+/// [`TcpStream`]: crate::net::TcpStream
+pub async fn sendfile<S: AsRawFd>(
+    file: &BufferedFile,
+    offset: u64,
+    len: usize,
+    to: &S,
+) -> Result<usize> {
+    let pipe = Pipe::new()?;
+    pipe.forward(file.as_raw_fd(), Some(offset), to.as_raw_fd(), len)
+        .await
+        .map_err(|err| match err {
+            GlommioError::EnhancedIoError { source, op, fd, .. }
+                if fd == Some(file.as_raw_fd()) =>
+            {
+                GlommioError::EnhancedIoError {
+                    source,
+                    op,
+                    path: file.path().map(|x| x.to_path_buf()),
+                    fd,
+                }
+            }
+            err => err,
+        })
+}
+
+/// Creates an anonymous pipe, returning its read and write ends.
+///
+/// Unlike a [`Pipe`], which moves data between other file descriptors, the
+/// ends of this pipe are read from and written to directly, through
+/// [`AsyncRead`] and [`AsyncWrite`]. Either end can be handed over, by its raw
+/// file descriptor, to a thread that doesn't run an executor or to a C
+/// library, to talk to it without blocking the executor.
+///
+/// # Examples
+///
+/// ```no_run
+/// use futures_lite::{AsyncReadExt, AsyncWriteExt};
+/// use glommio::LocalExecutor;
+///
+/// let ex = LocalExecutor::default();
+/// ex.run(async {
+///     let (mut reader, mut writer) = glommio::io::pipe().unwrap();
+///     writer.write_all(b"hello").await.unwrap();
+///     let mut buf = [0u8; 5];
+///     reader.read_exact(&mut buf).await.unwrap();
+/// });
+/// ```
+pub fn pipe() -> Result<(PipeReader, PipeWriter)> {
+    let (reader, writer) = nix::unistd::pipe2(nix::fcntl::OFlag::O_CLOEXEC)
+        .map_err(|err| io::Error::from_raw_os_error(err as i32))?;
+    let (reader, writer) = unsafe { (File::from_raw_fd(reader), File::from_raw_fd(writer)) };
+    Ok((
+        PipeReader {
+            fd: PollFd::new(reader)?,
+        },
+        PipeWriter {
+            fd: PollFd::new(writer)?,
+        },
+    ))
+}
+
+/// The read end of an anonymous pipe, created by [`pipe`].
+///
+/// Reads return zero once the write end is closed and the pipe is empty.
+#[derive(Debug)]
+pub struct PipeReader {
+    fd: PollFd<File>,
+}
+
+/// The write end of an anonymous pipe, created by [`pipe`].
+///
+/// The pipe is closed when this is dropped.
+#[derive(Debug)]
+pub struct PipeWriter {
+    fd: PollFd<File>,
+}
+
+impl AsRawFd for PipeReader {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.inner().as_raw_fd()
+    }
+}
+
+impl AsRawFd for PipeWriter {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.inner().as_raw_fd()
+    }
+}
+
+impl AsyncRead for PipeReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.fd.poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for PipeWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.fd.poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::make_tmp_test_directory;
+    use futures_lite::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn anonymous_pipe() {
+        test_executor!(async move {
+            let (mut reader, mut writer) = pipe().unwrap();
+            let data: Vec<u8> = (0..1 << 20).map(|x| x as u8).collect();
+            // larger than the pipe, so the writer has to wait for the reader
+            let write = crate::spawn_local(async move {
+                writer.write_all(&data).await.unwrap();
+                data
+            });
+            let mut received = Vec::new();
+            reader.read_to_end(&mut received).await.unwrap();
+            assert_eq!(received, write.await);
+        });
+    }
+
+    #[test]
+    fn pipe_splice_and_tee() {
+        test_executor!(async move {
+            let dir = make_tmp_test_directory("pipe_splice_and_tee");
+            let from = BufferedFile::create(dir.path.join("from")).await.unwrap();
+            from.write_at(b"hello world".to_vec(), 0).await.unwrap();
+            let to = BufferedFile::create(dir.path.join("to")).await.unwrap();
+            let copy = BufferedFile::create(dir.path.join("copy")).await.unwrap();
+
+            let pipe = Pipe::with_capacity(1 << 16).unwrap();
+            assert!(pipe.capacity().unwrap() >= 1 << 16);
+            let other = Pipe::new().unwrap();
+
+            assert_eq!(pipe.splice_from(&from, Some(6), 100).await.unwrap(), 5);
+            assert_eq!(pipe.tee(&other, 100).await.unwrap(), 5);
+            assert_eq!(pipe.splice_to(&to, Some(0), 5).await.unwrap(), 5);
+            assert_eq!(other.splice_to(&copy, Some(2), 5).await.unwrap(), 5);
+
+            assert_eq!(&*to.read_at(0, 100).await.unwrap(), b"world");
+            assert_eq!(&*copy.read_at(0, 100).await.unwrap(), b"\0\0world");
+
+            for file in [from, to, copy] {
+                file.close().await.unwrap();
+            }
+        });
+    }
+
+    #[test]
+    fn forward_stops_when_nothing_is_written() {
+        test_executor!(async move {
+            let dir = make_tmp_test_directory("forward_stops_when_nothing_is_written");
+            let from = BufferedFile::create(dir.path.join("from")).await.unwrap();
+            from.write_at(b"hello".to_vec(), 0).await.unwrap();
+            let to = BufferedFile::create(dir.path.join("to")).await.unwrap();
+
+            // data goes into one pipe but is taken out of another, empty and
+            // without a writer, so moving it out always returns zero
+            let (reader, writer) = nix::unistd::pipe2(nix::fcntl::OFlag::O_CLOEXEC).unwrap();
+            let (other_reader, other_writer) =
+                nix::unistd::pipe2(nix::fcntl::OFlag::O_CLOEXEC).unwrap();
+            nix::unistd::close(writer).unwrap();
+            let pipe = Pipe {
+                reader,
+                writer: other_writer,
+            };
+
+            let err = pipe
+                .forward(from.as_raw_fd(), Some(0), to.as_raw_fd(), 5)
+                .await
+                .unwrap_err();
+            assert_eq!(io::Error::from(err).kind(), io::ErrorKind::WriteZero);
+
+            drop(pipe);
+            nix::unistd::close(other_reader).unwrap();
+            from.close().await.unwrap();
+            to.close().await.unwrap();
+        });
+    }
+
+    #[test]
+    fn sendfile_to_file() {
+        test_executor!(async move {
+            let dir = make_tmp_test_directory("sendfile_to_file");
+            let from = BufferedFile::create(dir.path.join("from")).await.unwrap();
+            let data: Vec<u8> = (0..1 << 20).map(|x| x as u8).collect();
+            from.write_at(data.clone(), 0).await.unwrap();
+            let to = BufferedFile::create(dir.path.join("to")).await.unwrap();
+
+            // asking for more than the file holds stops at end of file
+            let sent = sendfile(&from, 10, 2 << 20, &to).await.unwrap();
+            assert_eq!(sent, data.len() - 10);
+            let read = to.read_at(0, data.len()).await.unwrap();
+            assert_eq!(&*read, &data[10..]);
+
+            from.close().await.unwrap();
+            to.close().await.unwrap();
+        });
+    }
+}
@@ -0,0 +1,106 @@
+// Unless explicitly stated otherwise all files in this repository are licensed
+// under the MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021 Datadog, Inc.
+//
+use crate::{reactor::Reactor, sys::Source};
+use std::{
+    io,
+    os::unix::io::{AsRawFd, RawFd},
+    rc::{Rc, Weak},
+    task::{Context, Poll},
+};
+
+/// A file descriptor put in non-blocking mode, whose readiness is polled by
+/// the reactor with `PollAdd` sources.
+#[derive(Debug)]
+pub(crate) struct PollFd<T> {
+    inner: T,
+    reactor: Weak<Reactor>,
+    read_source: Option<Source>,
+    write_source: Option<Source>,
+}
+
+impl<T: AsRawFd> PollFd<T> {
+    pub(crate) fn new(inner: T) -> io::Result<PollFd<T>> {
+        let fd = inner.as_raw_fd();
+        let flags =
+            nix::fcntl::OFlag::from_bits_truncate(nix::fcntl::fcntl(fd, nix::fcntl::F_GETFL)?);
+        nix::fcntl::fcntl(
+            fd,
+            nix::fcntl::F_SETFL(flags | nix::fcntl::OFlag::O_NONBLOCK),
+        )?;
+        Ok(PollFd {
+            inner,
+            reactor: Rc::downgrade(&crate::executor().reactor()),
+            read_source: None,
+            write_source: None,
+        })
+    }
+
+    pub(crate) fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Runs `op` until it doesn't fail with `EWOULDBLOCK`, waiting for the
+    /// file descriptor to be readable, or writable if `write` is set, in
+    /// between.
+    pub(crate) fn poll_io(
+        &mut self,
+        cx: &mut Context<'_>,
+        write: bool,
+        mut op: impl FnMut(RawFd) -> isize,
+    ) -> Poll<io::Result<usize>> {
+        let fd = self.inner.as_raw_fd();
+        let source = match write {
+            true => &mut self.write_source,
+            false => &mut self.read_source,
+        };
+        loop {
+            if let Some(src) = source.as_ref() {
+                if src.result().is_none() {
+                    // Several futures can wait on the same readiness
+                    src.add_waiter_unique(cx.waker());
+                    return Poll::Pending;
+                }
+                source.take();
+            }
+
+            match op(fd) {
+                x if x >= 0 => return Poll::Ready(Ok(x as usize)),
+                _ => {
+                    let err = io::Error::last_os_error();
+                    if err.kind() != io::ErrorKind::WouldBlock {
+                        return Poll::Ready(Err(err));
+                    }
+                }
+            }
+
+            let reactor = self.reactor.upgrade().unwrap();
+            *source = Some(match write {
+                true => reactor.poll_write_ready(fd),
+                false => reactor.poll_read_ready(fd),
+            });
+        }
+    }
+
+    pub(crate) fn poll_read(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_io(cx, false, |fd| unsafe {
+            libc::read(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len())
+        })
+    }
+
+    pub(crate) fn poll_write(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_io(cx, true, |fd| unsafe {
+            libc::write(fd, buf.as_ptr() as *const libc::c_void, buf.len())
+        })
+    }
+}
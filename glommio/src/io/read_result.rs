@@ -20,6 +20,12 @@ impl core::ops::Deref for ReadResult {
     }
 }
 
+impl AsRef<[u8]> for ReadResult {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
 #[derive(Clone, Debug)]
 struct ReadResultInner {
     buffer: ScheduledSource,
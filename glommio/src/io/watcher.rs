@@ -0,0 +1,367 @@
+// Unless explicitly stated otherwise all files in this repository are licensed
+// under the MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021 Datadog, Inc.
+//
+use crate::{reactor::Reactor, sys::Source, GlommioError};
+use ahash::AHashMap;
+use futures_lite::Stream;
+use std::{
+    cell::{Cell, RefCell},
+    ffi::{CString, OsStr},
+    io, mem,
+    os::unix::{
+        ffi::OsStrExt,
+        io::{AsRawFd, RawFd},
+    },
+    path::{Path, PathBuf},
+    pin::Pin,
+    rc::{Rc, Weak},
+    task::{Context, Poll},
+};
+
+type Result<T> = crate::Result<T, ()>;
+
+/// Large enough for many events, and at least one with the longest name.
+const EVENT_BUFFER_SIZE: usize = 16 << 10;
+
+bitflags::bitflags! {
+    /// The kinds of changes a [`Watcher`] reports for a watched path.
+    pub struct WatchMask: u32 {
+        /// A file or directory was created in the watched directory.
+        const CREATE = libc::IN_CREATE;
+        /// A file was written to.
+        const MODIFY = libc::IN_MODIFY;
+        /// A file opened for writing was closed.
+        const CLOSE_WRITE = libc::IN_CLOSE_WRITE;
+        /// The metadata of a file changed.
+        const ATTRIB = libc::IN_ATTRIB;
+        /// A file was moved out of the watched directory.
+        const MOVED_FROM = libc::IN_MOVED_FROM;
+        /// A file was moved into the watched directory.
+        const MOVED_TO = libc::IN_MOVED_TO;
+        /// A file was moved out of or into the watched directory.
+        const MOVE = libc::IN_MOVED_FROM | libc::IN_MOVED_TO;
+        /// A file or directory was deleted from the watched directory.
+        const DELETE = libc::IN_DELETE;
+        /// The watched path itself was deleted.
+        const DELETE_SELF = libc::IN_DELETE_SELF;
+        /// The watched path itself was moved.
+        const MOVE_SELF = libc::IN_MOVE_SELF;
+        /// All of the above.
+        const ALL = libc::IN_ALL_EVENTS;
+    }
+}
+
+/// Identifies a path watched by a [`Watcher`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WatchDescriptor(i32);
+
+/// What changed, as reported by a [`WatchEvent`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchEventKind {
+    /// A file or directory was created.
+    Create,
+    /// A file was written to.
+    Modify,
+    /// A file opened for writing was closed.
+    CloseWrite,
+    /// The metadata of a file changed.
+    Attrib,
+    /// A file was moved away. The matching [`MovedTo`] event, if the file
+    /// was moved into a watched directory, carries the same cookie.
+    ///
+    /// [`MovedTo`]: WatchEventKind::MovedTo
+    MovedFrom(u32),
+    /// A file was moved in. See [`MovedFrom`].
+    ///
+    /// [`MovedFrom`]: WatchEventKind::MovedFrom
+    MovedTo(u32),
+    /// A file or directory was deleted.
+    Delete,
+    /// The watched path itself was deleted.
+    DeleteSelf,
+    /// The watched path itself was moved.
+    MoveSelf,
+    /// The watch was removed, either explicitly or because the watched path
+    /// is gone. No more events are reported for it.
+    Removed,
+    /// The kernel's event queue overflowed and events were lost.
+    Overflow,
+}
+
+/// A change reported by a [`Watcher`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WatchEvent {
+    /// The watch the event is for. Meaningless for
+    /// [`WatchEventKind::Overflow`].
+    pub wd: WatchDescriptor,
+    /// What changed.
+    pub kind: WatchEventKind,
+    /// The path that changed. For events on entries of a watched directory,
+    /// this is the path of the entry.
+    pub path: PathBuf,
+    /// Whether the path that changed is a directory.
+    pub is_dir: bool,
+}
+
+/// Watches paths for changes, using inotify.
+///
+/// Paths are added with [`watch`] and the changes to all of them are read
+/// from the stream returned by [`events`]. Watching a directory reports
+/// changes to its entries, but not to the entries of its subdirectories.
+///
+/// The inotify file descriptor is polled by the reactor, so no extra thread
+/// is needed, and the events can be consumed from any task queue.
+///
+/// # Examples
+///
+/// ```no_run
+/// use futures_lite::StreamExt;
+/// use glommio::{
+///     io::{WatchEventKind, WatchMask, Watcher},
+///     LocalExecutor,
+/// };
+///
+/// let ex = LocalExecutor::default();
+/// ex.run(async {
+///     let watcher = Watcher::new().unwrap();
+///     watcher
+///         .watch("/var/ingest", WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO)
+///         .unwrap();
+///     let mut events = watcher.events();
+///     while let Some(event) = events.next().await {
+///         let event = event.unwrap();
+///         println!("{:?} is ready to be ingested", event.path);
+///     }
+/// });
+/// ```
+///
+/// [`watch`]: Watcher::watch
+/// [`events`]: Watcher::events
+#[derive(Debug)]
+pub struct Watcher {
+    fd: RawFd,
+    reactor: Weak<Reactor>,
+    source: RefCell<Option<Source>>,
+    paths: RefCell<AHashMap<i32, PathBuf>>,
+    buf: RefCell<Vec<u8>>,
+    pos: Cell<usize>,
+}
+
+impl Watcher {
+    /// Creates a watcher that doesn't watch anything yet.
+    pub fn new() -> Result<Watcher> {
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        Ok(Watcher {
+            fd,
+            reactor: Rc::downgrade(&crate::executor().reactor()),
+            source: RefCell::new(None),
+            paths: RefCell::new(AHashMap::new()),
+            buf: RefCell::new(Vec::new()),
+            pos: Cell::new(0),
+        })
+    }
+
+    /// Starts reporting the changes in `mask` for `path`.
+    ///
+    /// Watching a path that is already watched replaces its mask and returns
+    /// the same descriptor.
+    pub fn watch<P: AsRef<Path>>(&self, path: P, mask: WatchMask) -> Result<WatchDescriptor> {
+        let path = path.as_ref();
+        let cpath = CString::new(path.as_os_str().as_bytes())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        let wd = unsafe { libc::inotify_add_watch(self.fd, cpath.as_ptr(), mask.bits()) };
+        if wd < 0 {
+            return Err(GlommioError::create_enhanced(
+                io::Error::last_os_error(),
+                "Watching",
+                Some(path),
+                Some(self.fd),
+            ));
+        }
+        self.paths.borrow_mut().insert(wd, path.to_path_buf());
+        Ok(WatchDescriptor(wd))
+    }
+
+    /// Stops watching the path of `wd`. A [`WatchEventKind::Removed`] event
+    /// is reported for it.
+    pub fn unwatch(&self, wd: WatchDescriptor) -> Result<()> {
+        if unsafe { libc::inotify_rm_watch(self.fd, wd.0) } < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+
+    /// Returns a stream of the changes to the watched paths.
+    ///
+    /// Only one stream should be consumed at a time, as each event is
+    /// reported only once.
+    pub fn events(&self) -> WatchEvents<'_> {
+        WatchEvents { watcher: self }
+    }
+
+    fn next_buffered(&self) -> Option<WatchEvent> {
+        let buf = self.buf.borrow();
+        let header = mem::size_of::<libc::inotify_event>();
+        loop {
+            let pos = self.pos.get();
+            if pos + header > buf.len() {
+                return None;
+            }
+            let event: libc::inotify_event =
+                unsafe { std::ptr::read_unaligned(buf[pos..].as_ptr() as *const _) };
+            let name = &buf[pos + header..pos + header + event.len as usize];
+            self.pos.set(pos + header + event.len as usize);
+
+            let kind = match event.mask {
+                x if x & libc::IN_Q_OVERFLOW != 0 => WatchEventKind::Overflow,
+                x if x & libc::IN_IGNORED != 0 => WatchEventKind::Removed,
+                x if x & libc::IN_CREATE != 0 => WatchEventKind::Create,
+                x if x & libc::IN_MODIFY != 0 => WatchEventKind::Modify,
+                x if x & libc::IN_CLOSE_WRITE != 0 => WatchEventKind::CloseWrite,
+                x if x & libc::IN_ATTRIB != 0 => WatchEventKind::Attrib,
+                x if x & libc::IN_MOVED_FROM != 0 => WatchEventKind::MovedFrom(event.cookie),
+                x if x & libc::IN_MOVED_TO != 0 => WatchEventKind::MovedTo(event.cookie),
+                x if x & libc::IN_DELETE != 0 => WatchEventKind::Delete,
+                x if x & libc::IN_DELETE_SELF != 0 => WatchEventKind::DeleteSelf,
+                x if x & libc::IN_MOVE_SELF != 0 => WatchEventKind::MoveSelf,
+                // Some event we didn't ask for, like IN_UNMOUNT
+                _ => continue,
+            };
+
+            let mut paths = self.paths.borrow_mut();
+            let mut path = match kind {
+                WatchEventKind::Removed => paths.remove(&event.wd),
+                _ => paths.get(&event.wd).cloned(),
+            }
+            .unwrap_or_default();
+            // The name is padded with nul bytes
+            let name = name.split(|x| *x == 0).next().unwrap_or_default();
+            if !name.is_empty() {
+                path.push(OsStr::from_bytes(name));
+            }
+
+            return Some(WatchEvent {
+                wd: WatchDescriptor(event.wd),
+                kind,
+                path,
+                is_dir: event.mask & libc::IN_ISDIR != 0,
+            });
+        }
+    }
+
+    fn poll_next_event(&self, cx: &mut Context<'_>) -> Poll<Option<io::Result<WatchEvent>>> {
+        loop {
+            if let Some(event) = self.next_buffered() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+
+            let mut source = self.source.borrow_mut();
+            if let Some(src) = source.as_ref() {
+                if src.result().is_none() {
+                    src.add_waiter_single(cx.waker());
+                    return Poll::Pending;
+                }
+                source.take();
+            }
+
+            let mut buf = self.buf.borrow_mut();
+            buf.resize(EVENT_BUFFER_SIZE, 0);
+            let read =
+                unsafe { libc::read(self.fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+            if read < 0 {
+                buf.clear();
+                let err = io::Error::last_os_error();
+                if err.kind() != io::ErrorKind::WouldBlock {
+                    return Poll::Ready(Some(Err(err)));
+                }
+                *source = Some(self.reactor.upgrade().unwrap().poll_read_ready(self.fd));
+            } else {
+                buf.truncate(read as usize);
+            }
+            self.pos.set(0);
+        }
+    }
+}
+
+impl AsRawFd for Watcher {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.source.take();
+        let _ = nix::unistd::close(self.fd);
+    }
+}
+
+/// The stream of changes reported by a [`Watcher`], created by
+/// [`Watcher::events`].
+#[derive(Debug)]
+pub struct WatchEvents<'a> {
+    watcher: &'a Watcher,
+}
+
+impl Stream for WatchEvents<'_> {
+    type Item = io::Result<WatchEvent>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.watcher.poll_next_event(cx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::make_tmp_test_directory;
+    use futures_lite::StreamExt;
+
+    #[test]
+    fn watch_directory() {
+        test_executor!(async move {
+            let dir = make_tmp_test_directory("watch_directory");
+            let watcher = Watcher::new().unwrap();
+            let wd = watcher
+                .watch(
+                    &dir.path,
+                    WatchMask::CREATE | WatchMask::MODIFY | WatchMask::MOVE | WatchMask::DELETE,
+                )
+                .unwrap();
+            let mut events = watcher.events();
+
+            let from = dir.path.join("from");
+            let to = dir.path.join("to");
+            std::fs::write(&from, b"hello").unwrap();
+            let event = events.next().await.unwrap().unwrap();
+            assert_eq!(event.wd, wd);
+            assert_eq!(event.kind, WatchEventKind::Create);
+            assert_eq!(event.path, from);
+            assert!(!event.is_dir);
+            let event = events.next().await.unwrap().unwrap();
+            assert_eq!(event.kind, WatchEventKind::Modify);
+
+            std::fs::rename(&from, &to).unwrap();
+            let moved_from = events.next().await.unwrap().unwrap();
+            let moved_to = events.next().await.unwrap().unwrap();
+            match (moved_from.kind, moved_to.kind) {
+                (WatchEventKind::MovedFrom(x), WatchEventKind::MovedTo(y)) => assert_eq!(x, y),
+                x => panic!("unexpected events {:?}", x),
+            }
+            assert_eq!(moved_to.path, to);
+
+            std::fs::remove_file(&to).unwrap();
+            let event = events.next().await.unwrap().unwrap();
+            assert_eq!(event.kind, WatchEventKind::Delete);
+
+            watcher.unwatch(wd).unwrap();
+            let event = events.next().await.unwrap().unwrap();
+            assert_eq!(event.kind, WatchEventKind::Removed);
+        });
+    }
+}
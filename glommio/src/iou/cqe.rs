@@ -207,6 +207,8 @@ impl Iterator for CQEsBlocking<'_> {
 bitflags::bitflags! {
     /// Flags that can be returned from the kernel on [`CQE`]s.
     pub struct CompletionFlags: u32 {
-        const BUFFER_SHIFT    = 1 << 0;
+        const BUFFER          = 1 << 0;
+        const MORE            = 1 << 1;
+        const NOTIF           = 1 << 3;
     }
 }
@@ -92,6 +92,85 @@ impl<'ring> Registrar<'ring> {
             .map(|(i, buf)| Registered::new(i as u32, &mut **buf)))
     }
 
+    /// Whether the buffer table of the ring can be registered with
+    /// [`register_buffers_sparse`] and updated in place with
+    /// [`update_registered_buffers`], which needs Linux 5.13.
+    ///
+    /// [`register_buffers_sparse`]: Registrar::register_buffers_sparse
+    /// [`update_registered_buffers`]: Registrar::update_registered_buffers
+    pub fn supports_buffer_updates(&self) -> bool {
+        unsafe { self.ring.as_ref().features & uring_sys::IORING_FEAT_RSRC_TAGS != 0 }
+    }
+
+    /// Register a table of `slots` buffers, whose first entries are `buffers`
+    /// and the rest are empty. Unlike tables registered with
+    /// [`register_buffers`](Registrar::register_buffers), its entries can be
+    /// replaced with [`update_registered_buffers`] without waiting for the
+    /// ring to be idle.
+    ///
+    /// [`update_registered_buffers`]: Registrar::update_registered_buffers
+    pub fn register_buffers_sparse(&self, buffers: &[&[u8]], slots: u32) -> io::Result<()> {
+        assert!(buffers.len() <= slots as usize);
+        let mut iovecs = to_iovecs(buffers);
+        iovecs.resize(
+            slots as usize,
+            libc::iovec {
+                iov_base: std::ptr::null_mut(),
+                iov_len: 0,
+            },
+        );
+        let arg = uring_sys::io_uring_rsrc_register {
+            nr: slots,
+            resv: 0,
+            resv2: 0,
+            data: iovecs.as_ptr() as u64,
+            tags: 0,
+        };
+        self.register(
+            uring_sys::IORING_REGISTER_BUFFERS2,
+            &arg as *const _ as *const _,
+            std::mem::size_of_val(&arg) as _,
+        )
+    }
+
+    /// Replace the entries of the buffer table starting at `offset` with
+    /// `buffers`. Empty buffers leave their entry empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the table wasn't registered with
+    /// [`register_buffers_sparse`](Registrar::register_buffers_sparse), or if
+    /// the entries are out of bounds.
+    pub fn update_registered_buffers(&self, offset: u32, buffers: &[&[u8]]) -> io::Result<()> {
+        let iovecs = to_iovecs(buffers);
+        let arg = uring_sys::io_uring_rsrc_update2 {
+            offset,
+            resv: 0,
+            data: iovecs.as_ptr() as u64,
+            tags: 0,
+            nr: iovecs.len() as _,
+            resv2: 0,
+        };
+        self.register(
+            uring_sys::IORING_REGISTER_BUFFERS_UPDATE,
+            &arg as *const _ as *const _,
+            std::mem::size_of_val(&arg) as _,
+        )
+    }
+
+    fn register(
+        &self,
+        opcode: libc::c_uint,
+        arg: *const libc::c_void,
+        nr_args: libc::c_uint,
+    ) -> io::Result<()> {
+        let fd = unsafe { self.ring.as_ref().ring_fd };
+        match unsafe { uring_sys::syscalls::io_uring_register(fd, opcode, arg, nr_args) } {
+            x if x < 0 => Err(io::Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
     /// Unregister all currently registered buffers. An explicit call to this
     /// method is often unnecessary, because all buffers will be unregistered
     /// automatically when the ring is dropped.
@@ -204,6 +283,19 @@ impl<'ring> Registrar<'ring> {
     }
 }
 
+fn to_iovecs(buffers: &[&[u8]]) -> Vec<libc::iovec> {
+    buffers
+        .iter()
+        .map(|buf| libc::iovec {
+            iov_base: match buf.is_empty() {
+                true => std::ptr::null_mut(),
+                false => buf.as_ptr() as *mut _,
+            },
+            iov_len: buf.len(),
+        })
+        .collect()
+}
+
 impl fmt::Debug for Registrar<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let fd = unsafe { self.ring.as_ref().ring_fd };
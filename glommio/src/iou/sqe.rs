@@ -89,6 +89,13 @@ impl<'a> SQE<'a> {
         self.sqe.flags |= flags.bits();
     }
 
+    /// Let the kernel pick the buffer of this submission from `group`.
+    #[inline]
+    pub fn set_buffer_group(&mut self, group: BufferGroupId) {
+        self.sqe.buf_index.buf_index.index_or_group = group.id as _;
+        self.set_flags(SubmissionFlags::BUFFER_SELECT);
+    }
+
     /// Set the [`Personality`] associated with this submission.
     #[inline]
     pub fn set_personality(&mut self, personality: Personality) {
@@ -193,6 +200,27 @@ impl<'a> SQE<'a> {
         fd.update_sqe(self);
     }
 
+    /// Prepare a `sync_file_range` on a file descriptor.
+    #[inline]
+    pub unsafe fn prep_sync_file_range(
+        &mut self,
+        fd: impl UringFd,
+        offset: u64,
+        len: u32,
+        flags: u32,
+    ) {
+        uring_sys::io_uring_prep_rw(
+            uring_sys::IoRingOp::IORING_OP_SYNC_FILE_RANGE as _,
+            self.sqe,
+            fd.as_raw_fd(),
+            std::ptr::null(),
+            len,
+            offset,
+        );
+        self.sqe.cmd_flags.sync_range_flags = flags;
+        fd.update_sqe(self);
+    }
+
     /// Prepare a splice, copying data from one file descriptor to another.
     #[inline]
     pub unsafe fn prep_splice(
@@ -215,6 +243,43 @@ impl<'a> SQE<'a> {
         );
     }
 
+    /// Prepare a tee, duplicating data from one pipe to another without
+    /// consuming it.
+    #[inline]
+    pub unsafe fn prep_tee(&mut self, fd_in: RawFd, fd_out: RawFd, count: u32, flags: SpliceFlags) {
+        uring_sys::io_uring_prep_rw(
+            uring_sys::IoRingOp::IORING_OP_TEE as _,
+            self.sqe,
+            fd_out,
+            std::ptr::null(),
+            count,
+            0,
+        );
+        self.sqe.buf_index.buf_index.splice_fd_in = fd_in;
+        self.sqe.cmd_flags.splice_flags = flags.bits();
+    }
+
+    /// Prepare a `recv` event on a file descriptor that receives into a
+    /// buffer the kernel picks from `group`.
+    #[inline]
+    pub unsafe fn prep_recv_select(
+        &mut self,
+        fd: impl UringFd,
+        len: usize,
+        group: BufferGroupId,
+        flags: MsgFlags,
+    ) {
+        uring_sys::io_uring_prep_recv(
+            self.sqe,
+            fd.as_raw_fd(),
+            std::ptr::null_mut(),
+            len,
+            flags.bits(),
+        );
+        fd.update_sqe(self);
+        self.set_buffer_group(group);
+    }
+
     /// Prepare a `recv` event on a file descriptor.
     #[inline]
     pub unsafe fn prep_recv(&mut self, fd: impl UringFd, buf: &mut [u8], flags: MsgFlags) {
@@ -233,6 +298,25 @@ impl<'a> SQE<'a> {
         fd.update_sqe(self);
     }
 
+    /// Prepare a zero-copy send event on a file descriptor.
+    ///
+    /// The kernel posts a second completion, flagged with
+    /// [`CompletionFlags::NOTIF`](crate::iou::CompletionFlags::NOTIF), once
+    /// it no longer reads from `buf`. Until then, `buf` must stay valid.
+    #[inline]
+    pub unsafe fn prep_send_zc(&mut self, fd: impl UringFd, buf: &[u8], flags: MsgFlags) {
+        uring_sys::io_uring_prep_rw(
+            uring_sys::IoRingOp::IORING_OP_SEND_ZC as _,
+            self.sqe,
+            fd.as_raw_fd(),
+            buf.as_ptr() as _,
+            buf.len() as _,
+            0,
+        );
+        self.sqe.cmd_flags.msg_flags = flags.bits() as _;
+        fd.update_sqe(self);
+    }
+
     /// Prepare a `recvmsg` event on a file descriptor.
     pub unsafe fn prep_recvmsg(
         &mut self,
@@ -308,6 +392,88 @@ impl<'a> SQE<'a> {
 
     // TODO openat2
 
+    /// Prepare a `renameat2` event.
+    #[inline]
+    pub unsafe fn prep_renameat(
+        &mut self,
+        old_dirfd: RawFd,
+        old_path: &CStr,
+        new_dirfd: RawFd,
+        new_path: &CStr,
+        flags: u32,
+    ) {
+        uring_sys::io_uring_prep_rw(
+            uring_sys::IoRingOp::IORING_OP_RENAMEAT as _,
+            self.sqe,
+            old_dirfd,
+            old_path.as_ptr() as _,
+            new_dirfd as _,
+            new_path.as_ptr() as _,
+        );
+        self.sqe.cmd_flags.rename_flags = flags;
+    }
+
+    /// Prepare a `linkat` event.
+    #[inline]
+    pub unsafe fn prep_linkat(
+        &mut self,
+        old_dirfd: RawFd,
+        old_path: &CStr,
+        new_dirfd: RawFd,
+        new_path: &CStr,
+        flags: i32,
+    ) {
+        uring_sys::io_uring_prep_rw(
+            uring_sys::IoRingOp::IORING_OP_LINKAT as _,
+            self.sqe,
+            old_dirfd,
+            old_path.as_ptr() as _,
+            new_dirfd as _,
+            new_path.as_ptr() as _,
+        );
+        self.sqe.cmd_flags.hardlink_flags = flags as _;
+    }
+
+    /// Prepare an `unlinkat` event.
+    #[inline]
+    pub unsafe fn prep_unlinkat(&mut self, dirfd: RawFd, path: &CStr, flags: i32) {
+        uring_sys::io_uring_prep_rw(
+            uring_sys::IoRingOp::IORING_OP_UNLINKAT as _,
+            self.sqe,
+            dirfd,
+            path.as_ptr() as _,
+            0,
+            0,
+        );
+        self.sqe.cmd_flags.unlink_flags = flags as _;
+    }
+
+    /// Prepare a `mkdirat` event.
+    #[inline]
+    pub unsafe fn prep_mkdirat(&mut self, dirfd: RawFd, path: &CStr, mode: Mode) {
+        uring_sys::io_uring_prep_rw(
+            uring_sys::IoRingOp::IORING_OP_MKDIRAT as _,
+            self.sqe,
+            dirfd,
+            path.as_ptr() as _,
+            mode.bits(),
+            0,
+        );
+    }
+
+    /// Prepare a `symlinkat` event.
+    #[inline]
+    pub unsafe fn prep_symlinkat(&mut self, target: &CStr, new_dirfd: RawFd, link_path: &CStr) {
+        uring_sys::io_uring_prep_rw(
+            uring_sys::IoRingOp::IORING_OP_SYMLINKAT as _,
+            self.sqe,
+            new_dirfd,
+            target.as_ptr() as _,
+            0,
+            link_path.as_ptr() as _,
+        );
+    }
+
     /// Prepare a close event on a file descriptor.
     #[inline]
     pub unsafe fn prep_close(&mut self, fd: impl UringFd) {
@@ -376,6 +542,45 @@ impl<'a> SQE<'a> {
         fd.update_sqe(self);
     }
 
+    /// Prepare an `accept` that keeps posting a completion for every new
+    /// connection until it is canceled.
+    #[inline]
+    pub unsafe fn prep_multishot_accept(&mut self, fd: impl UringFd, flags: SockFlag) {
+        self.prep_accept(fd, None, flags);
+        self.sqe.ioprio |= uring_sys::IORING_ACCEPT_MULTISHOT;
+    }
+
+    /// Prepare a `recv` that keeps receiving into buffers picked from
+    /// `group`, posting a completion for each, until it is canceled or runs
+    /// out of buffers.
+    #[inline]
+    pub unsafe fn prep_multishot_recv_select(
+        &mut self,
+        fd: impl UringFd,
+        group: BufferGroupId,
+        flags: MsgFlags,
+    ) {
+        self.prep_recv_select(fd, 0, group, flags);
+        self.sqe.ioprio |= uring_sys::IORING_RECV_MULTISHOT;
+    }
+
+    /// Prepare a `recvmsg` that keeps receiving into buffers picked from
+    /// `group`. Each buffer starts with an `io_uring_recvmsg_out` header,
+    /// followed by the name and control areas sized after `msg`, and then
+    /// the payload.
+    #[inline]
+    pub unsafe fn prep_multishot_recvmsg_select(
+        &mut self,
+        fd: impl UringFd,
+        msg: *mut libc::msghdr,
+        group: BufferGroupId,
+        flags: MsgFlags,
+    ) {
+        self.prep_recvmsg(fd, msg, flags);
+        self.set_buffer_group(group);
+        self.sqe.ioprio |= uring_sys::IORING_RECV_MULTISHOT;
+    }
+
     #[inline]
     pub unsafe fn prep_fadvise(
         &mut self,
@@ -399,7 +604,7 @@ impl<'a> SQE<'a> {
     }
 
     #[inline]
-    pub unsafe fn prep_madvise(&mut self, data: &mut [u8], advice: MmapAdvise) {
+    pub unsafe fn prep_madvise(&mut self, addr: *mut u8, len: usize, advice: MmapAdvise) {
         use MmapAdvise::*;
         let advice = match advice {
             MADV_NORMAL => libc::MADV_NORMAL,
@@ -421,12 +626,7 @@ impl<'a> SQE<'a> {
             MADV_FREE => libc::MADV_FREE,
             _ => unreachable!(),
         };
-        uring_sys::io_uring_prep_madvise(
-            self.sqe,
-            data.as_mut_ptr() as *mut _,
-            data.len() as _,
-            advice,
-        );
+        uring_sys::io_uring_prep_madvise(self.sqe, addr as *mut _, len as _, advice);
     }
 
     #[inline]
@@ -434,7 +434,9 @@ mod error;
 mod executor;
 pub mod io;
 pub mod net;
+pub mod process;
 mod shares;
+pub mod signal;
 pub mod sync;
 pub mod timer;
 
@@ -449,8 +451,8 @@ pub use crate::{
         allocate_dma_buffer, allocate_dma_buffer_global, executor, spawn_local, spawn_local_into,
         spawn_scoped_local, spawn_scoped_local_into,
         stall::{DefaultStallDetectionHandler, StallDetectionHandler},
-        yield_if_needed, CpuSet, ExecutorJoinHandle, ExecutorProxy, ExecutorStats, LocalExecutor,
-        LocalExecutorBuilder, LocalExecutorPoolBuilder, Placement, PoolPlacement,
+        yield_if_needed, CpuSet, ExecutorJoinHandle, ExecutorProxy, ExecutorStats, HugePageSize,
+        LocalExecutor, LocalExecutorBuilder, LocalExecutorPoolBuilder, Placement, PoolPlacement,
         PoolThreadHandles, ScopedTask, Task, TaskQueueHandle, TaskQueueStats,
     },
     shares::{Shares, SharesManager},
@@ -713,17 +715,38 @@ pub struct IoStats {
     pub latency_ring: RingIoStats,
     /// The IO stats of the poll ring
     pub poll_ring: RingIoStats,
+    dma_buffer_fallbacks: (u64, u64),
 }
 
 impl IoStats {
-    fn new(main_ring: RingIoStats, latency_ring: RingIoStats, poll_ring: RingIoStats) -> IoStats {
+    fn new(
+        main_ring: RingIoStats,
+        latency_ring: RingIoStats,
+        poll_ring: RingIoStats,
+        dma_buffer_fallbacks: (u64, u64),
+    ) -> IoStats {
         IoStats {
             main_ring,
             latency_ring,
             poll_ring,
+            dma_buffer_fallbacks,
         }
     }
 
+    /// DMA buffer allocations that didn't fit in the `io_memory` arena
+    ///
+    /// Returns the number of buffers that were allocated from the heap
+    /// instead, as well as their total size. I/O using those buffers can't
+    /// use fixed-buffer requests. These are only counted for the executor as
+    /// a whole: the stats of task queues always report zero.
+    ///
+    /// Allocate buffers from a [`DmaBufferPool`] to rule out fallbacks.
+    ///
+    /// [`DmaBufferPool`]: crate::io::DmaBufferPool
+    pub fn dma_buffer_fallbacks(&self) -> (u64, u64) {
+        self.dma_buffer_fallbacks
+    }
+
     /// Combine stats from all rings
     pub fn all_rings(&self) -> RingIoStats {
         [&self.main_ring, &self.latency_ring, &self.poll_ring]
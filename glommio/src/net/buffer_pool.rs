@@ -0,0 +1,231 @@
+// Unless explicitly stated otherwise all files in this repository are licensed
+// under the MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021 Datadog, Inc.
+//
+use crate::{
+    net::stream::{Buffered, RxBuf},
+    sys::{DmaBuffer, ProvidedBufferGroup},
+};
+use std::rc::Rc;
+
+type Result<T> = crate::Result<T, ()>;
+
+/// A pool of receive buffers handed over to the kernel ahead of time.
+///
+/// Receives that use a `BufferPool` don't pass a buffer to the kernel.
+/// Instead, the kernel picks one of the pool's buffers when data arrives, so
+/// memory is only tied up for sockets that actually have something to read.
+/// This keeps the memory footprint of servers with many mostly idle
+/// connections bounded by the size of the pool rather than by the number of
+/// connections.
+///
+/// Buffers picked by the kernel are returned as [`DmaBuffer`]s and go back to
+/// the pool when dropped. If all buffers are in use, receives that select
+/// from the pool fail with `ENOBUFS`, so the pool should be sized for the
+/// number of buffers the application holds on to at any given time.
+///
+/// A pool can be shared by any number of sockets of the executor that
+/// created it. Pools are backed by `IORING_OP_PROVIDE_BUFFERS`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use glommio::{
+///     net::{BufferPool, Pooled, TcpListener},
+///     LocalExecutor,
+/// };
+/// use futures_lite::AsyncBufReadExt;
+///
+/// let ex = LocalExecutor::default();
+/// ex.run(async {
+///     let pool = BufferPool::new(1024, 4096).unwrap();
+///     let listener = TcpListener::bind("127.0.0.1:8000").unwrap();
+///     let stream = listener.accept().await.unwrap();
+///     let mut stream = stream.buffered_with(Pooled::new(&pool));
+///     let mut line = String::new();
+///     stream.read_line(&mut line).await.unwrap();
+/// });
+/// ```
+#[derive(Clone, Debug)]
+pub struct BufferPool {
+    group: Rc<ProvidedBufferGroup>,
+}
+
+impl BufferPool {
+    /// Creates a pool of `count` buffers of `buffer_size` bytes each.
+    ///
+    /// `count` can't be more than 65535, and a single executor can have at
+    /// most 65536 pools alive at once.
+    pub fn new(count: usize, buffer_size: usize) -> Result<BufferPool> {
+        let group = crate::executor()
+            .reactor()
+            .provided_buffer_group(count, buffer_size)?;
+        Ok(BufferPool { group })
+    }
+
+    /// The size of each buffer of the pool.
+    pub fn buffer_size(&self) -> usize {
+        self.group.buffer_size()
+    }
+
+    /// How many buffers the pool holds in total.
+    pub fn capacity(&self) -> usize {
+        self.group.count()
+    }
+
+    /// How many buffers are currently available to the kernel.
+    pub fn available(&self) -> usize {
+        self.group.count() - self.group.in_use()
+    }
+
+    pub(crate) fn group(&self) -> &Rc<ProvidedBufferGroup> {
+        &self.group
+    }
+}
+
+/// A receive buffer for streams that takes its memory from a [`BufferPool`].
+///
+/// Unlike [`Preallocated`], a `Pooled` receive buffer only holds memory while
+/// it has data that wasn't consumed yet. The buffer goes back to the pool as
+/// soon as all of its data was read.
+///
+/// [`Preallocated`]: crate::net::Preallocated
+#[derive(Debug)]
+pub struct Pooled {
+    pool: BufferPool,
+    buf: Option<DmaBuffer>,
+    head: usize,
+}
+
+impl Pooled {
+    /// Creates a receive buffer that takes its memory from `pool`.
+    pub fn new(pool: &BufferPool) -> Self {
+        Self {
+            pool: pool.clone(),
+            buf: None,
+            head: 0,
+        }
+    }
+
+    fn release_if_consumed(&mut self) {
+        if self.as_bytes().is_empty() {
+            self.buf.take();
+            self.head = 0;
+        }
+    }
+}
+
+impl Buffered for Pooled {}
+
+impl RxBuf for Pooled {
+    fn read(&mut self, buf: &mut [u8]) -> usize {
+        let sz = self.peek(buf);
+        self.consume(sz);
+        sz
+    }
+
+    fn peek(&self, buf: &mut [u8]) -> usize {
+        let data = self.as_bytes();
+        let sz = std::cmp::min(data.len(), buf.len());
+        buf[..sz].copy_from_slice(&data[..sz]);
+        sz
+    }
+
+    fn is_empty(&self) -> bool {
+        self.buf.is_none()
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        match &self.buf {
+            Some(buf) => &buf.as_bytes()[self.head..],
+            None => &[],
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.head += std::cmp::min(self.as_bytes().len(), amt);
+        self.release_if_consumed();
+    }
+
+    fn buffer_size(&self) -> usize {
+        self.pool.buffer_size()
+    }
+
+    fn handle_result(&mut self, _result: usize) {}
+
+    fn unfilled(&mut self) -> &mut [u8] {
+        &mut []
+    }
+
+    fn buffer_pool(&self) -> Option<&BufferPool> {
+        Some(&self.pool)
+    }
+
+    fn handle_buffer(&mut self, buf: DmaBuffer) {
+        self.buf = Some(buf);
+        self.head = 0;
+        self.release_if_consumed();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::net::{TcpListener, TcpStream, UdpSocket};
+    use futures_lite::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn pooled_stream_read() {
+        test_executor!(async move {
+            let pool = BufferPool::new(4, 16).unwrap();
+            assert_eq!(pool.capacity(), 4);
+            assert_eq!(pool.buffer_size(), 16);
+
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            let mut client = TcpStream::connect(addr).await.unwrap();
+            let server = listener.accept().await.unwrap();
+            let mut server = server.buffered_with(Pooled::new(&pool));
+
+            let data: Vec<u8> = (0..100).collect();
+            client.write_all(&data).await.unwrap();
+            client.write_all(b"\nrest").await.unwrap();
+
+            let mut received = vec![0; data.len()];
+            server.read_exact(&mut received).await.unwrap();
+            assert_eq!(received, data);
+
+            let mut line = String::new();
+            server.read_line(&mut line).await.unwrap();
+            assert_eq!(line, "\n");
+            assert_eq!(pool.available(), 3);
+
+            server.consume(4);
+            assert_eq!(pool.available(), 4);
+        });
+    }
+
+    #[test]
+    fn udp_recv_pooled() {
+        test_executor!(async move {
+            let pool = BufferPool::new(2, 64).unwrap();
+            let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+            receiver
+                .connect(sender.local_addr().unwrap())
+                .await
+                .unwrap();
+
+            sender
+                .send_to(b"hello", receiver.local_addr().unwrap())
+                .await
+                .unwrap();
+            let buf = receiver.recv_pooled(&pool).await.unwrap();
+            assert_eq!(buf.as_bytes(), b"hello");
+            assert_eq!(pool.available(), 1);
+            drop(buf);
+            assert_eq!(pool.available(), 2);
+        });
+    }
+}
@@ -4,7 +4,8 @@
 // This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2020 Datadog, Inc.
 //
 use crate::{
-    sys::{self, DmaBuffer, Source, SourceType},
+    net::BufferPool,
+    sys::{self, DmaBuffer, Source, SourceType, ZeroCopyBuffer},
     ByteSliceMutExt, Reactor,
 };
 use nix::sys::socket::MsgFlags;
@@ -119,6 +120,26 @@ impl<S: AsRawFd + FromRawFd + From<socket2::Socket>> GlommioDatagram<S> {
         }
     }
 
+    pub(crate) async fn recv_pooled(&self, pool: &BufferPool) -> io::Result<DmaBuffer> {
+        let source = self.reactor.upgrade().unwrap().recv_select(
+            self.socket.as_raw_fd(),
+            pool.group(),
+            self.read_timeout.get(),
+        );
+        source.collect_rw().await?;
+        match source.extract_source_type() {
+            SourceType::SockRecvSelect(_, Some(buf)) => Ok(buf),
+            // No buffer is consumed by a zero-length datagram
+            SourceType::SockRecvSelect(_, None) => {
+                let mut buf =
+                    DmaBuffer::new(1).ok_or_else(|| io::Error::from_raw_os_error(libc::ENOMEM))?;
+                buf.trim_to_size(0);
+                Ok(buf)
+            }
+            _ => unreachable!(),
+        }
+    }
+
     pub(crate) async fn recv_from_blocking(
         &self,
         buf: &mut [u8],
@@ -229,6 +250,15 @@ impl<S: AsRawFd + FromRawFd + From<socket2::Socket>> GlommioDatagram<S> {
         }
     }
 
+    pub(crate) async fn send_zc(&self, buf: ZeroCopyBuffer) -> io::Result<usize> {
+        let reactor = self.reactor.upgrade().unwrap();
+        if !reactor.supports_send_zc() {
+            return self.send(&buf).await;
+        }
+        let source = reactor.send_zc(self.socket.as_raw_fd(), buf, self.write_timeout.get());
+        source.collect_rw().await
+    }
+
     fn allocate_buffer(&self, size: usize) -> DmaBuffer {
         self.reactor.upgrade().unwrap().alloc_dma_buffer(size)
     }
@@ -0,0 +1,362 @@
+// Unless explicitly stated otherwise all files in this repository are licensed
+// under the MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021 Datadog, Inc.
+//
+//! A stub DNS resolver, which the resolver cache uses to learn how long the
+//! addresses of a name are valid for. The system resolver doesn't report it.
+use crate::net::UdpSocket;
+use socket2::{Domain, Protocol, Socket, Type};
+use std::{
+    collections::hash_map::RandomState,
+    convert::TryInto,
+    hash::{BuildHasher, Hasher},
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+
+/// How long to wait for the nameserver to answer.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Large enough for any answer sent over UDP without EDNS.
+const MAX_MESSAGE: usize = 512;
+
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+const CLASS_IN: u16 = 1;
+
+/// The addresses a name resolved to.
+#[derive(Debug, PartialEq)]
+pub(super) struct Lookup {
+    pub(super) addrs: Vec<IpAddr>,
+    /// How long the addresses are valid for, or `None` if they come from
+    /// `/etc/hosts`.
+    pub(super) ttl: Option<Duration>,
+}
+
+/// Resolves `host` with `/etc/hosts`, and then by querying the first
+/// nameserver of `/etc/resolv.conf` for its `A` and `AAAA` records.
+///
+/// Returns `None` if `host` can't be resolved this way, as the system
+/// resolver may still know about it: it also tries the search domains and
+/// the other sources of `/etc/nsswitch.conf`.
+pub(super) async fn lookup(host: &str) -> Option<Lookup> {
+    let name = host.to_owned();
+    let (hosts, server) = crate::executor()
+        .spawn_blocking(move || {
+            let hosts = std::fs::read_to_string("/etc/hosts")
+                .ok()
+                .and_then(|hosts| hosts_lookup(&hosts, &name));
+            let server = std::fs::read_to_string("/etc/resolv.conf")
+                .ok()
+                .and_then(|conf| nameserver(&conf));
+            (hosts, server)
+        })
+        .await;
+    if let Some(addrs) = hosts {
+        return Some(Lookup { addrs, ttl: None });
+    }
+    query(server?, host).await.ok().flatten()
+}
+
+/// The addresses `/etc/hosts` gives `host`, if any.
+fn hosts_lookup(hosts: &str, host: &str) -> Option<Vec<IpAddr>> {
+    let host = host.strip_suffix('.').unwrap_or(host);
+    let addrs: Vec<IpAddr> = hosts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('#').next()?.split_whitespace();
+            let ip = fields.next()?.parse().ok()?;
+            fields
+                .any(|name| name.eq_ignore_ascii_case(host))
+                .then(|| ip)
+        })
+        .collect();
+    if addrs.is_empty() {
+        None
+    } else {
+        Some(addrs)
+    }
+}
+
+/// The first nameserver of `/etc/resolv.conf`.
+fn nameserver(conf: &str) -> Option<SocketAddr> {
+    conf.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        match fields.next() {
+            Some("nameserver") => {
+                let ip: IpAddr = fields.next()?.parse().ok()?;
+                Some(SocketAddr::new(ip, 53))
+            }
+            _ => None,
+        }
+    })
+}
+
+/// Queries `server` for the `A` and `AAAA` records of `host`. Returns `None`
+/// if it has neither.
+async fn query(server: SocketAddr, host: &str) -> io::Result<Option<Lookup>> {
+    // Connecting a datagram socket doesn't wait for anything, and going
+    // through `UdpSocket::connect` would have this resolve `server`
+    let socket = Socket::new(
+        Domain::for_address(server),
+        Type::DGRAM,
+        Some(Protocol::UDP),
+    )?;
+    socket.connect(&server.into())?;
+    let socket = UdpSocket::from(socket);
+    socket.set_read_timeout(Some(QUERY_TIMEOUT))?;
+
+    // The ids only need to be hard to guess, to make spoofing answers harder
+    let id = RandomState::new().build_hasher().finish() as u16;
+    let ids = [id, id.wrapping_add(1)];
+    socket.send(&encode_query(ids[0], host, TYPE_A)?).await?;
+    socket.send(&encode_query(ids[1], host, TYPE_AAAA)?).await?;
+
+    let mut answers: [Option<Answer>; 2] = [None, None];
+    let mut buf = [0u8; MAX_MESSAGE];
+    while answers.iter().any(Option::is_none) {
+        let len = socket.recv(&mut buf).await?;
+        for (id, answer) in ids.iter().zip(answers.iter_mut()) {
+            if let Some(parsed) = parse_response(&buf[..len], *id) {
+                *answer = Some(parsed?);
+            }
+        }
+    }
+
+    let mut addrs = Vec::new();
+    let mut ttl = u32::MAX;
+    for answer in answers.iter().flatten() {
+        addrs.extend_from_slice(&answer.addrs);
+        ttl = std::cmp::min(ttl, answer.ttl);
+    }
+    Ok(match addrs.is_empty() {
+        true => None,
+        false => Some(Lookup {
+            addrs,
+            ttl: Some(Duration::from_secs(ttl.into())),
+        }),
+    })
+}
+
+/// The records of an answer to a query.
+#[derive(Debug)]
+struct Answer {
+    addrs: Vec<IpAddr>,
+    /// The smallest TTL of the records, including the aliases that led to
+    /// the addresses.
+    ttl: u32,
+}
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn encode_query(id: u16, host: &str, qtype: u16) -> io::Result<Vec<u8>> {
+    let mut msg = Vec::with_capacity(MAX_MESSAGE);
+    msg.extend_from_slice(&id.to_be_bytes());
+    // A standard query asking for recursion, with a single question
+    msg.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    let host = host.strip_suffix('.').unwrap_or(host);
+    for label in host.split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid domain name",
+            ));
+        }
+        msg.push(label.len() as u8);
+        msg.extend_from_slice(label.as_bytes());
+    }
+    msg.push(0);
+    if msg.len() > 12 + 255 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "domain name is too long",
+        ));
+    }
+    msg.extend_from_slice(&qtype.to_be_bytes());
+    msg.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(msg)
+}
+
+/// Parses `msg` if it is the response to the query `id`.
+fn parse_response(msg: &[u8], id: u16) -> Option<io::Result<Answer>> {
+    if msg.len() < 12 || u16_at(msg, 0)? != id || msg[2] & 0x80 == 0 {
+        return None;
+    }
+    Some(parse_records(msg))
+}
+
+fn parse_records(msg: &[u8]) -> io::Result<Answer> {
+    let truncated = || invalid("truncated DNS message");
+    let flags = u16_at(msg, 2).ok_or_else(truncated)?;
+    if flags & 0x0200 != 0 {
+        return Err(invalid("DNS answer doesn't fit in a datagram"));
+    }
+    match flags & 0xf {
+        0 => {}
+        3 => return Err(io::Error::new(io::ErrorKind::NotFound, "no such domain")),
+        _ => return Err(invalid("DNS query failed")),
+    }
+    let questions = u16_at(msg, 4).ok_or_else(truncated)?;
+    let records = u16_at(msg, 6).ok_or_else(truncated)?;
+
+    let mut pos = 12;
+    for _ in 0..questions {
+        pos = skip_name(msg, pos).ok_or_else(truncated)? + 4;
+    }
+    let mut answer = Answer {
+        addrs: Vec::new(),
+        ttl: u32::MAX,
+    };
+    for _ in 0..records {
+        pos = skip_name(msg, pos).ok_or_else(truncated)?;
+        let header = msg.get(pos..pos + 10).ok_or_else(truncated)?;
+        let rtype = u16::from_be_bytes([header[0], header[1]]);
+        let class = u16::from_be_bytes([header[2], header[3]]);
+        let ttl = u32::from_be_bytes(header[4..8].try_into().unwrap());
+        let len = u16::from_be_bytes([header[8], header[9]]) as usize;
+        let data = msg.get(pos + 10..pos + 10 + len).ok_or_else(truncated)?;
+        pos += 10 + len;
+        if class != CLASS_IN {
+            continue;
+        }
+        // Aliases are followed by the server, which answers with the records
+        // of the whole chain
+        answer.ttl = std::cmp::min(answer.ttl, ttl);
+        match (rtype, data.len()) {
+            (TYPE_A, 4) => {
+                let octets: [u8; 4] = data.try_into().unwrap();
+                answer.addrs.push(Ipv4Addr::from(octets).into());
+            }
+            (TYPE_AAAA, 16) => {
+                let octets: [u8; 16] = data.try_into().unwrap();
+                answer.addrs.push(Ipv6Addr::from(octets).into());
+            }
+            _ => {}
+        }
+    }
+    Ok(answer)
+}
+
+/// Returns the position right after the name at `pos`.
+fn skip_name(msg: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let len = *msg.get(pos)? as usize;
+        match len & 0xc0 {
+            // A pointer to the rest of the name
+            0xc0 => return Some(pos + 2).filter(|x| *x <= msg.len()),
+            0 if len == 0 => return Some(pos + 1),
+            0 => pos += 1 + len,
+            _ => return None,
+        }
+    }
+}
+
+fn u16_at(msg: &[u8], pos: usize) -> Option<u16> {
+    let bytes = msg.get(pos..pos + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hosts_and_resolv_conf() {
+        let hosts = "127.0.0.1 localhost\n\
+                     # 10.0.0.1 commented\n\
+                     ::1 localhost ip6-localhost # trailing comment\n\
+                     10.0.0.2 db.internal db\n";
+        assert_eq!(
+            hosts_lookup(hosts, "LOCALHOST."),
+            Some(vec![
+                IpAddr::from(Ipv4Addr::LOCALHOST),
+                Ipv6Addr::LOCALHOST.into()
+            ])
+        );
+        assert_eq!(
+            hosts_lookup(hosts, "db"),
+            Some(vec![IpAddr::from([10, 0, 0, 2])])
+        );
+        assert_eq!(hosts_lookup(hosts, "commented"), None);
+
+        let conf = "search example.com\nnameserver fe80::1%eth0\nnameserver 10.0.0.53\n";
+        assert_eq!(nameserver(conf), Some("10.0.0.53:53".parse().unwrap()));
+        assert_eq!(nameserver("search example.com\n"), None);
+    }
+
+    /// Answers a query the way a nameserver would, with `records` of type
+    /// `rtype`, all valid for `ttl` seconds.
+    fn respond(query: &[u8], records: &[(u16, &[u8])], ttl: u32) -> Vec<u8> {
+        let mut msg = query.to_vec();
+        msg[2] |= 0x80;
+        msg[3] |= 0x80;
+        msg[6..8].copy_from_slice(&(records.len() as u16).to_be_bytes());
+        for (rtype, data) in records {
+            // The name of the question
+            msg.extend_from_slice(&[0xc0, 12]);
+            msg.extend_from_slice(&rtype.to_be_bytes());
+            msg.extend_from_slice(&CLASS_IN.to_be_bytes());
+            msg.extend_from_slice(&ttl.to_be_bytes());
+            msg.extend_from_slice(&(data.len() as u16).to_be_bytes());
+            msg.extend_from_slice(data);
+        }
+        msg
+    }
+
+    #[test]
+    fn parse_answers() {
+        let query = encode_query(7, "www.example.com.", TYPE_A).unwrap();
+        assert_eq!(&query[12..17], b"\x03www\x07");
+        assert!(encode_query(7, "www..com", TYPE_A).is_err());
+
+        let cname = b"\x03cdn\xc0\x10";
+        let msg = respond(&query, &[(5, cname), (TYPE_A, &[10, 0, 0, 1])], 30);
+        assert!(parse_response(&msg, 8).is_none());
+        let answer = parse_response(&msg, 7).unwrap().unwrap();
+        assert_eq!(answer.addrs, vec![IpAddr::from([10, 0, 0, 1])]);
+        assert_eq!(answer.ttl, 30);
+
+        let mut nxdomain = respond(&query, &[], 0);
+        nxdomain[3] |= 3;
+        let err = parse_response(&nxdomain, 7).unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+        let msg = respond(&query, &[(TYPE_A, &[10, 0, 0, 1])], 30);
+        assert!(parse_response(&msg[..msg.len() - 1], 7).unwrap().is_err());
+    }
+
+    #[test]
+    fn query_nameserver() {
+        test_executor!(async move {
+            let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let addr = server.local_addr().unwrap();
+            let nameserver = crate::spawn_local(async move {
+                let mut buf = [0u8; MAX_MESSAGE];
+                for _ in 0..2 {
+                    let (len, from) = server.recv_from(&mut buf).await.unwrap();
+                    let query = &buf[..len];
+                    let qtype = u16_at(query, len - 4).unwrap();
+                    let msg = match qtype {
+                        TYPE_A => respond(query, &[(TYPE_A, &[10, 0, 0, 1])], 30),
+                        _ => respond(query, &[], 0),
+                    };
+                    server.send_to(&msg, from).await.unwrap();
+                }
+            });
+
+            let lookup = query(addr, "db.example.com").await.unwrap().unwrap();
+            assert_eq!(
+                lookup,
+                Lookup {
+                    addrs: vec![IpAddr::from([10, 0, 0, 1])],
+                    ttl: Some(Duration::from_secs(30)),
+                }
+            );
+            nameserver.await;
+        });
+    }
+}
@@ -0,0 +1,221 @@
+// Unless explicitly stated otherwise all files in this repository are licensed
+// under the MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021 Datadog, Inc.
+//
+use crate::{net::tcp_socket::make_tcp_socket, reactor::Reactor, timer::Timer};
+use futures_lite::future::poll_fn;
+use socket2::Socket;
+use std::{
+    fmt, future::Future, io, net::SocketAddr, os::unix::io::AsRawFd, pin::Pin, rc::Rc, task::Poll,
+    time::Duration,
+};
+
+/// Options of [`TcpStream::connect_happy_eyeballs`].
+///
+/// [`TcpStream::connect_happy_eyeballs`]: crate::net::TcpStream::connect_happy_eyeballs
+#[derive(Debug, Clone, Copy)]
+pub struct HappyEyeballsOptions {
+    attempt_delay: Duration,
+    timeout: Option<Duration>,
+}
+
+impl Default for HappyEyeballsOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HappyEyeballsOptions {
+    /// Creates the default options: attempts are started 250 milliseconds
+    /// apart, as recommended by RFC 8305, and there is no overall timeout.
+    pub fn new() -> HappyEyeballsOptions {
+        HappyEyeballsOptions {
+            attempt_delay: Duration::from_millis(250),
+            timeout: None,
+        }
+    }
+
+    /// How long to wait for an attempt to connect before starting the next
+    /// one, if it didn't fail earlier. RFC 8305 recommends no less than 100
+    /// milliseconds.
+    #[must_use = "The options must be passed to connect_happy_eyeballs to be useful"]
+    pub fn attempt_delay(mut self, delay: Duration) -> HappyEyeballsOptions {
+        self.attempt_delay = delay;
+        self
+    }
+
+    /// How long to wait overall for a connection to be made. The attempts
+    /// still in flight then fail with [`io::ErrorKind::TimedOut`].
+    #[must_use = "The options must be passed to connect_happy_eyeballs to be useful"]
+    pub fn timeout(mut self, timeout: Duration) -> HappyEyeballsOptions {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/// The error returned by [`TcpStream::connect_happy_eyeballs`] when none of
+/// the addresses could be connected to, with the failure of each attempt.
+///
+/// It's the inner error of the [`io::Error`] that is returned, which can be
+/// retrieved with [`io::Error::get_ref`]:
+///
+/// ```no_run
+/// use glommio::{
+///     net::{HappyEyeballsError, HappyEyeballsOptions, TcpStream},
+///     GlommioError,
+///     LocalExecutor,
+/// };
+///
+/// let ex = LocalExecutor::default();
+/// ex.run(async move {
+///     let opts = HappyEyeballsOptions::new();
+///     if let Err(GlommioError::IoError(err)) =
+///         TcpStream::connect_happy_eyeballs("example.com:80", opts).await
+///     {
+///         if let Some(err) = err.get_ref().and_then(|x| x.downcast_ref::<HappyEyeballsError>()) {
+///             for (addr, err) in err.failures() {
+///                 println!("{}: {}", addr, err);
+///             }
+///         }
+///     }
+/// });
+/// ```
+///
+/// [`TcpStream::connect_happy_eyeballs`]: crate::net::TcpStream::connect_happy_eyeballs
+#[derive(Debug)]
+pub struct HappyEyeballsError {
+    failures: Vec<(SocketAddr, io::Error)>,
+}
+
+impl HappyEyeballsError {
+    /// The addresses that were attempted, in the order in which they failed,
+    /// and the error of each.
+    pub fn failures(&self) -> &[(SocketAddr, io::Error)] {
+        &self.failures
+    }
+}
+
+impl fmt::Display for HappyEyeballsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to connect to any address")?;
+        for (i, (addr, err)) in self.failures.iter().enumerate() {
+            let sep = if i == 0 { ": " } else { ", " };
+            write!(f, "{sep}{addr} ({err})")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for HappyEyeballsError {}
+
+type Attempt = Pin<Box<dyn Future<Output = io::Result<Socket>>>>;
+
+async fn attempt(reactor: Rc<Reactor>, addr: SocketAddr) -> io::Result<Socket> {
+    let (addr, socket) = make_tcp_socket(&addr)?;
+    let source = reactor.connect(socket.as_raw_fd(), addr);
+    source.collect_rw().await?;
+    Ok(socket)
+}
+
+/// Orders addresses by alternating between address families, starting with
+/// the family of the first one, as described in section 4 of RFC 8305.
+fn interleave(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let first_v6 = addrs.first().map_or(false, |x| x.is_ipv6());
+    let (preferred, other): (Vec<_>, Vec<_>) =
+        addrs.into_iter().partition(|x| x.is_ipv6() == first_v6);
+    let (mut preferred, mut other) = (preferred.into_iter(), other.into_iter());
+    let mut ret = Vec::new();
+    loop {
+        match (preferred.next(), other.next()) {
+            (None, None) => return ret,
+            (a, b) => ret.extend(a.into_iter().chain(b)),
+        }
+    }
+}
+
+/// Races connections to `addrs`, starting them one after the other, until
+/// one succeeds. Dropping the attempts that lose cancels their requests.
+pub(super) async fn connect(
+    addrs: Vec<SocketAddr>,
+    opts: HappyEyeballsOptions,
+) -> io::Result<Socket> {
+    let reactor = crate::executor().reactor();
+    let mut pending = interleave(addrs).into_iter().peekable();
+    let mut attempts: Vec<(SocketAddr, Attempt)> = Vec::new();
+    let mut failures = Vec::new();
+    let mut delay = Timer::new(opts.attempt_delay);
+    let mut deadline = opts.timeout.map(Timer::new);
+    let mut timed_out = false;
+
+    let socket = poll_fn(|cx| {
+        loop {
+            let mut i = 0;
+            while i < attempts.len() {
+                match attempts[i].1.as_mut().poll(cx) {
+                    Poll::Ready(Ok(socket)) => return Poll::Ready(Some(socket)),
+                    Poll::Ready(Err(err)) => {
+                        let (addr, _) = attempts.remove(i);
+                        failures.push((addr, err));
+                    }
+                    Poll::Pending => i += 1,
+                }
+            }
+            if pending.peek().is_none() {
+                if attempts.is_empty() {
+                    return Poll::Ready(None);
+                }
+                break;
+            }
+            // The next attempt starts once the previous one failed, or after
+            // the attempt delay
+            if !attempts.is_empty() && Pin::new(&mut delay).poll(cx).is_pending() {
+                break;
+            }
+            let addr = pending.next().unwrap();
+            attempts.push((addr, Box::pin(attempt(reactor.clone(), addr))));
+            delay.reset(opts.attempt_delay);
+        }
+        timed_out = deadline
+            .as_mut()
+            .map_or(false, |x| Pin::new(x).poll(cx).is_ready());
+        if timed_out {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    })
+    .await;
+
+    if let Some(socket) = socket {
+        return Ok(socket);
+    }
+    // Cancels the attempts still in flight
+    for (addr, _) in attempts.drain(..) {
+        let err = io::Error::new(io::ErrorKind::TimedOut, "connection timed out");
+        failures.push((addr, err));
+    }
+    let kind = match failures.last() {
+        Some((_, err)) if !timed_out => err.kind(),
+        _ => io::ErrorKind::TimedOut,
+    };
+    Err(io::Error::new(kind, HappyEyeballsError { failures }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn interleave_families() {
+        let v4: Vec<SocketAddr> = vec![
+            "127.0.0.1:1".parse().unwrap(),
+            "127.0.0.2:1".parse().unwrap(),
+        ];
+        let v6: Vec<SocketAddr> = vec!["[::1]:1".parse().unwrap(), "[::2]:1".parse().unwrap()];
+        let addrs = vec![v6[0], v6[1], v4[0], v4[1]];
+        assert_eq!(interleave(addrs), vec![v6[0], v4[0], v6[1], v4[1]]);
+        let addrs = vec![v4[0], v4[1], v6[0]];
+        assert_eq!(interleave(addrs), vec![v4[0], v6[0], v4[1]]);
+    }
+}
@@ -4,9 +4,19 @@
 // This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2020 Datadog, Inc.
 //
 //! This module provides glommio's networking support.
-use crate::sys;
+use crate::{
+    reactor::Reactor,
+    sys::{self, DmaBuffer},
+};
+use futures_lite::Stream;
 use nix::sys::socket::MsgFlags;
-use std::{io, os::unix::io::RawFd};
+use std::{
+    io,
+    os::unix::io::RawFd,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
 
 fn yolo_accept(fd: RawFd) -> Option<io::Result<RawFd>> {
     let flags =
@@ -106,14 +116,132 @@ fn yolo_sendmsg(
     }
 }
 
+/// Connections accepted by a single multishot accept request. The request is
+/// armed again whenever the kernel terminates it.
+struct MultishotAccept {
+    reactor: Rc<Reactor>,
+    fd: RawFd,
+    source: Option<sys::Source>,
+}
+
+impl MultishotAccept {
+    /// Returns `None` if the kernel does not support multishot accepts.
+    fn new(reactor: Rc<Reactor>, fd: RawFd) -> Option<MultishotAccept> {
+        let source = reactor.accept_multi(fd)?;
+        Some(MultishotAccept {
+            reactor,
+            fd,
+            source: Some(source),
+        })
+    }
+}
+
+impl Stream for MultishotAccept {
+    type Item = io::Result<RawFd>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let this = &mut *self;
+            let source = match this.source.as_ref() {
+                Some(source) => source,
+                None => this
+                    .source
+                    .insert(this.reactor.accept_multi(this.fd).unwrap()),
+            };
+            match source.poll_multishot(cx) {
+                Poll::Ready(Some((res, _))) => return Poll::Ready(Some(res.map(|x| x as RawFd))),
+                Poll::Ready(None) => this.source = None,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Buffers received by a single multishot receive request. The request is
+/// armed again whenever the kernel terminates it, like when the pool runs out
+/// of buffers.
+struct MultishotRecv {
+    reactor: Rc<Reactor>,
+    fd: RawFd,
+    pool: BufferPool,
+    // Receive with recvmsg, so buffers also carry the address of the sender.
+    // See `sys::parse_recvmsg_out`.
+    msg: bool,
+    source: Option<sys::Source>,
+    done: bool,
+}
+
+impl MultishotRecv {
+    /// Returns `None` if the kernel does not support multishot receives.
+    fn new(reactor: Rc<Reactor>, fd: RawFd, pool: &BufferPool, msg: bool) -> Option<MultishotRecv> {
+        let source = Self::arm(&reactor, fd, pool, msg)?;
+        Some(MultishotRecv {
+            reactor,
+            fd,
+            pool: pool.clone(),
+            msg,
+            source: Some(source),
+            done: false,
+        })
+    }
+
+    fn arm(reactor: &Reactor, fd: RawFd, pool: &BufferPool, msg: bool) -> Option<sys::Source> {
+        if msg {
+            reactor.recvmsg_multi(fd, pool.group())
+        } else {
+            reactor.recv_multi(fd, pool.group())
+        }
+    }
+}
+
+impl Stream for MultishotRecv {
+    type Item = io::Result<DmaBuffer>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let this = &mut *self;
+            if this.done {
+                return Poll::Ready(None);
+            }
+            let source = match this.source.as_ref() {
+                Some(source) => source,
+                None => this
+                    .source
+                    .insert(Self::arm(&this.reactor, this.fd, &this.pool, this.msg).unwrap()),
+            };
+            match source.poll_multishot(cx) {
+                Poll::Ready(Some((Err(err), _))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(Some((Ok(len), flags))) => {
+                    match this.pool.group().take(flags, len) {
+                        Some(buf) if len > 0 => return Poll::Ready(Some(Ok(buf))),
+                        // an empty receive on a stream means the peer closed it
+                        _ => this.done = !this.msg,
+                    }
+                }
+                Poll::Ready(None) => this.source = None,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+mod buffer_pool;
 mod datagram;
+mod dns;
+mod happy_eyeballs;
+mod resolve;
+mod splice;
 mod stream;
 mod tcp_socket;
 mod udp_socket;
 mod unix;
 pub use self::{
+    buffer_pool::{BufferPool, Pooled},
+    happy_eyeballs::{HappyEyeballsError, HappyEyeballsOptions},
+    resolve::{resolve, set_resolve_cache_ttl},
+    splice::splice,
     stream::{Buffered, Preallocated},
-    tcp_socket::{AcceptedTcpStream, TcpListener, TcpStream},
+    tcp_socket::{AcceptedTcpStream, TcpListener, TcpSocket, TcpStream},
     udp_socket::UdpSocket,
     unix::{AcceptedUnixStream, UnixDatagram, UnixListener, UnixStream},
 };
@@ -0,0 +1,305 @@
+// Unless explicitly stated otherwise all files in this repository are licensed
+// under the MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021 Datadog, Inc.
+//
+use super::dns;
+use ahash::AHashMap;
+use std::{
+    any::Any,
+    cell::RefCell,
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6, ToSocketAddrs},
+    time::{Duration, Instant},
+};
+
+type Result<T> = crate::Result<T, ()>;
+
+#[derive(Debug, Default)]
+struct ResolveCache {
+    ttl: Option<Duration>,
+    // When each entry expires
+    entries: AHashMap<(String, u16), (Instant, Vec<SocketAddr>)>,
+}
+
+thread_local! {
+    static CACHE: RefCell<ResolveCache> = RefCell::new(ResolveCache::default());
+}
+
+/// Resolves `host` to the socket addresses it designates, with `port`.
+///
+/// `host` is either an IP address, which is returned as is, or a name, which
+/// is resolved with the system resolver (`getaddrinfo`). Resolving a name can
+/// block for as long as the resolver takes to answer, so it's done on the
+/// blocking thread pool of the executor instead of stalling it.
+///
+/// Names are resolved again on every call, unless the resolver cache of the
+/// executor is enabled with [`set_resolve_cache_ttl`].
+///
+/// This is what [`TcpStream::connect`], [`UdpSocket::connect`] and
+/// [`UdpSocket::send_to`] use to resolve the addresses they are given.
+///
+/// # Examples
+///
+/// ```no_run
+/// use glommio::{net, LocalExecutor};
+///
+/// let ex = LocalExecutor::default();
+/// ex.run(async move {
+///     let addrs = net::resolve("localhost", 8000).await.unwrap();
+///     println!("localhost is {:?}", addrs);
+/// });
+/// ```
+///
+/// [`TcpStream::connect`]: crate::net::TcpStream::connect
+/// [`UdpSocket::connect`]: crate::net::UdpSocket::connect
+/// [`UdpSocket::send_to`]: crate::net::UdpSocket::send_to
+pub async fn resolve(host: &str, port: u16) -> Result<Vec<SocketAddr>> {
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Ok(vec![SocketAddr::new(ip, port)]);
+    }
+    resolve_name(host.to_owned(), port)
+        .await
+        .map_err(Into::into)
+}
+
+/// Enables the resolver cache of the executor, whose entries are kept for
+/// at most `ttl`, or disables it if `None`. The cache is disabled by default.
+///
+/// The system resolver doesn't report how long the addresses it returns are
+/// valid for, so while the cache is enabled names are resolved with
+/// `/etc/hosts` and by querying the first nameserver of `/etc/resolv.conf`
+/// instead. Entries then expire once the TTL of their DNS records elapsed,
+/// if that is sooner than `ttl`. Names that can't be resolved this way, like
+/// those that need a search domain, are resolved by the system resolver, and
+/// kept for `ttl`. Failed lookups are never cached, and expired entries are
+/// dropped whenever a new one is added.
+///
+/// The cache belongs to the executor that calls this function, and only the
+/// names resolved on it are cached. Changing the TTL clears the cache.
+pub fn set_resolve_cache_ttl(ttl: Option<Duration>) {
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        cache.ttl = ttl;
+        cache.entries.clear();
+    });
+}
+
+async fn resolve_name(host: String, port: u16) -> io::Result<Vec<SocketAddr>> {
+    let key = (host, port);
+    let (ttl, cached) = CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let cached = match cache.entries.get(&key) {
+            Some((expires, addrs)) if *expires > Instant::now() => Some(addrs.clone()),
+            Some(_) => {
+                cache.entries.remove(&key);
+                None
+            }
+            None => None,
+        };
+        (cache.ttl, cached)
+    });
+    if let Some(addrs) = cached {
+        return Ok(addrs);
+    }
+    let ttl = match ttl {
+        Some(ttl) => ttl,
+        None => return system_lookup(key).await,
+    };
+
+    let (addrs, ttl) = match dns::lookup(&key.0).await {
+        Some(lookup) => {
+            let addrs = lookup
+                .addrs
+                .into_iter()
+                .map(|ip| SocketAddr::new(ip, port))
+                .collect();
+            (addrs, lookup.ttl.map_or(ttl, |x| std::cmp::min(x, ttl)))
+        }
+        None => (system_lookup(key.clone()).await?, ttl),
+    };
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        // The cache may have been disabled or changed in the meantime
+        if cache.ttl.is_some() {
+            // Otherwise the entries of names that are never looked up again
+            // would pile up
+            let now = Instant::now();
+            cache.entries.retain(|_, (expires, _)| *expires > now);
+            cache.entries.insert(key, (now + ttl, addrs.clone()));
+        }
+    });
+    Ok(addrs)
+}
+
+/// Resolves a name with the system resolver, on the blocking thread pool.
+async fn system_lookup(name: (String, u16)) -> io::Result<Vec<SocketAddr>> {
+    let addrs = crate::executor()
+        .spawn_blocking(move || {
+            name.to_socket_addrs()
+                .map(|addrs| addrs.collect::<Vec<_>>())
+        })
+        .await?;
+    if addrs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "could not resolve to any addresses",
+        ));
+    }
+    Ok(addrs)
+}
+
+/// Addresses given to the networking APIs, which are either already resolved
+/// or a name to resolve.
+#[derive(Debug)]
+enum Addrs {
+    Resolved(Vec<SocketAddr>),
+    Name(String, u16),
+}
+
+/// The addresses `addr` designates, or the name it holds, if it is of one of
+/// the types the standard library implements [`ToSocketAddrs`] for.
+fn known_addrs(addr: &dyn Any) -> Option<io::Result<Addrs>> {
+    macro_rules! resolved {
+        ($($ty:ty),*) => {
+            $(
+                if let Some(addr) = addr.downcast_ref::<$ty>() {
+                    return Some(Ok(Addrs::Resolved(vec![SocketAddr::from(*addr)])));
+                }
+            )*
+        };
+    }
+
+    resolved!(
+        SocketAddr,
+        SocketAddrV4,
+        SocketAddrV6,
+        (IpAddr, u16),
+        (Ipv4Addr, u16),
+        (Ipv6Addr, u16)
+    );
+    if let Some(addrs) = addr.downcast_ref::<&[SocketAddr]>() {
+        return Some(Ok(Addrs::Resolved(addrs.to_vec())));
+    }
+    if let Some(addr) = addr.downcast_ref::<&str>() {
+        return Some(str_addrs(addr));
+    }
+    if let Some(addr) = addr.downcast_ref::<String>() {
+        return Some(str_addrs(addr));
+    }
+    if let Some((host, port)) = addr.downcast_ref::<(&str, u16)>() {
+        return Some(Ok(host_addrs(host, *port)));
+    }
+    if let Some((host, port)) = addr.downcast_ref::<(String, u16)>() {
+        return Some(Ok(host_addrs(host, *port)));
+    }
+    None
+}
+
+fn host_addrs(host: &str, port: u16) -> Addrs {
+    match host.parse::<IpAddr>() {
+        Ok(ip) => Addrs::Resolved(vec![SocketAddr::new(ip, port)]),
+        Err(_) => Addrs::Name(host.to_owned(), port),
+    }
+}
+
+fn str_addrs(addr: &str) -> io::Result<Addrs> {
+    if let Ok(addr) = addr.parse::<SocketAddr>() {
+        return Ok(Addrs::Resolved(vec![addr]));
+    }
+    let invalid = |msg| io::Error::new(io::ErrorKind::InvalidInput, msg);
+    let (host, port) = addr
+        .rsplit_once(':')
+        .ok_or_else(|| invalid("invalid socket address"))?;
+    let port = port.parse().map_err(|_| invalid("invalid port value"))?;
+    Ok(host_addrs(host, port))
+}
+
+/// Resolves `addr` without blocking the executor.
+///
+/// Names held by the types of the standard library are resolved with
+/// [`resolve`], and go through the cache. Other implementations of
+/// [`ToSocketAddrs`] are run on the blocking thread pool, as they may block.
+pub(crate) async fn resolve_addrs<A: ToSocketAddrs + Send + 'static>(
+    addr: A,
+) -> io::Result<Vec<SocketAddr>> {
+    let addrs = match known_addrs(&addr) {
+        Some(Ok(Addrs::Resolved(addrs))) => addrs,
+        Some(Ok(Addrs::Name(host, port))) => return resolve_name(host, port).await,
+        Some(Err(err)) => return Err(err),
+        None => {
+            crate::executor()
+                .spawn_blocking(move || addr.to_socket_addrs().map(Iterator::collect))
+                .await?
+        }
+    };
+    if addrs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "could not resolve to any addresses",
+        ));
+    }
+    Ok(addrs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resolve_addresses() {
+        test_executor!(async move {
+            let addrs = resolve("127.0.0.1", 80).await.unwrap();
+            assert_eq!(addrs, vec!["127.0.0.1:80".parse().unwrap()]);
+
+            let addrs = resolve("localhost", 80).await.unwrap();
+            assert!(addrs.iter().all(|x| x.ip().is_loopback() && x.port() == 80));
+
+            let addrs = resolve_addrs("localhost:81").await.unwrap();
+            assert!(addrs.iter().all(|x| x.ip().is_loopback() && x.port() == 81));
+            assert!(resolve_addrs("localhost").await.is_err());
+            assert!(resolve_addrs("localhost:port").await.is_err());
+
+            // types the standard library doesn't know about are resolved on
+            // the blocking thread pool
+            struct Loopback;
+            impl ToSocketAddrs for Loopback {
+                type Iter = std::option::IntoIter<SocketAddr>;
+                fn to_socket_addrs(&self) -> io::Result<Self::Iter> {
+                    Ok(Some(SocketAddr::from((Ipv4Addr::LOCALHOST, 82))).into_iter())
+                }
+            }
+            let addrs = resolve_addrs(Loopback).await.unwrap();
+            assert_eq!(addrs, vec!["127.0.0.1:82".parse().unwrap()]);
+        });
+    }
+
+    #[test]
+    fn resolve_cache() {
+        test_executor!(async move {
+            set_resolve_cache_ttl(Some(Duration::from_secs(60)));
+            let addrs = resolve("localhost", 80).await.unwrap();
+            CACHE.with(|cache| {
+                let cache = cache.borrow();
+                let (_, cached) = &cache.entries[&("localhost".to_owned(), 80)];
+                assert_eq!(cached, &addrs);
+            });
+            assert_eq!(resolve("localhost", 80).await.unwrap(), addrs);
+
+            // Expired entries go away when others are added
+            set_resolve_cache_ttl(Some(Duration::from_millis(10)));
+            resolve("localhost", 80).await.unwrap();
+            crate::timer::sleep(Duration::from_millis(20)).await;
+            resolve("localhost", 81).await.unwrap();
+            CACHE.with(|cache| {
+                let cache = cache.borrow();
+                assert_eq!(cache.entries.len(), 1);
+                assert!(cache.entries.contains_key(&("localhost".to_owned(), 81)));
+            });
+
+            set_resolve_cache_ttl(None);
+            resolve("localhost", 80).await.unwrap();
+            CACHE.with(|cache| assert!(cache.borrow().entries.is_empty()));
+        });
+    }
+}
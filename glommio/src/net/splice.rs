@@ -0,0 +1,79 @@
+// Unless explicitly stated otherwise all files in this repository are licensed
+// under the MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021 Datadog, Inc.
+//
+use crate::{
+    io::Pipe,
+    net::{stream::RxBuf, TcpStream},
+};
+use std::os::unix::io::AsRawFd;
+
+type Result<T> = crate::Result<T, ()>;
+
+/// Forwards up to `len` bytes from one [`TcpStream`] to another without
+/// copying them into user space.
+///
+/// The bytes go through a [`Pipe`] that is created for the duration of the
+/// call, using `IORING_OP_SPLICE` on both ends. The transfer stops early
+/// only if `from` is closed by its peer, and returns how many bytes were
+/// forwarded.
+///
+/// `from` must not be buffered: bytes already sitting in a user space
+/// receive buffer would be skipped, so only [`TcpStream`]s that read
+/// straight from the socket are accepted.
+///
+/// # Examples
+///
+/// ```no_run
+/// use glommio::{
+///     net::{self, TcpListener, TcpStream},
+///     LocalExecutor,
+/// };
+///
+/// let ex = LocalExecutor::default();
+/// ex.run(async {
+///     let listener = TcpListener::bind("127.0.0.1:8000").unwrap();
+///     let client = listener.accept().await.unwrap();
+///     let upstream = TcpStream::connect("127.0.0.1:9000").await.unwrap();
+///     net::splice(&client, &upstream, 1 << 20).await.unwrap();
+/// });
+/// ```
+pub async fn splice<B: RxBuf>(from: &TcpStream, to: &TcpStream<B>, len: usize) -> Result<usize> {
+    let pipe = Pipe::new()?;
+    pipe.forward(from.as_raw_fd(), None, to.as_raw_fd(), len)
+        .await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::net::TcpListener;
+    use futures_lite::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn splice_between_streams() {
+        test_executor!(async move {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            let mut client = TcpStream::connect(addr).await.unwrap();
+            let proxy_in = listener.accept().await.unwrap();
+            let proxy_out = TcpStream::connect(addr).await.unwrap();
+            let mut server = listener.accept().await.unwrap();
+
+            let data: Vec<u8> = (0..100_000).map(|x| x as u8).collect();
+            client.write_all(&data).await.unwrap();
+            let forwarded = splice(&proxy_in, &proxy_out, data.len()).await.unwrap();
+            assert_eq!(forwarded, data.len());
+
+            let mut received = vec![0; data.len()];
+            server.read_exact(&mut received).await.unwrap();
+            assert_eq!(received, data);
+
+            // a closed peer ends the transfer early
+            client.write_all(b"bye").await.unwrap();
+            client.close().await.unwrap();
+            assert_eq!(splice(&proxy_in, &proxy_out, 100).await.unwrap(), 3);
+        });
+    }
+}
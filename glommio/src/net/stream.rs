@@ -4,10 +4,11 @@
 // This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2020 Datadog, Inc.
 //
 use crate::{
+    net::BufferPool,
     reactor::Reactor,
-    sys::{self, Source, SourceType},
+    sys::{self, DmaBuffer, Source, SourceType, ZeroCopyBuffer},
 };
-use futures_lite::ready;
+use futures_lite::{future, ready};
 use nix::sys::socket::MsgFlags;
 use std::{
     cell::Cell,
@@ -31,6 +32,16 @@ pub trait RxBuf {
     fn buffer_size(&self) -> usize;
     fn handle_result(&mut self, result: usize);
     fn unfilled(&mut self) -> &mut [u8];
+
+    /// The pool the kernel should pick receive buffers from, if any. When
+    /// set, data is received into buffers of the pool instead of
+    /// [`unfilled`](RxBuf::unfilled).
+    fn buffer_pool(&self) -> Option<&BufferPool> {
+        None
+    }
+
+    /// Hands over a buffer picked from [`buffer_pool`](RxBuf::buffer_pool).
+    fn handle_buffer(&mut self, _buf: DmaBuffer) {}
 }
 
 #[derive(Debug, Default)]
@@ -215,12 +226,30 @@ impl Timeout {
     }
 }
 
+/// Keeps a socket in the fixed file table of the executor until dropped.
+#[derive(Debug)]
+struct FixedFile {
+    reactor: Weak<Reactor>,
+    fd: RawFd,
+}
+
+impl Drop for FixedFile {
+    fn drop(&mut self) {
+        if let Some(reactor) = self.reactor.upgrade() {
+            reactor.unregister_file(self.fd);
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct NonBufferedStream<S> {
     reactor: Weak<Reactor>,
+    // Declared before `stream` so the socket leaves the table before closing
+    fixed: Option<FixedFile>,
     stream: S,
     source_tx: Option<Source>,
     source_rx: Option<Source>,
+    source_pooled: Option<Source>,
     write_timeout: Timeout,
     read_timeout: Timeout,
 }
@@ -232,6 +261,18 @@ impl<S: AsRawFd> NonBufferedStream<S> {
         self.source_rx = Some(reactor.poll_read_ready(stream_fd));
     }
 
+    pub(crate) fn register(&mut self) -> io::Result<()> {
+        if self.fixed.is_none() {
+            let fd = self.stream.as_raw_fd();
+            self.reactor.upgrade().unwrap().register_file(fd)?;
+            self.fixed = Some(FixedFile {
+                reactor: self.reactor.clone(),
+                fd,
+            });
+        }
+        Ok(())
+    }
+
     pub(crate) fn try_peek(&self, buf: &mut [u8]) -> Option<io::Result<usize>> {
         super::yolo_peek(self.stream.as_raw_fd(), buf)
     }
@@ -298,6 +339,38 @@ impl<S: AsRawFd> NonBufferedStream<S> {
         Poll::Pending
     }
 
+    /// Receives into a buffer the kernel picks from `pool`. Returns `None`
+    /// once the peer closed the stream.
+    pub(crate) fn poll_recv_pooled(
+        &mut self,
+        cx: &mut Context<'_>,
+        pool: &BufferPool,
+    ) -> Poll<io::Result<Option<DmaBuffer>>> {
+        let reactor = self.reactor.upgrade().unwrap();
+        let reactor = reactor.as_ref();
+
+        if self.source_pooled.is_none() {
+            self.source_pooled =
+                Some(reactor.recv_select(self.stream.as_raw_fd(), pool.group(), None));
+        }
+
+        let source = self.source_pooled.as_ref().unwrap();
+        if let Some(result) = source.result() {
+            self.read_timeout.cancel_timer(reactor);
+            let source = self.source_pooled.take().unwrap();
+            let sz = poll_err!(result);
+            return match source.extract_source_type() {
+                SourceType::SockRecvSelect(_, buf) => Poll::Ready(Ok(buf.filter(|_| sz > 0))),
+                _ => unreachable!(),
+            };
+        }
+
+        poll_err!(self.read_timeout.check(reactor));
+        source.add_waiter_single(cx.waker());
+        self.read_timeout.maybe_set_timer(reactor, cx.waker());
+        Poll::Pending
+    }
+
     pub(crate) fn poll_write(
         &mut self,
         cx: &mut Context<'_>,
@@ -334,6 +407,15 @@ impl<S: AsRawFd> NonBufferedStream<S> {
         Poll::Pending
     }
 
+    pub(crate) async fn send_zc(&mut self, buf: ZeroCopyBuffer) -> io::Result<usize> {
+        let reactor = self.reactor.upgrade().unwrap();
+        if !reactor.supports_send_zc() {
+            return future::poll_fn(|cx| self.poll_write(cx, &buf)).await;
+        }
+        let source = reactor.send_zc(self.stream.as_raw_fd(), buf, self.write_timeout.get());
+        source.collect_rw().await
+    }
+
     pub(crate) fn poll_close(&mut self, _: &mut Context<'_>) -> Poll<io::Result<()>> {
         self.source_tx.take();
         Poll::Ready(sys::shutdown(self.stream.as_raw_fd(), Shutdown::Write))
@@ -367,9 +449,11 @@ where
         let reactor = crate::executor().reactor();
         let mut stream = NonBufferedStream {
             reactor: Rc::downgrade(&reactor),
+            fixed: None,
             stream: socket.into(),
             source_tx: None,
             source_rx: None,
+            source_pooled: None,
             write_timeout: Timeout::new(reactor.register_timer()),
             read_timeout: Timeout::new(reactor.register_timer()),
         };
@@ -400,8 +484,13 @@ where
 
 impl<S> GlommioStream<S, NonBuffered> {
     pub(crate) fn buffered_with<B: Buffered>(self, rx_buf: B) -> GlommioStream<S, B> {
+        let mut stream = self.stream;
+        if rx_buf.buffer_pool().is_some() {
+            // pooled streams don't wait for readiness
+            stream.source_rx.take();
+        }
         GlommioStream {
-            stream: self.stream,
+            stream,
             rx_buf,
             rx_done: self.rx_done,
         }
@@ -442,7 +531,7 @@ impl<S: AsRawFd, B: RxBuf> GlommioStream<S, B> {
         buf: &mut [u8],
     ) -> Poll<io::Result<usize>> {
         if self.rx_buf.is_empty() {
-            if buf.len() >= self.rx_buf.buffer_size() {
+            if buf.len() >= self.rx_buf.buffer_size() && self.rx_buf.buffer_pool().is_none() {
                 return self.stream.poll_read(cx, buf);
             }
             if !self.rx_done.get() {
@@ -453,6 +542,16 @@ impl<S: AsRawFd, B: RxBuf> GlommioStream<S, B> {
     }
 
     fn poll_replenish_buffer(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        if let Some(pool) = self.rx_buf.buffer_pool() {
+            let pool = pool.clone();
+            let buf = poll_err!(ready!(self.stream.poll_recv_pooled(cx, &pool)));
+            let result = buf.as_ref().map(|buf| buf.len()).unwrap_or(0);
+            match buf {
+                Some(buf) => self.rx_buf.handle_buffer(buf),
+                None => self.rx_done.set(true),
+            }
+            return Poll::Ready(Ok(result));
+        }
         let result = poll_err!(ready!(self.stream.poll_read(cx, self.rx_buf.unfilled())));
         self.rx_buf.handle_result(result);
         if result == 0 {
@@ -469,6 +568,14 @@ impl<S: AsRawFd, B: RxBuf> GlommioStream<S, B> {
         self.stream.poll_write(cx, buf)
     }
 
+    pub(crate) async fn send_zc(&mut self, buf: ZeroCopyBuffer) -> io::Result<usize> {
+        self.stream.send_zc(buf).await
+    }
+
+    pub(crate) fn register(&mut self) -> io::Result<()> {
+        self.stream.register()
+    }
+
     pub(crate) fn poll_flush(&self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
         Poll::Ready(Ok(()))
     }
@@ -6,23 +6,26 @@
 use super::stream::GlommioStream;
 use crate::{
     net::{
+        happy_eyeballs::{self, HappyEyeballsOptions},
+        resolve::resolve_addrs,
         stream::{Buffered, NonBuffered, Preallocated, RxBuf},
-        yolo_accept,
+        yolo_accept, BufferPool, MultishotAccept, MultishotRecv,
     },
     reactor::Reactor,
+    sys::{DmaBuffer, SourceType, ZeroCopyBuffer},
     GlommioError,
 };
 use futures_lite::{
     future::poll_fn,
     io::{AsyncBufRead, AsyncRead, AsyncWrite},
-    stream::{self, Stream},
+    stream::{self, Stream, StreamExt},
 };
 use nix::sys::socket::{InetAddr, SockAddr};
 use pin_project_lite::pin_project;
 use socket2::{Domain, Protocol, Socket, Type};
 use std::{
     io,
-    net::{self, Shutdown, SocketAddr, ToSocketAddrs},
+    net::{self, Shutdown, SocketAddr},
     os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd},
     pin::Pin,
     rc::{Rc, Weak},
@@ -97,7 +100,8 @@ impl TcpListener {
     ///
     /// This method sets the ReusePort option in the bound socket, so it is
     /// designed to be called from multiple executors to achieve
-    /// parallelism.
+    /// parallelism. Use a [`TcpSocket`] to configure the socket otherwise,
+    /// or to set the length of the backlog, which is 1024.
     ///
     /// # Examples
     ///
@@ -110,29 +114,17 @@ impl TcpListener {
     ///     println!("Listening on {}", listener.local_addr().unwrap());
     /// });
     /// ```
-    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<TcpListener> {
+    pub fn bind<A: net::ToSocketAddrs>(addr: A) -> Result<TcpListener> {
         let addr = addr
             .to_socket_addrs()
             .unwrap()
             .next()
             .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "empty address"))?;
 
-        let domain = if addr.is_ipv6() {
-            Domain::IPV6
-        } else {
-            Domain::IPV4
-        };
-        let sk = Socket::new(domain, Type::STREAM, Some(Protocol::TCP))?;
-        let addr = socket2::SockAddr::from(addr);
-        sk.set_reuse_port(true)?;
-        sk.bind(&addr)?;
-        sk.listen(1024)?;
-        let listener = sk.into();
-
-        Ok(TcpListener {
-            reactor: Rc::downgrade(&crate::executor().reactor()),
-            listener,
-        })
+        let socket = TcpSocket::for_addr(&addr)?;
+        socket.set_reuseport(true)?;
+        socket.bind(addr)?;
+        socket.listen(1024)
     }
 
     /// Accepts a new incoming TCP connection and allows the result to be sent
@@ -232,6 +224,47 @@ impl TcpListener {
         }))
     }
 
+    /// Creates a stream of incoming connections backed by a single multishot
+    /// accept request.
+    ///
+    /// Instead of issuing one request per connection like [`incoming`], a
+    /// single request is armed and the kernel posts a completion for every
+    /// connection it accepts. This saves a submission and a wakeup per
+    /// connection under heavy connection rates.
+    ///
+    /// Multishot accepts need Linux 5.19. On older kernels this behaves
+    /// exactly like [`incoming`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use futures_lite::stream::StreamExt;
+    /// use glommio::{net::TcpListener, LocalExecutor};
+    ///
+    /// let ex = LocalExecutor::default();
+    /// ex.run(async move {
+    ///     let listener = TcpListener::bind("127.0.0.1:8000").unwrap();
+    ///     let mut incoming = listener.incoming_multishot();
+    ///     while let Some(conn) = incoming.next().await {
+    ///         println!("Accepted client: {conn:?}");
+    ///     }
+    /// });
+    /// ```
+    ///
+    /// [`incoming`]: TcpListener::incoming
+    pub fn incoming_multishot(&self) -> impl Stream<Item = Result<TcpStream>> + Unpin + '_ {
+        let reactor = self.reactor.upgrade().unwrap();
+        let stream: Pin<Box<dyn Stream<Item = Result<TcpStream>> + '_>> =
+            match MultishotAccept::new(reactor, self.listener.as_raw_fd()) {
+                Some(multishot) => Box::pin(multishot.map(|res| {
+                    let fd = res?;
+                    Ok(AcceptedTcpStream { fd }.bind_to_executor())
+                })),
+                None => Box::pin(self.incoming()),
+            };
+        stream
+    }
+
     /// Returns the socket address of the local half of this TCP connection.
     ///
     /// # Examples
@@ -291,6 +324,223 @@ impl TcpListener {
     }
 }
 
+/// A TCP socket that has not yet been converted to a [`TcpListener`] or a
+/// [`TcpStream`].
+///
+/// `TcpSocket` gives control over the options of a socket that must be set
+/// before it listens or connects, and that [`TcpListener::bind`] and
+/// [`TcpStream::connect`] don't expose: the buffer sizes, binding to a
+/// source address or to a device, transparent proxying, TCP Fast Open or
+/// whether the ReusePort flag is set. Once configured, the socket is turned
+/// into a [`TcpListener`] with [`listen`] or into a [`TcpStream`] with
+/// [`connect`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use glommio::{net::TcpSocket, LocalExecutor};
+///
+/// let ex = LocalExecutor::default();
+/// ex.run(async move {
+///     let socket = TcpSocket::new_v4().unwrap();
+///     socket.set_reuseport(false).unwrap();
+///     socket.set_recv_buffer_size(1 << 20).unwrap();
+///     socket.bind("127.0.0.1:8000".parse().unwrap()).unwrap();
+///     let listener = socket.listen(4096).unwrap();
+///
+///     let socket = TcpSocket::new_v4().unwrap();
+///     socket.bind("127.0.0.2:0".parse().unwrap()).unwrap();
+///     let stream = socket
+///         .connect("127.0.0.1:8000".parse().unwrap())
+///         .await
+///         .unwrap();
+/// });
+/// ```
+///
+/// [`listen`]: TcpSocket::listen
+/// [`connect`]: TcpSocket::connect
+#[derive(Debug)]
+pub struct TcpSocket {
+    socket: Socket,
+}
+
+impl TcpSocket {
+    /// Creates a new IPv4 TCP socket.
+    pub fn new_v4() -> Result<TcpSocket> {
+        TcpSocket::new(Domain::IPV4)
+    }
+
+    /// Creates a new IPv6 TCP socket.
+    pub fn new_v6() -> Result<TcpSocket> {
+        TcpSocket::new(Domain::IPV6)
+    }
+
+    fn new(domain: Domain) -> Result<TcpSocket> {
+        let socket = Socket::new(domain, Type::STREAM, Some(Protocol::TCP))?;
+        Ok(TcpSocket { socket })
+    }
+
+    fn for_addr(addr: &SocketAddr) -> Result<TcpSocket> {
+        match addr {
+            SocketAddr::V4(_) => TcpSocket::new_v4(),
+            SocketAddr::V6(_) => TcpSocket::new_v6(),
+        }
+    }
+
+    /// Sets the `SO_REUSEADDR` option, which allows binding to an address
+    /// that is in the `TIME_WAIT` state.
+    pub fn set_reuseaddr(&self, reuse: bool) -> Result<()> {
+        self.socket.set_reuse_address(reuse).map_err(Into::into)
+    }
+
+    /// Gets the value of the `SO_REUSEADDR` option.
+    pub fn reuseaddr(&self) -> Result<bool> {
+        self.socket.reuse_address().map_err(Into::into)
+    }
+
+    /// Sets the `SO_REUSEPORT` option, which allows many sockets to bind to
+    /// the same address, and the kernel to balance the connections made to
+    /// it among them. [`TcpListener::bind`] always sets it.
+    pub fn set_reuseport(&self, reuse: bool) -> Result<()> {
+        self.socket.set_reuse_port(reuse).map_err(Into::into)
+    }
+
+    /// Gets the value of the `SO_REUSEPORT` option.
+    pub fn reuseport(&self) -> Result<bool> {
+        self.socket.reuse_port().map_err(Into::into)
+    }
+
+    /// Sets the size of the receive buffer of the socket (`SO_RCVBUF`).
+    ///
+    /// The kernel doubles the value set, to leave room for its bookkeeping,
+    /// and caps it to `net.core.rmem_max`.
+    pub fn set_recv_buffer_size(&self, size: usize) -> Result<()> {
+        self.socket.set_recv_buffer_size(size).map_err(Into::into)
+    }
+
+    /// Gets the size of the receive buffer of the socket (`SO_RCVBUF`).
+    pub fn recv_buffer_size(&self) -> Result<usize> {
+        self.socket.recv_buffer_size().map_err(Into::into)
+    }
+
+    /// Sets the size of the send buffer of the socket (`SO_SNDBUF`).
+    ///
+    /// The kernel doubles the value set, to leave room for its bookkeeping,
+    /// and caps it to `net.core.wmem_max`.
+    pub fn set_send_buffer_size(&self, size: usize) -> Result<()> {
+        self.socket.set_send_buffer_size(size).map_err(Into::into)
+    }
+
+    /// Gets the size of the send buffer of the socket (`SO_SNDBUF`).
+    pub fn send_buffer_size(&self) -> Result<usize> {
+        self.socket.send_buffer_size().map_err(Into::into)
+    }
+
+    /// Sets the `IP_FREEBIND` option, which allows binding to an address that
+    /// is not assigned to any interface (yet).
+    pub fn set_freebind(&self, freebind: bool) -> Result<()> {
+        self.socket.set_freebind(freebind).map_err(Into::into)
+    }
+
+    /// Sets the `IP_TRANSPARENT` option, which allows binding to any address
+    /// and accepting connections made to any address, for transparent
+    /// proxying. Requires the `CAP_NET_ADMIN` capability.
+    pub fn set_transparent(&self, transparent: bool) -> Result<()> {
+        self.socket
+            .set_ip_transparent(transparent)
+            .map_err(Into::into)
+    }
+
+    /// Binds the socket to the network device named `interface`
+    /// (`SO_BINDTODEVICE`), so it only sends and receives packets through
+    /// it, or unbinds it if `None`.
+    pub fn bind_device(&self, interface: Option<&[u8]>) -> Result<()> {
+        self.socket.bind_device(interface).map_err(Into::into)
+    }
+
+    /// Enables TCP Fast Open on a socket that will listen (`TCP_FASTOPEN`),
+    /// with a queue of at most `queue_len` pending connections whose
+    /// handshake isn't complete yet. Clients can then send data in the SYN
+    /// of the connections they make after the first one.
+    pub fn set_tcp_fastopen(&self, queue_len: u32) -> Result<()> {
+        setsockopt_int(&self.socket, libc::TCP_FASTOPEN, queue_len as libc::c_int)
+    }
+
+    /// Enables TCP Fast Open on a socket that will connect
+    /// (`TCP_FASTOPEN_CONNECT`). The connection then completes right away,
+    /// and the SYN is sent with the first data written to the stream.
+    pub fn set_tcp_fastopen_connect(&self, enabled: bool) -> Result<()> {
+        setsockopt_int(
+            &self.socket,
+            libc::TCP_FASTOPEN_CONNECT,
+            enabled as libc::c_int,
+        )
+    }
+
+    /// Sets the `TCP_NODELAY` option, which disables Nagle's algorithm. See
+    /// [`TcpStream::set_nodelay`].
+    pub fn set_nodelay(&self, nodelay: bool) -> Result<()> {
+        self.socket.set_nodelay(nodelay).map_err(Into::into)
+    }
+
+    /// Binds the socket to `addr`. For a socket that will connect, this sets
+    /// the source address of the connection.
+    pub fn bind(&self, addr: SocketAddr) -> Result<()> {
+        self.socket
+            .bind(&socket2::SockAddr::from(addr))
+            .map_err(Into::into)
+    }
+
+    /// Returns the address the socket is bound to.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        let addr = self.socket.local_addr()?;
+        Ok(addr.as_socket().unwrap())
+    }
+
+    /// Turns the socket into a [`TcpListener`], with a queue of at most
+    /// `backlog` connections waiting to be accepted. The socket should be
+    /// bound, or it listens on a random port.
+    pub fn listen(self, backlog: u32) -> Result<TcpListener> {
+        let backlog = std::cmp::min(backlog, libc::c_int::MAX as u32);
+        self.socket.listen(backlog as libc::c_int)?;
+        Ok(TcpListener {
+            reactor: Rc::downgrade(&crate::executor().reactor()),
+            listener: self.socket.into(),
+        })
+    }
+
+    /// Turns the socket into a [`TcpStream`] connected to `addr`.
+    pub async fn connect(self, addr: SocketAddr) -> Result<TcpStream> {
+        let inet = InetAddr::from_std(&addr);
+        let reactor = crate::executor().reactor();
+        let source = reactor.connect(self.socket.as_raw_fd(), SockAddr::new_inet(inet));
+        source.collect_rw().await?;
+        Ok(TcpStream::from(self.socket))
+    }
+}
+
+impl AsRawFd for TcpSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.as_raw_fd()
+    }
+}
+
+fn setsockopt_int(socket: &Socket, opt: libc::c_int, value: libc::c_int) -> Result<()> {
+    let res = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::IPPROTO_TCP,
+            opt,
+            &value as *const libc::c_int as *const libc::c_void,
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if res != 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
 #[derive(Copy, Clone, Debug)]
 /// An Accepted Tcp connection that can be moved to a different executor
 ///
@@ -393,7 +643,7 @@ impl FromRawFd for TcpStream {
     }
 }
 
-fn make_tcp_socket(addr: &SocketAddr) -> io::Result<(SockAddr, Socket)> {
+pub(super) fn make_tcp_socket(addr: &SocketAddr) -> io::Result<(SockAddr, Socket)> {
     let domain = if addr.is_ipv6() {
         Domain::IPV6
     } else {
@@ -408,6 +658,12 @@ fn make_tcp_socket(addr: &SocketAddr) -> io::Result<(SockAddr, Socket)> {
 impl TcpStream {
     /// Creates a TCP connection to the specified address.
     ///
+    /// If `addr` is a name, it's resolved with [`resolve`], without blocking
+    /// the executor, and the connection is made to the first address it
+    /// resolves to. Implementations of [`ToSocketAddrs`] other than the ones
+    /// of the standard library are run on the blocking thread pool, which is
+    /// why `addr` can't borrow anything but `'static` data.
+    ///
     /// # Examples
     ///
     /// ```no_run
@@ -418,8 +674,11 @@ impl TcpStream {
     ///     TcpStream::connect("127.0.0.1:10000").await.unwrap();
     /// })
     /// ```
-    pub async fn connect<A: ToSocketAddrs>(addr: A) -> Result<TcpStream> {
-        let addr = addr.to_socket_addrs()?.next().unwrap();
+    ///
+    /// [`resolve`]: crate::net::resolve
+    /// [`ToSocketAddrs`]: std::net::ToSocketAddrs
+    pub async fn connect<A: net::ToSocketAddrs + Send + 'static>(addr: A) -> Result<TcpStream> {
+        let addr = resolve_addrs(addr).await?[0];
         let (addr, socket) = make_tcp_socket(&addr)?;
         let reactor = crate::executor().reactor();
         let source = reactor.connect(socket.as_raw_fd(), addr);
@@ -450,7 +709,7 @@ impl TcpStream {
     ///         .unwrap();
     /// })
     /// ```
-    pub async fn connect_timeout<A: ToSocketAddrs>(
+    pub async fn connect_timeout<A: net::ToSocketAddrs + Send + 'static>(
         addr: A,
         duration: Duration,
     ) -> Result<TcpStream> {
@@ -462,7 +721,7 @@ impl TcpStream {
             .into());
         }
 
-        let addr = addr.to_socket_addrs()?.next().unwrap();
+        let addr = resolve_addrs(addr).await?[0];
         let (addr, socket) = make_tcp_socket(&addr)?;
         let reactor = crate::executor().reactor();
         let source = reactor.connect_timeout(socket.as_raw_fd(), addr, duration);
@@ -485,6 +744,119 @@ impl TcpStream {
         })
     }
 
+    /// Creates a TCP connection to one of the addresses `addrs` resolves to,
+    /// racing them as described by RFC 8305 ("Happy Eyeballs").
+    ///
+    /// Unlike [`connect`], which only tries the first address, this tries
+    /// all of them, alternating between IPv6 and IPv4 addresses. A new
+    /// attempt is started whenever the previous one failed, or didn't
+    /// succeed within the [`attempt_delay`], without waiting for it to fail.
+    /// So when a route to the backend is broken, the first working address
+    /// gets the connection within a few attempt delays rather than after
+    /// the timeout of the broken one. The first connection to be made wins,
+    /// and the requests of all the other attempts are canceled.
+    ///
+    /// If no connection can be made, the error returned wraps a
+    /// [`HappyEyeballsError`] with the failure of every address attempted.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use glommio::{
+    ///     net::{HappyEyeballsOptions, TcpStream},
+    ///     LocalExecutor,
+    /// };
+    /// use std::time::Duration;
+    ///
+    /// let ex = LocalExecutor::default();
+    /// ex.run(async move {
+    ///     let opts = HappyEyeballsOptions::new().timeout(Duration::from_secs(10));
+    ///     TcpStream::connect_happy_eyeballs("example.com:80", opts)
+    ///         .await
+    ///         .unwrap();
+    /// })
+    /// ```
+    ///
+    /// [`connect`]: TcpStream::connect
+    /// [`attempt_delay`]: HappyEyeballsOptions::attempt_delay
+    /// [`HappyEyeballsError`]: crate::net::HappyEyeballsError
+    pub async fn connect_happy_eyeballs<A: net::ToSocketAddrs + Send + 'static>(
+        addrs: A,
+        opts: HappyEyeballsOptions,
+    ) -> Result<TcpStream> {
+        let addrs = resolve_addrs(addrs).await?;
+        let socket = happy_eyeballs::connect(addrs, opts).await?;
+        Ok(TcpStream {
+            stream: GlommioStream::from(socket),
+        })
+    }
+
+    /// Returns a stream of the data received on the socket, in buffers the
+    /// kernel picks from `pool`.
+    ///
+    /// Instead of issuing one request per read, a single multishot `recv`
+    /// request is armed and the kernel posts a completion every time it
+    /// fills a buffer. This saves a submission and a wakeup per read on
+    /// busy connections. The stream ends when the peer closes the
+    /// connection.
+    ///
+    /// Buffers go back to the pool when dropped. If the pool runs out of
+    /// buffers, the stream yields an `ENOBUFS` error, after which it can be
+    /// polled again. The stream must not be used together with the
+    /// [`AsyncRead`] implementation, and the read timeout does not apply to
+    /// it.
+    ///
+    /// Multishot receives need Linux 6.0. On older kernels data is received
+    /// one request at a time, still into buffers of the pool.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use futures_lite::stream::StreamExt;
+    /// use glommio::{
+    ///     net::{BufferPool, TcpStream},
+    ///     LocalExecutor,
+    /// };
+    ///
+    /// let ex = LocalExecutor::default();
+    /// ex.run(async move {
+    ///     let pool = BufferPool::new(256, 4096).unwrap();
+    ///     let stream = TcpStream::connect("127.0.0.1:10000").await.unwrap();
+    ///     let mut received = stream.recv_stream(&pool);
+    ///     while let Some(buf) = received.next().await {
+    ///         println!("received {} bytes", buf.unwrap().len());
+    ///     }
+    /// })
+    /// ```
+    pub fn recv_stream(
+        &self,
+        pool: &BufferPool,
+    ) -> impl Stream<Item = Result<DmaBuffer>> + Unpin + '_ {
+        let reactor = crate::executor().reactor();
+        let fd = self.as_raw_fd();
+        let stream: Pin<Box<dyn Stream<Item = Result<DmaBuffer>> + '_>> =
+            match MultishotRecv::new(reactor, fd, pool, false) {
+                Some(multishot) => Box::pin(multishot.map(|res| res.map_err(Into::into))),
+                None => Box::pin(stream::unfold(Some(pool.clone()), move |pool| async move {
+                    let pool = pool?;
+                    let source = crate::executor()
+                        .reactor()
+                        .recv_select(fd, pool.group(), None);
+                    let res = source.collect_rw().await;
+                    let buf = match source.extract_source_type() {
+                        SourceType::SockRecvSelect(_, buf) => buf,
+                        _ => unreachable!(),
+                    };
+                    match (res, buf) {
+                        (Err(err), _) => Some((Err(err.into()), Some(pool))),
+                        (Ok(len), Some(buf)) if len > 0 => Some((Ok(buf), Some(pool))),
+                        _ => None,
+                    }
+                })),
+            };
+        stream
+    }
+
     /// Creates a buffered TCP connection with default receive buffer.
     pub fn buffered(self) -> TcpStream<Preallocated> {
         self.buffered_with(Preallocated::default())
@@ -553,6 +925,74 @@ impl<B: RxBuf> TcpStream<B> {
         self.stream.write_timeout()
     }
 
+    /// Sends data on the socket without copying it, returning how many bytes
+    /// were sent.
+    ///
+    /// This takes ownership of `buf`, which can be a [`DmaBuffer`], a
+    /// [`ReadResult`] or any other owned buffer. The kernel sends straight from
+    /// its memory, which is only released once the kernel is done with it,
+    /// possibly some time after this returns. Like a single write, this may
+    /// send only part of `buf`. Zero-copy sends pay off for large buffers;
+    /// for small ones, regular writes are usually faster.
+    ///
+    /// Zero-copy sends need Linux 6.0. On older kernels the data is copied
+    /// and sent like with a regular write.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use glommio::{io::ImmutableFileBuilder, net::TcpStream, LocalExecutor};
+    ///
+    /// let ex = LocalExecutor::default();
+    /// ex.run(async move {
+    ///     let file = ImmutableFileBuilder::new("index.html")
+    ///         .build_existing()
+    ///         .await
+    ///         .unwrap();
+    ///     let mut stream = TcpStream::connect("127.0.0.1:10000").await.unwrap();
+    ///     let data = file.read_at(0, 1 << 20).await.unwrap();
+    ///     let sent = stream.send_zc(data).await.unwrap();
+    /// })
+    /// ```
+    ///
+    /// [`ReadResult`]: crate::io::ReadResult
+    pub async fn send_zc<T: AsRef<[u8]> + 'static>(&mut self, buf: T) -> Result<usize> {
+        self.stream
+            .send_zc(ZeroCopyBuffer::new(buf))
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Registers the socket in the executor's table of fixed files.
+    ///
+    /// Requests issued on a registered socket skip the lookup and reference
+    /// counting of its file descriptor that the kernel does otherwise. From
+    /// this call on, sends, receives and readiness polls issued through
+    /// `io_uring` use the registered handle. The registration is released
+    /// when the stream is dropped.
+    ///
+    /// The table is sized after the open files limit of the process and
+    /// shared by all registered files and sockets of the executor.
+    /// If the kernel can't set up the table, this does nothing and requests
+    /// keep using the file descriptor.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use futures_lite::AsyncWriteExt;
+    /// use glommio::{net::TcpStream, LocalExecutor};
+    ///
+    /// let ex = LocalExecutor::default();
+    /// ex.run(async move {
+    ///     let mut stream = TcpStream::connect("127.0.0.1:10000").await.unwrap();
+    ///     stream.register().unwrap();
+    ///     stream.write_all(b"hello").await.unwrap();
+    /// })
+    /// ```
+    pub fn register(&mut self) -> Result<()> {
+        self.stream.register().map_err(Into::into)
+    }
+
     /// Shuts down the read, write, or both halves of this connection.
     pub async fn shutdown(&self, how: Shutdown) -> Result<()> {
         poll_fn(|cx| self.stream.poll_shutdown(cx, how))
@@ -778,6 +1218,93 @@ mod tests {
         });
     }
 
+    #[test]
+    fn tcp_socket_listen_and_connect() {
+        test_executor!(async move {
+            let socket = TcpSocket::new_v4().unwrap();
+            socket.set_reuseaddr(true).unwrap();
+            assert!(socket.reuseaddr().unwrap());
+            assert!(!socket.reuseport().unwrap());
+            socket.set_recv_buffer_size(1 << 16).unwrap();
+            assert!(socket.recv_buffer_size().unwrap() >= 1 << 16);
+            socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+            let addr = socket.local_addr().unwrap();
+            let listener = socket.listen(16).unwrap();
+            assert_eq!(listener.local_addr().unwrap(), addr);
+
+            // Without ReusePort, nothing else can bind to the address
+            let other = TcpSocket::new_v4().unwrap();
+            other.set_reuseport(true).unwrap();
+            assert!(other.bind(addr).is_err());
+
+            let socket = TcpSocket::new_v4().unwrap();
+            socket.set_nodelay(true).unwrap();
+            socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+            let source = socket.local_addr().unwrap();
+            let stream = socket.connect(addr).await.unwrap();
+            assert_eq!(stream.local_addr().unwrap(), source);
+            assert!(stream.nodelay().unwrap());
+
+            let accepted = listener.accept().await.unwrap();
+            assert_eq!(accepted.peer_addr().unwrap(), source);
+        });
+    }
+
+    #[test]
+    fn connect_by_name() {
+        test_executor!(async move {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let port = listener.local_addr().unwrap().port();
+            // localhost may resolve to ::1 first, which nothing listens on
+            let addrs = crate::net::resolve("localhost", port).await.unwrap();
+            if addrs[0].is_ipv4() {
+                let stream = TcpStream::connect(("localhost", port)).await.unwrap();
+                assert_eq!(stream.peer_addr().unwrap(), addrs[0]);
+            }
+            let stream = TcpStream::connect(format!("127.0.0.1:{}", port))
+                .await
+                .unwrap();
+            assert_eq!(stream.peer_addr().unwrap(), listener.local_addr().unwrap());
+        });
+    }
+
+    #[test]
+    fn connect_happy_eyeballs() {
+        test_executor!(async move {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            let closed = TcpListener::bind("127.0.0.1:0")
+                .unwrap()
+                .local_addr()
+                .unwrap();
+            let refused: SocketAddr = format!("[::1]:{}", closed.port()).parse().unwrap();
+
+            let opts = HappyEyeballsOptions::new().attempt_delay(Duration::from_secs(10));
+            // the addresses may be resolved on another thread, so they can't
+            // be borrowed from the stack
+            let addrs: &'static [SocketAddr] = Box::leak(Box::new([refused, closed, addr]));
+            let stream = TcpStream::connect_happy_eyeballs(addrs, opts)
+                .await
+                .unwrap();
+            assert_eq!(stream.peer_addr().unwrap(), addr);
+
+            let err = TcpStream::connect_happy_eyeballs(&addrs[..2], opts)
+                .await
+                .unwrap_err();
+            let err = match err {
+                GlommioError::IoError(err) => err,
+                err => panic!("unexpected error {:?}", err),
+            };
+            let err = err
+                .get_ref()
+                .and_then(|x| x.downcast_ref::<happy_eyeballs::HappyEyeballsError>())
+                .unwrap();
+            let mut failed: Vec<_> = err.failures().iter().map(|(addr, _)| *addr).collect();
+            failed.sort();
+            assert_eq!(failed, vec![closed, refused]);
+        });
+    }
+
     #[test]
     fn connect_local_server() {
         test_executor!(async move {
@@ -799,6 +1326,97 @@ mod tests {
         });
     }
 
+    #[test]
+    fn incoming_multishot() {
+        test_executor!(async move {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            let mut incoming = listener.incoming_multishot();
+
+            let mut clients = Vec::new();
+            for _ in 0..3 {
+                clients.push(TcpStream::connect(addr).await.unwrap());
+            }
+            for client in &clients {
+                let stream = incoming.next().await.unwrap().unwrap();
+                assert_eq!(stream.peer_addr().unwrap(), client.local_addr().unwrap());
+            }
+
+            // the stream keeps accepting after being idle
+            let client = TcpStream::connect(addr).await.unwrap();
+            let stream = incoming.next().await.unwrap().unwrap();
+            assert_eq!(stream.peer_addr().unwrap(), client.local_addr().unwrap());
+        });
+    }
+
+    #[test]
+    fn recv_stream() {
+        test_executor!(async move {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            let mut client = TcpStream::connect(addr).await.unwrap();
+            let server = listener.accept().await.unwrap();
+            let pool = BufferPool::new(8, 64).unwrap();
+
+            let data: Vec<u8> = (0..200).collect();
+            client.write_all(&data).await.unwrap();
+            client.close().await.unwrap();
+
+            let mut received = Vec::new();
+            let mut bufs = server.recv_stream(&pool);
+            while let Some(buf) = bufs.next().await {
+                received.extend_from_slice(buf.unwrap().as_bytes());
+            }
+            assert_eq!(received, data);
+            assert_eq!(pool.available(), pool.capacity());
+        });
+    }
+
+    #[test]
+    fn send_zc() {
+        test_executor!(async move {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            let mut client = TcpStream::connect(addr).await.unwrap();
+            let mut server = listener.accept().await.unwrap();
+
+            let mut buf = crate::executor().reactor().alloc_dma_buffer(4096);
+            buf.as_bytes_mut().fill(42);
+            let mut sent = client.send_zc(buf).await.unwrap();
+            sent += client.send_zc(vec![7u8; 100]).await.unwrap();
+            assert_eq!(sent, 4196);
+
+            let mut received = vec![0; 4196];
+            server.read_exact(&mut received).await.unwrap();
+            assert!(received[..4096].iter().all(|x| *x == 42));
+            assert!(received[4096..].iter().all(|x| *x == 7));
+        });
+    }
+
+    #[test]
+    fn registered_stream() {
+        test_executor!(async move {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            let mut client = TcpStream::connect(addr).await.unwrap();
+            let mut server = listener.accept().await.unwrap();
+            client.register().unwrap();
+            server.register().unwrap();
+
+            client.write_all(b"ping").await.unwrap();
+            let mut buf = [0u8; 4];
+            server.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"ping");
+            server.write_all(b"pong").await.unwrap();
+            client.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"pong");
+
+            let fd = client.as_raw_fd();
+            drop(client);
+            assert!(crate::executor().reactor().sys.fixed_file(fd).is_none());
+        });
+    }
+
     #[test]
     fn multi_executor_bind_works() {
         test_executor!(async move {
@@ -3,18 +3,23 @@
 //
 // This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2020 Datadog, Inc.
 //
-use super::datagram::GlommioDatagram;
+use super::{datagram::GlommioDatagram, resolve::resolve_addrs, BufferPool, MultishotRecv};
+use crate::sys::{self, DmaBuffer, ZeroCopyBuffer};
+use futures_lite::stream::{self, Stream, StreamExt};
 use nix::sys::socket::{InetAddr, SockAddr};
 use socket2::{Domain, Protocol, Socket, Type};
 use std::{
     io,
-    net::{self, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs},
+    net::{self, Ipv4Addr, Ipv6Addr, SocketAddr},
     os::unix::io::{AsRawFd, FromRawFd, RawFd},
+    pin::Pin,
     time::Duration,
 };
 
 type Result<T> = crate::Result<T, ()>;
 
+type DatagramStream<'a> = Pin<Box<dyn Stream<Item = Result<(DmaBuffer, SocketAddr)>> + 'a>>;
+
 #[derive(Debug)]
 /// An Udp Socket.
 pub struct UdpSocket {
@@ -62,7 +67,7 @@ impl UdpSocket {
     ///     println!("Listening on {}", listener.local_addr().unwrap());
     /// });
     /// ```
-    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<UdpSocket> {
+    pub fn bind<A: net::ToSocketAddrs>(addr: A) -> Result<UdpSocket> {
         let addr = addr
             .to_socket_addrs()
             .unwrap()
@@ -117,8 +122,8 @@ impl UdpSocket {
     ///
     /// [`send`]: UdpSocket::send
     /// [`recv`]: UdpSocket::recv
-    pub async fn connect<A: ToSocketAddrs>(&self, addr: A) -> Result<()> {
-        let iter = addr.to_socket_addrs()?;
+    pub async fn connect<A: net::ToSocketAddrs + Send + 'static>(&self, addr: A) -> Result<()> {
+        let iter = resolve_addrs(addr).await?;
         let mut err = io::Error::new(io::ErrorKind::Other, "No Valid addresses");
         for addr in iter {
             let inet = InetAddr::from_std(&addr);
@@ -553,6 +558,40 @@ impl UdpSocket {
         self.socket.recv(buf).await.map_err(Into::into)
     }
 
+    /// Receives a single datagram message on the socket from the remote address
+    /// to which it is connected, into a buffer the kernel picks from `pool`.
+    ///
+    /// The returned buffer goes back to the pool when dropped. If the pool has
+    /// no buffer available, this fails with `ENOBUFS`. Messages longer than
+    /// the pool's [`buffer_size`] are truncated.
+    ///
+    /// To use this function, [`connect`] must have been called
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use glommio::{
+    ///     net::{BufferPool, UdpSocket},
+    ///     LocalExecutor,
+    /// };
+    ///
+    /// let ex = LocalExecutor::default();
+    /// ex.run(async move {
+    ///     let pool = BufferPool::new(64, 1500).unwrap();
+    ///     let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    ///     socket.connect("127.0.0.1:8000").await.unwrap();
+    ///     let buf = socket.recv_pooled(&pool).await.unwrap();
+    ///     println!("received {} bytes", buf.len());
+    /// })
+    /// ```
+    ///
+    /// [`buffer_size`]: BufferPool::buffer_size
+    /// [`connect`]: UdpSocket::connect
+    pub async fn recv_pooled(&self, pool: &BufferPool) -> Result<DmaBuffer> {
+        let _ = self.peer_addr()?;
+        self.socket.recv_pooled(pool).await.map_err(Into::into)
+    }
+
     /// Receives a single datagram message on the socket. On success, returns
     /// the number of bytes read and the origin.
     ///
@@ -588,11 +627,90 @@ impl UdpSocket {
         Ok((sz, addr.to_std()))
     }
 
+    /// Returns a stream of the datagrams received on the socket, with the
+    /// addresses they came from, in buffers the kernel picks from `pool`.
+    ///
+    /// Instead of issuing one request per datagram like [`recv_from`], a
+    /// single multishot `recvmsg` request is armed and the kernel posts a
+    /// completion for every datagram it receives. Each buffer also holds the
+    /// address of the sender, which takes the first 144 bytes of it, so the
+    /// pool's buffers must be that much larger than the datagrams. Datagrams
+    /// that don't fit are truncated.
+    ///
+    /// Buffers go back to the pool when dropped. If the pool runs out of
+    /// buffers, the stream yields an `ENOBUFS` error, after which it can be
+    /// polled again.
+    ///
+    /// The read timeout does not apply to this stream. Multishot receives
+    /// need Linux 6.0. On older kernels a buffer is allocated for each
+    /// datagram instead of being taken from the pool, and datagrams are
+    /// received one request at a time.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use futures_lite::stream::StreamExt;
+    /// use glommio::{
+    ///     net::{BufferPool, UdpSocket},
+    ///     LocalExecutor,
+    /// };
+    ///
+    /// let ex = LocalExecutor::default();
+    /// ex.run(async move {
+    ///     let pool = BufferPool::new(256, 2048).unwrap();
+    ///     let socket = UdpSocket::bind("127.0.0.1:8000").unwrap();
+    ///     let mut datagrams = socket.recv_stream(&pool);
+    ///     while let Some(Ok((buf, addr))) = datagrams.next().await {
+    ///         socket.send_to(buf.as_bytes(), addr).await.unwrap();
+    ///     }
+    /// })
+    /// ```
+    ///
+    /// [`recv_from`]: UdpSocket::recv_from
+    pub fn recv_stream(
+        &self,
+        pool: &BufferPool,
+    ) -> impl Stream<Item = Result<(DmaBuffer, SocketAddr)>> + Unpin + '_ {
+        let reactor = self.socket.reactor.upgrade().unwrap();
+        let stream: DatagramStream<'_> =
+            match MultishotRecv::new(reactor, self.as_raw_fd(), pool, true) {
+                Some(multishot) => Box::pin(multishot.map(|res| {
+                    let (buf, addr) = sys::parse_recvmsg_out(res?)?;
+                    let addr = match addr {
+                        SockAddr::Inet(addr) => addr,
+                        x => panic!("invalid socket addr for this family!: {:?}", x),
+                    };
+                    Ok((buf, addr.to_std()))
+                })),
+                None => {
+                    let size = pool.buffer_size();
+                    Box::pin(stream::unfold((), move |_| async move {
+                        let mut buf = match DmaBuffer::new(size) {
+                            Some(buf) => buf,
+                            None => {
+                                let err = io::Error::from_raw_os_error(libc::ENOMEM);
+                                return Some((Err(err.into()), ()));
+                            }
+                        };
+                        let res = self.recv_from(buf.as_bytes_mut()).await;
+                        let res = res.map(|(sz, addr)| {
+                            buf.trim_to_size(sz);
+                            (buf, addr)
+                        });
+                        Some((res, ()))
+                    }))
+                }
+            };
+        stream
+    }
+
     /// Sends data on the socket to the given address. On success, returns the
     /// number of bytes written. Address type can be any implementor of
-    /// [`ToSocketAddrs`] trait. See its documentation for concrete examples.
-    /// It is possible for `addr` to yield multiple addresses, but send_to will
-    /// only send data to the first address yielded by `addr`.
+    /// [`ToSocketAddrs`] trait that doesn't borrow non-`'static` data, and
+    /// names are resolved without blocking the executor, as with
+    /// [`TcpStream::connect`]. It is possible for `addr` to yield multiple
+    /// addresses, but send_to will only send data to the first address
+    /// yielded by `addr`.
     ///
     /// # Examples
     ///
@@ -614,13 +732,14 @@ impl UdpSocket {
     /// })
     /// ```
     ///
-    /// [`ToSocketAddrs`]: https://doc.rust-lang.org/stable/std/net/trait.ToSocketAddrs.html
-    pub async fn send_to<A: ToSocketAddrs>(&self, buf: &[u8], addr: A) -> Result<usize> {
-        let addr = addr
-            .to_socket_addrs()
-            .unwrap()
-            .next()
-            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "empty address"))?;
+    /// [`ToSocketAddrs`]: std::net::ToSocketAddrs
+    /// [`TcpStream::connect`]: crate::net::TcpStream::connect
+    pub async fn send_to<A: net::ToSocketAddrs + Send + 'static>(
+        &self,
+        buf: &[u8],
+        addr: A,
+    ) -> Result<usize> {
+        let addr = resolve_addrs(addr).await?[0];
 
         let inet = nix::sys::socket::InetAddr::from_std(&addr);
         let sockaddr = nix::sys::socket::SockAddr::new_inet(inet);
@@ -653,6 +772,45 @@ impl UdpSocket {
     pub async fn send(&self, buf: &[u8]) -> Result<usize> {
         self.socket.send(buf).await.map_err(Into::into)
     }
+
+    /// Sends data on the socket to the remote address to which it is
+    /// connected, without copying it.
+    ///
+    /// This takes ownership of `buf`, which can be a [`DmaBuffer`], a
+    /// [`ReadResult`] or any other owned buffer. The kernel sends straight from
+    /// its memory, which is only released once the kernel is done with it,
+    /// possibly some time after this returns. Zero-copy sends pay off for
+    /// large datagrams; for small ones, [`send`] is usually faster.
+    ///
+    /// Zero-copy sends need Linux 6.0. On older kernels the data is copied
+    /// and sent like with [`send`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use glommio::{io::ImmutableFileBuilder, net::UdpSocket, LocalExecutor};
+    ///
+    /// let ex = LocalExecutor::default();
+    /// ex.run(async move {
+    ///     let file = ImmutableFileBuilder::new("data")
+    ///         .build_existing()
+    ///         .await
+    ///         .unwrap();
+    ///     let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    ///     sender.connect("127.0.0.1:8000").await.unwrap();
+    ///     let data = file.read_at(0, 8192).await.unwrap();
+    ///     sender.send_zc(data).await.unwrap();
+    /// })
+    /// ```
+    ///
+    /// [`send`]: UdpSocket::send
+    /// [`ReadResult`]: crate::io::ReadResult
+    pub async fn send_zc<T: AsRef<[u8]> + 'static>(&self, buf: T) -> Result<usize> {
+        self.socket
+            .send_zc(ZeroCopyBuffer::new(buf))
+            .await
+            .map_err(Into::into)
+    }
 }
 
 #[cfg(test)]
@@ -1068,4 +1226,36 @@ mod tests {
             assert_eq!(s.ttl().unwrap(), 42);
         });
     }
+
+    #[test]
+    fn recv_stream() {
+        test_executor!(async move {
+            let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let pool = BufferPool::new(4, 1024).unwrap();
+            let mut datagrams = receiver.recv_stream(&pool);
+
+            for i in 0..10u8 {
+                sender
+                    .send_to(&[i; 10], receiver.local_addr().unwrap())
+                    .await
+                    .unwrap();
+                let (buf, addr) = datagrams.next().await.unwrap().unwrap();
+                assert_eq!(buf.as_bytes(), &[i; 10]);
+                assert_eq!(addr, sender.local_addr().unwrap());
+            }
+        });
+    }
+
+    #[test]
+    fn send_zc() {
+        test_executor!(async move {
+            let (s1, s2) = connected_pair!();
+            assert_eq!(s1.send_zc(vec![1u8; 1000]).await.unwrap(), 1000);
+
+            let mut buf = vec![0; 2000];
+            assert_eq!(s2.recv(&mut buf).await.unwrap(), 1000);
+            assert!(buf[..1000].iter().all(|x| *x == 1));
+        });
+    }
 }
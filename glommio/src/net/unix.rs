@@ -5,13 +5,16 @@
 //
 use super::{datagram::GlommioDatagram, stream::GlommioStream};
 use crate::{
-    net::stream::{Buffered, NonBuffered, Preallocated, RxBuf},
+    net::{
+        stream::{Buffered, NonBuffered, Preallocated, RxBuf},
+        MultishotAccept,
+    },
     reactor::Reactor,
 };
 use futures_lite::{
     future::poll_fn,
     io::{AsyncBufRead, AsyncRead, AsyncWrite},
-    stream::{self, Stream},
+    stream::{self, Stream, StreamExt},
 };
 use nix::sys::socket::{SockAddr, UnixAddr};
 use pin_project_lite::pin_project;
@@ -191,6 +194,43 @@ impl UnixListener {
         }))
     }
 
+    /// Creates a stream of incoming connections backed by a single multishot
+    /// accept request.
+    ///
+    /// See [`TcpListener::incoming_multishot`] for details. On kernels older
+    /// than 5.19 this behaves exactly like [`incoming`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use futures_lite::stream::StreamExt;
+    /// use glommio::{net::UnixListener, LocalExecutor};
+    ///
+    /// let ex = LocalExecutor::default();
+    /// ex.run(async move {
+    ///     let listener = UnixListener::bind("/tmp/named").unwrap();
+    ///     let mut incoming = listener.incoming_multishot();
+    ///     while let Some(conn) = incoming.next().await {
+    ///         // handle
+    ///     }
+    /// });
+    /// ```
+    ///
+    /// [`TcpListener::incoming_multishot`]: crate::net::TcpListener::incoming_multishot
+    /// [`incoming`]: UnixListener::incoming
+    pub fn incoming_multishot(&self) -> impl Stream<Item = Result<UnixStream>> + Unpin + '_ {
+        let reactor = self.reactor.upgrade().unwrap();
+        let stream: Pin<Box<dyn Stream<Item = Result<UnixStream>> + '_>> =
+            match MultishotAccept::new(reactor, self.listener.as_raw_fd()) {
+                Some(multishot) => Box::pin(multishot.map(|res| {
+                    let fd = res?;
+                    Ok(AcceptedUnixStream { fd }.bind_to_executor())
+                })),
+                None => Box::pin(self.incoming()),
+            };
+        stream
+    }
+
     /// Returns the socket address of the local half of this Unix connection.
     ///
     /// # Examples
@@ -765,6 +805,22 @@ mod tests {
         listener_handle.await.unwrap();
     });
 
+    unix_socket_test!(incoming_multishot, dir, {
+        let mut file = dir.clone();
+        file.push("name");
+
+        let listener = UnixListener::bind(&file).unwrap();
+        let mut incoming = listener.incoming_multishot();
+        for _ in 0..3 {
+            let mut client = UnixStream::connect(&file).await.unwrap();
+            let mut stream = incoming.next().await.unwrap().unwrap();
+            client.write_all(b"hi").await.unwrap();
+            let mut buf = [0u8; 2];
+            stream.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"hi");
+        }
+    });
+
     unix_socket_test!(pair, _dir, {
         let (mut p1, mut p2) = UnixStream::pair().unwrap();
         let sz = p1.write(&[65u8; 1]).await.unwrap();
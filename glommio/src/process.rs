@@ -0,0 +1,400 @@
+// Unless explicitly stated otherwise all files in this repository are licensed
+// under the MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021 Datadog, Inc.
+//
+//! glommio::process is a module that spawns and manages child processes.
+//!
+//! [`Command`] mirrors [`std::process::Command`], but waiting for a child
+//! and talking to it through its standard streams never blocks the executor.
+//! The pipes connected to a child are polled by the reactor, and the exit of
+//! a child is detected by polling a `pidfd`, so no helper thread is needed.
+//!
+//! ```no_run
+//! use glommio::{process::Command, LocalExecutor};
+//!
+//! let ex = LocalExecutor::default();
+//! ex.run(async {
+//!     let output = Command::new("fsck")
+//!         .arg("-n")
+//!         .arg("/dev/sdb1")
+//!         .output()
+//!         .await
+//!         .unwrap();
+//!     println!("fsck exited with {}", output.status);
+//! });
+//! ```
+use crate::{io::PollFd, reactor::Reactor};
+use futures_lite::{future, io::AsyncReadExt, AsyncRead, AsyncWrite};
+use std::{
+    ffi::OsStr,
+    io,
+    os::unix::io::{AsRawFd, RawFd},
+    path::Path,
+    pin::Pin,
+    process::{self, ExitStatus, Output, Stdio},
+    rc::{Rc, Weak},
+    task::{Context, Poll},
+};
+
+type Result<T> = crate::Result<T, ()>;
+
+macro_rules! child_pipe {
+    ($(#[$attr:meta])* $name:ident, $inner:ty) => {
+        $(#[$attr])*
+        #[derive(Debug)]
+        pub struct $name {
+            fd: PollFd<$inner>,
+        }
+
+        impl $name {
+            fn new(inner: $inner) -> io::Result<$name> {
+                Ok($name {
+                    fd: PollFd::new(inner)?,
+                })
+            }
+        }
+
+        impl AsRawFd for $name {
+            fn as_raw_fd(&self) -> RawFd {
+                self.fd.inner().as_raw_fd()
+            }
+        }
+    };
+}
+
+child_pipe!(
+    /// The write end of a pipe connected to the standard input of a [`Child`].
+    ///
+    /// The child sees end of file once this is dropped.
+    ChildStdin,
+    process::ChildStdin
+);
+
+child_pipe!(
+    /// The read end of a pipe connected to the standard output of a
+    /// [`Child`].
+    ChildStdout,
+    process::ChildStdout
+);
+
+child_pipe!(
+    /// The read end of a pipe connected to the standard error of a [`Child`].
+    ChildStderr,
+    process::ChildStderr
+);
+
+impl AsyncWrite for ChildStdin {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.fd.poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncRead for ChildStdout {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.fd.poll_read(cx, buf)
+    }
+}
+
+impl AsyncRead for ChildStderr {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.fd.poll_read(cx, buf)
+    }
+}
+
+/// A process builder, providing fine-grained control over how a new process
+/// should be spawned.
+///
+/// This is a thin wrapper over [`std::process::Command`] and is configured
+/// the same way. Only spawning and waiting differ: the child's standard
+/// streams, when piped, are [`AsyncRead`]/[`AsyncWrite`] pipes driven by the
+/// reactor, and waiting for the child doesn't block the executor.
+///
+/// A `Command` can only be used from within an executor.
+#[derive(Debug)]
+pub struct Command {
+    inner: process::Command,
+}
+
+impl Command {
+    /// Constructs a new `Command` for launching the program at path
+    /// `program`. See [`std::process::Command::new`] for the defaults.
+    pub fn new<S: AsRef<OsStr>>(program: S) -> Command {
+        Command {
+            inner: process::Command::new(program),
+        }
+    }
+
+    /// Adds an argument to pass to the program.
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Command {
+        self.inner.arg(arg);
+        self
+    }
+
+    /// Adds multiple arguments to pass to the program.
+    pub fn args<I, S>(&mut self, args: I) -> &mut Command
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.inner.args(args);
+        self
+    }
+
+    /// Inserts or updates an environment variable of the child.
+    pub fn env<K, V>(&mut self, key: K, val: V) -> &mut Command
+    where
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.inner.env(key, val);
+        self
+    }
+
+    /// Inserts or updates multiple environment variables of the child.
+    pub fn envs<I, K, V>(&mut self, vars: I) -> &mut Command
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.inner.envs(vars);
+        self
+    }
+
+    /// Removes an environment variable from the child.
+    pub fn env_remove<K: AsRef<OsStr>>(&mut self, key: K) -> &mut Command {
+        self.inner.env_remove(key);
+        self
+    }
+
+    /// Clears the entire environment of the child.
+    pub fn env_clear(&mut self) -> &mut Command {
+        self.inner.env_clear();
+        self
+    }
+
+    /// Sets the working directory of the child.
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Command {
+        self.inner.current_dir(dir);
+        self
+    }
+
+    /// Configures the standard input of the child. Use [`Stdio::piped`] to
+    /// write to it through [`Child::stdin`].
+    pub fn stdin<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Command {
+        self.inner.stdin(cfg);
+        self
+    }
+
+    /// Configures the standard output of the child. Use [`Stdio::piped`] to
+    /// read from it through [`Child::stdout`].
+    pub fn stdout<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Command {
+        self.inner.stdout(cfg);
+        self
+    }
+
+    /// Configures the standard error of the child. Use [`Stdio::piped`] to
+    /// read from it through [`Child::stderr`].
+    pub fn stderr<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Command {
+        self.inner.stderr(cfg);
+        self
+    }
+
+    /// Executes the command as a child process, returning a handle to it.
+    ///
+    /// The standard streams of the child are inherited from the parent,
+    /// unless configured otherwise.
+    pub fn spawn(&mut self) -> Result<Child> {
+        Child::new(self.inner.spawn()?)
+    }
+
+    /// Executes the command as a child process, waiting for it to finish and
+    /// returning its exit status.
+    pub async fn status(&mut self) -> Result<ExitStatus> {
+        self.spawn()?.wait().await
+    }
+
+    /// Executes the command as a child process, waiting for it to finish and
+    /// collecting all of its output.
+    ///
+    /// The standard output and error of the child are always captured, and
+    /// its standard input is inherited unless configured otherwise.
+    pub async fn output(&mut self) -> Result<Output> {
+        self.inner.stdout(Stdio::piped()).stderr(Stdio::piped());
+        self.spawn()?.wait_with_output().await
+    }
+}
+
+impl From<process::Command> for Command {
+    fn from(inner: process::Command) -> Command {
+        Command { inner }
+    }
+}
+
+/// A handle to a child process, created by [`Command::spawn`].
+///
+/// Like with [`std::process::Child`], dropping a `Child` neither kills the
+/// process nor waits for it. A child that is never waited for stays around
+/// as a zombie until the parent exits.
+#[derive(Debug)]
+pub struct Child {
+    /// The handle to write to the standard input of the child, if it was
+    /// piped.
+    pub stdin: Option<ChildStdin>,
+    /// The handle to read from the standard output of the child, if it was
+    /// piped.
+    pub stdout: Option<ChildStdout>,
+    /// The handle to read from the standard error of the child, if it was
+    /// piped.
+    pub stderr: Option<ChildStderr>,
+    child: process::Child,
+    pidfd: RawFd,
+    reactor: Weak<Reactor>,
+}
+
+impl Child {
+    fn new(mut child: process::Child) -> Result<Child> {
+        let pidfd = unsafe { libc::syscall(libc::SYS_pidfd_open, child.id(), 0) } as RawFd;
+        if pidfd < 0 {
+            let err = io::Error::last_os_error();
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(err.into());
+        }
+        let mut ret = Child {
+            stdin: None,
+            stdout: None,
+            stderr: None,
+            child,
+            pidfd,
+            reactor: Rc::downgrade(&crate::executor().reactor()),
+        };
+        ret.stdin = ret.child.stdin.take().map(ChildStdin::new).transpose()?;
+        ret.stdout = ret.child.stdout.take().map(ChildStdout::new).transpose()?;
+        ret.stderr = ret.child.stderr.take().map(ChildStderr::new).transpose()?;
+        Ok(ret)
+    }
+
+    /// Returns the OS-assigned process identifier of the child.
+    pub fn id(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Forces the child to exit, with `SIGKILL`.
+    pub fn kill(&mut self) -> Result<()> {
+        self.child.kill().map_err(Into::into)
+    }
+
+    /// Returns the exit status of the child if it exited, without waiting.
+    pub fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+        self.child.try_wait().map_err(Into::into)
+    }
+
+    /// Waits for the child to exit and returns its exit status.
+    ///
+    /// The standard input of the child is closed before waiting, so a child
+    /// reading from it doesn't wait for more input forever.
+    pub async fn wait(&mut self) -> Result<ExitStatus> {
+        drop(self.stdin.take());
+        loop {
+            if let Some(status) = self.child.try_wait()? {
+                return Ok(status);
+            }
+            let source = self.reactor.upgrade().unwrap().poll_read_ready(self.pidfd);
+            source.collect_rw().await?;
+        }
+    }
+
+    /// Waits for the child to exit and collects everything it wrote to its
+    /// piped standard output and error.
+    pub async fn wait_with_output(mut self) -> Result<Output> {
+        drop(self.stdin.take());
+        let (stdout, stderr) =
+            future::try_zip(read_all(self.stdout.take()), read_all(self.stderr.take())).await?;
+        let status = self.wait().await?;
+        Ok(Output {
+            status,
+            stdout,
+            stderr,
+        })
+    }
+}
+
+impl Drop for Child {
+    fn drop(&mut self) {
+        let _ = nix::unistd::close(self.pidfd);
+    }
+}
+
+async fn read_all<R: AsyncRead + Unpin>(reader: Option<R>) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    if let Some(mut reader) = reader {
+        reader.read_to_end(&mut buf).await?;
+    }
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures_lite::AsyncWriteExt;
+
+    #[test]
+    fn command_output() {
+        test_executor!(async move {
+            let output = Command::new("sh")
+                .arg("-c")
+                .arg("echo out; echo err >&2; exit 3")
+                .output()
+                .await
+                .unwrap();
+            assert_eq!(output.status.code(), Some(3));
+            assert_eq!(output.stdout, b"out\n");
+            assert_eq!(output.stderr, b"err\n");
+
+            let status = Command::new("true").status().await.unwrap();
+            assert!(status.success());
+        });
+    }
+
+    #[test]
+    fn child_pipes() {
+        test_executor!(async move {
+            let mut child = Command::new("cat")
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()
+                .unwrap();
+            let mut stdin = child.stdin.take().unwrap();
+            stdin.write_all(b"hello").await.unwrap();
+            drop(stdin);
+
+            let mut out = String::new();
+            let mut stdout = child.stdout.take().unwrap();
+            stdout.read_to_string(&mut out).await.unwrap();
+            assert_eq!(out, "hello");
+            assert!(child.wait().await.unwrap().success());
+        });
+    }
+}
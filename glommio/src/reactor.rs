@@ -33,7 +33,8 @@ use crate::{
     iou::sqe::SockAddrStorage,
     sys::{
         self, common_flags, read_flags, sysfs, DirectIo, DmaBuffer, DmaSource, IoBuffer,
-        PollableStatus, SleepNotifier, Source, SourceType, StatsCollection, Statx,
+        PollableStatus, ProvidedBufferGroup, SleepNotifier, Source, SourceType, StatsCollection,
+        Statx, UringBufferAllocator, ZeroCopyBuffer,
     },
     IoRequirements, IoStats, PoolPlacement, TaskQueueHandle,
 };
@@ -178,11 +179,18 @@ impl Reactor {
     pub(crate) fn new(
         notifier: Arc<SleepNotifier>,
         io_memory: usize,
+        io_arena: Option<UringBufferAllocator>,
         ring_depth: usize,
         record_io_latencies: bool,
         thread_pool_placement: PoolPlacement,
     ) -> io::Result<Reactor> {
-        let sys = sys::Reactor::new(notifier, io_memory, ring_depth, thread_pool_placement)?;
+        let sys = sys::Reactor::new(
+            notifier,
+            io_memory,
+            io_arena,
+            ring_depth,
+            thread_pool_placement,
+        )?;
         let (preempt_ptr_head, preempt_ptr_tail) = sys.preempt_pointers();
         Ok(Reactor {
             sys,
@@ -199,6 +207,10 @@ impl Reactor {
         self.sys.io_stats()
     }
 
+    pub(crate) fn io_memory_page_size(&self) -> usize {
+        self.sys.io_memory_page_size()
+    }
+
     pub(crate) fn task_queue_io_stats(&self, handle: &TaskQueueHandle) -> Option<IoStats> {
         self.sys.task_queue_io_stats(handle)
     }
@@ -222,13 +234,27 @@ impl Reactor {
         stype: SourceType,
         stats_collection: Option<StatsCollection>,
     ) -> Source {
-        sys::Source::new(
+        let source = sys::Source::new(
             self.io_scheduler.requirements(),
             raw,
             stype,
             stats_collection,
             Some(crate::executor().current_task_queue()),
-        )
+        );
+        if let Some(slot) = self.sys.fixed_file(raw) {
+            source.set_fixed_file(slot);
+        }
+        source
+    }
+
+    /// Registers `fd` in the fixed file table of the rings. Requests on it use
+    /// the table from then on, until it is closed or unregistered.
+    pub(crate) fn register_file(&self, fd: RawFd) -> io::Result<()> {
+        self.sys.register_file(fd)
+    }
+
+    pub(crate) fn unregister_file(&self, fd: RawFd) {
+        self.sys.unregister_file(fd)
     }
 
     pub(crate) fn inform_io_requirements(&self, req: IoRequirements) {
@@ -319,6 +345,13 @@ impl Reactor {
         self.sys.alloc_dma_buffer(size)
     }
 
+    pub(crate) fn register_buffer_arena(
+        &self,
+        arena: &Rc<sys::UringBufferAllocator>,
+    ) -> io::Result<()> {
+        self.sys.register_buffer_arena(arena)
+    }
+
     pub(crate) fn write_dma(
         &self,
         raw: RawFd,
@@ -370,6 +403,83 @@ impl Reactor {
         source
     }
 
+    /// Writes `bufs` back to back starting at `pos`, with a single request.
+    pub(crate) fn write_vectored(
+        &self,
+        raw: RawFd,
+        bufs: Vec<IoBuffer>,
+        pos: u64,
+        pollable: PollableStatus,
+    ) -> Source {
+        let stats = StatsCollection {
+            fulfilled: match pollable {
+                PollableStatus::NonPollable(DirectIo::Disabled) => {
+                    Some(|result, stats, op_count| {
+                        if let Ok(result) = result {
+                            stats.file_buffered_writes += op_count;
+                            stats.file_buffered_bytes_written += *result as u64 * op_count;
+                        }
+                    })
+                }
+                _ => Some(|result, stats, op_count| {
+                    if let Ok(result) = result {
+                        stats.file_writes += op_count;
+                        stats.file_bytes_written += *result as u64 * op_count;
+                    }
+                }),
+            },
+            reused: None,
+            latency: None,
+        };
+
+        let source = self.new_source(
+            raw,
+            SourceType::WriteVectored(pollable, bufs, Vec::new()),
+            Some(stats),
+        );
+        self.sys.write_vectored(&source, pos);
+        source
+    }
+
+    /// Fills `bufs` one after the other with the data starting at `pos`, with
+    /// a single request.
+    pub(crate) fn read_vectored(
+        &self,
+        raw: RawFd,
+        bufs: Vec<IoBuffer>,
+        pos: u64,
+        pollable: PollableStatus,
+    ) -> Source {
+        let stats = StatsCollection {
+            fulfilled: match pollable {
+                PollableStatus::NonPollable(DirectIo::Disabled) => {
+                    Some(|result, stats, op_count| {
+                        if let Ok(result) = result {
+                            stats.file_buffered_reads += op_count;
+                            stats.file_buffered_bytes_read += *result as u64 * op_count;
+                        }
+                    })
+                }
+                _ => Some(|result, stats, op_count| {
+                    if let Ok(result) = result {
+                        stats.file_reads += op_count;
+                        stats.file_bytes_read += *result as u64 * op_count;
+                    }
+                }),
+            },
+            reused: None,
+            latency: None,
+        };
+
+        let source = self.new_source(
+            raw,
+            SourceType::ReadVectored(pollable, bufs, Vec::new()),
+            Some(stats),
+        );
+        self.sys.read_vectored(&source, pos);
+        source
+    }
+
     pub(crate) fn connect(&self, raw: RawFd, addr: SockAddr) -> Source {
         let source = self.new_source(raw, SourceType::Connect(addr), None);
         self.sys.connect(&source);
@@ -390,6 +500,59 @@ impl Reactor {
         source
     }
 
+    /// Returns `None` if the kernel can't accept more than one connection per
+    /// request.
+    pub(crate) fn accept_multi(&self, raw: RawFd) -> Option<Source> {
+        if !self.sys.supports_multishot_accept() {
+            return None;
+        }
+        let source = self.new_source(raw, SourceType::AcceptMulti, None);
+        source.set_multishot(Box::new(|res, _| {
+            if let Ok(fd) = res {
+                let _ = nix::unistd::close(fd as RawFd);
+            }
+        }));
+        self.sys.accept_multi(&source);
+        Some(source)
+    }
+
+    /// Receives into buffers the kernel picks from `group`, posting an event
+    /// for each. Returns `None` if the kernel can't receive more than once
+    /// per request.
+    pub(crate) fn recv_multi(&self, fd: RawFd, group: &Rc<ProvidedBufferGroup>) -> Option<Source> {
+        if !self.sys.supports_multishot_recv() {
+            return None;
+        }
+        let source = self.new_source(fd, SourceType::RecvMulti(group.clone()), None);
+        source.set_multishot(Self::release_provided_buffer(group));
+        self.sys.recv_multi(&source, MsgFlags::empty());
+        Some(source)
+    }
+
+    /// Like [`Reactor::recv_multi`], but each buffer also carries the address
+    /// of the sender. See [`sys::parse_recvmsg_out`].
+    pub(crate) fn recvmsg_multi(
+        &self,
+        fd: RawFd,
+        group: &Rc<ProvidedBufferGroup>,
+    ) -> Option<Source> {
+        if !self.sys.supports_multishot_recv() {
+            return None;
+        }
+        let mut hdr = unsafe { std::mem::zeroed::<libc::msghdr>() };
+        hdr.msg_namelen =
+            std::mem::size_of::<nix::sys::socket::sockaddr_storage>() as libc::socklen_t;
+        let source = self.new_source(fd, SourceType::RecvMsgMulti(group.clone(), hdr), None);
+        source.set_multishot(Self::release_provided_buffer(group));
+        self.sys.recv_multi(&source, MsgFlags::empty());
+        Some(source)
+    }
+
+    fn release_provided_buffer(group: &Rc<ProvidedBufferGroup>) -> sys::DiscardFn {
+        let group = group.clone();
+        Box::new(move |_, flags| drop(group.take(flags, 0)))
+    }
+
     pub(crate) fn poll_read_ready(&self, fd: RawFd) -> Source {
         let source = self.new_source(fd, SourceType::PollAdd, None);
         self.sys.poll_ready(&source, common_flags() | read_flags());
@@ -418,6 +581,26 @@ impl Reactor {
         Ok(source)
     }
 
+    pub(crate) fn supports_send_zc(&self) -> bool {
+        self.sys.supports_send_zc()
+    }
+
+    /// Sends `buf` without copying it. The source completes once the data is
+    /// sent, but `buf` is only released when the kernel is done with it.
+    pub(crate) fn send_zc(
+        &self,
+        fd: RawFd,
+        buf: ZeroCopyBuffer,
+        timeout: Option<Duration>,
+    ) -> Source {
+        let source = self.new_source(fd, SourceType::SockSendZc(buf), None);
+        if let Some(timeout) = timeout {
+            source.set_timeout(timeout);
+        }
+        self.sys.send_zc(&source, MsgFlags::empty());
+        source
+    }
+
     pub(crate) fn rushed_sendmsg(
         &self,
         fd: RawFd,
@@ -488,6 +671,31 @@ impl Reactor {
         Ok(source)
     }
 
+    pub(crate) fn provided_buffer_group(
+        &self,
+        count: usize,
+        buffer_size: usize,
+    ) -> io::Result<Rc<ProvidedBufferGroup>> {
+        self.sys.provided_buffer_group(count, buffer_size)
+    }
+
+    /// Receives into a buffer the kernel picks from `group`. The buffer is
+    /// stored in the source once it completes.
+    pub(crate) fn recv_select(
+        &self,
+        fd: RawFd,
+        group: &Rc<ProvidedBufferGroup>,
+        timeout: Option<Duration>,
+    ) -> Source {
+        let source = self.new_source(fd, SourceType::SockRecvSelect(group.clone(), None), None);
+        if let Some(timeout) = timeout {
+            source.set_timeout(timeout);
+        }
+        self.sys
+            .recv_select(&source, group.buffer_size(), MsgFlags::empty());
+        source
+    }
+
     pub(crate) fn recv(&self, fd: RawFd, size: usize, flags: MsgFlags) -> Source {
         let source = self.new_source(fd, SourceType::SockRecv(None), None);
         self.sys.recv(&source, size, flags);
@@ -604,6 +812,37 @@ impl Reactor {
         source
     }
 
+    pub(crate) fn fsync(&self, raw: RawFd) -> Source {
+        let source = self.new_source(raw, SourceType::Fsync, None);
+        self.sys.fsync(&source);
+        source
+    }
+
+    pub(crate) fn sync_file_range(&self, raw: RawFd, offset: u64, len: u32, flags: u32) -> Source {
+        let source = self.new_source(raw, SourceType::SyncFileRange, None);
+        self.sys.sync_file_range(&source, offset, len, flags);
+        source
+    }
+
+    pub(crate) fn splice(
+        &self,
+        fd_in: RawFd,
+        off_in: Option<u64>,
+        fd_out: RawFd,
+        off_out: Option<u64>,
+        len: u32,
+    ) -> Source {
+        let source = self.new_source(fd_out, SourceType::Splice, None);
+        self.sys.splice(&source, fd_in, off_in, off_out, len);
+        source
+    }
+
+    pub(crate) fn tee(&self, fd_in: RawFd, fd_out: RawFd, len: u32) -> Source {
+        let source = self.new_source(fd_out, SourceType::Tee, None);
+        self.sys.tee(&source, fd_in, len);
+        source
+    }
+
     pub(crate) fn fallocate(
         &self,
         raw: RawFd,
@@ -616,6 +855,29 @@ impl Reactor {
         source
     }
 
+    pub(crate) fn fadvise(
+        &self,
+        raw: RawFd,
+        offset: u64,
+        len: u64,
+        advice: nix::fcntl::PosixFadviseAdvice,
+    ) -> Source {
+        let source = self.new_source(raw, SourceType::Advise, None);
+        self.sys.fadvise(&source, offset, len, advice);
+        source
+    }
+
+    pub(crate) fn madvise(
+        &self,
+        addr: *mut u8,
+        len: usize,
+        advice: nix::sys::mman::MmapAdvise,
+    ) -> Source {
+        let source = self.new_source(-1, SourceType::Advise, None);
+        self.sys.madvise(&source, addr, len, advice);
+        source
+    }
+
     pub(crate) fn truncate(&self, raw: RawFd, size: u64) -> impl Future<Output = Source> {
         let source = self.new_source(raw, SourceType::Truncate, None);
         let waiter = self.sys.truncate(&source, size);
@@ -626,6 +888,16 @@ impl Reactor {
         }
     }
 
+    pub(crate) fn read_dir(&self, raw: RawFd, size: usize) -> impl Future<Output = Source> {
+        let source = self.new_source(raw, SourceType::ReadDir(Vec::new()), None);
+        let waiter = self.sys.read_dir(&source, size);
+
+        async move {
+            waiter.await;
+            source
+        }
+    }
+
     pub(crate) fn rename<P, Q>(&self, old_path: P, new_path: Q) -> impl Future<Output = Source>
     where
         P: AsRef<Path>,
@@ -644,6 +916,105 @@ impl Reactor {
         }
     }
 
+    pub(crate) fn rename_at(
+        &self,
+        old_dir: RawFd,
+        old_path: &Path,
+        new_dir: RawFd,
+        new_path: &Path,
+        flags: libc::c_uint,
+    ) -> impl Future<Output = Source> {
+        let old_path = CString::new(old_path.as_os_str().as_bytes()).expect("path contained null!");
+        let new_path = CString::new(new_path.as_os_str().as_bytes()).expect("path contained null!");
+        let source = self.new_source(old_dir, SourceType::RenameAt(old_path, new_path), None);
+        let waiter = self.sys.rename_at(&source, new_dir, flags);
+
+        async move {
+            waiter.await;
+            source
+        }
+    }
+
+    pub(crate) fn link_at(
+        &self,
+        old_dir: RawFd,
+        old_path: &Path,
+        new_dir: RawFd,
+        new_path: &Path,
+        flags: libc::c_int,
+    ) -> impl Future<Output = Source> {
+        let old_path = CString::new(old_path.as_os_str().as_bytes()).expect("path contained null!");
+        let new_path = CString::new(new_path.as_os_str().as_bytes()).expect("path contained null!");
+        let source = self.new_source(old_dir, SourceType::LinkAt(old_path, new_path), None);
+        let waiter = self.sys.link_at(&source, new_dir, flags);
+
+        async move {
+            waiter.await;
+            source
+        }
+    }
+
+    pub(crate) fn symlink_at(
+        &self,
+        target: &Path,
+        new_dir: RawFd,
+        link_path: &Path,
+    ) -> impl Future<Output = Source> {
+        let target = CString::new(target.as_os_str().as_bytes()).expect("path contained null!");
+        let link_path =
+            CString::new(link_path.as_os_str().as_bytes()).expect("path contained null!");
+        let source = self.new_source(new_dir, SourceType::SymlinkAt(target, link_path), None);
+        let waiter = self.sys.symlink_at(&source);
+
+        async move {
+            waiter.await;
+            source
+        }
+    }
+
+    pub(crate) fn unlink_at(
+        &self,
+        dir: RawFd,
+        path: &Path,
+        flags: libc::c_int,
+    ) -> impl Future<Output = Source> {
+        let path = CString::new(path.as_os_str().as_bytes()).expect("path contained null!");
+        let source = self.new_source(dir, SourceType::UnlinkAt(path), None);
+        let waiter = self.sys.unlink_at(&source, flags);
+
+        async move {
+            waiter.await;
+            source
+        }
+    }
+
+    pub(crate) fn mkdir_at(
+        &self,
+        dir: RawFd,
+        path: &Path,
+        mode: libc::mode_t,
+    ) -> impl Future<Output = Source> {
+        let path = CString::new(path.as_os_str().as_bytes()).expect("path contained null!");
+        let source = self.new_source(dir, SourceType::MkdirAt(path), None);
+        let waiter = self.sys.mkdir_at(&source, mode);
+
+        async move {
+            waiter.await;
+            source
+        }
+    }
+
+    pub(crate) fn read_link(&self, dir: RawFd, path: &Path) -> impl Future<Output = Source> {
+        let path = CString::new(path.as_os_str().as_bytes()).expect("path contained null!");
+        let source = self.new_source(dir, SourceType::ReadLink(Vec::new()), None);
+        let waiter = self.sys.read_link(&source, path);
+
+        async move {
+            waiter.await;
+            source
+        }
+    }
+
     pub(crate) fn remove_file<P: AsRef<Path>>(&self, path: P) -> impl Future<Output = Source> {
         let source = self.new_source(-1, SourceType::Remove(path.as_ref().to_owned()), None);
         let waiter = self.sys.remove_file(&source);
@@ -654,6 +1025,16 @@ impl Reactor {
         }
     }
 
+    pub(crate) fn remove_dir<P: AsRef<Path>>(&self, path: P) -> impl Future<Output = Source> {
+        let source = self.new_source(-1, SourceType::Remove(path.as_ref().to_owned()), None);
+        let waiter = self.sys.remove_dir(&source);
+
+        async move {
+            waiter.await;
+            source
+        }
+    }
+
     pub(crate) fn create_dir<P: AsRef<Path>>(
         &self,
         path: P,
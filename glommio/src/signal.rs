@@ -0,0 +1,373 @@
+// Unless explicitly stated otherwise all files in this repository are licensed
+// under the MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021 Datadog, Inc.
+//
+//! glommio::signal is a module that provides asynchronous handling of Unix
+//! signals.
+//!
+//! A [`Signal`] is a stream that yields every time the process receives a
+//! given signal. Unlike a regular signal handler, which runs on whatever
+//! thread the kernel picks, a signal is delivered to every executor that
+//! subscribed to it. That makes it easy to shut down all shards of a server
+//! when it receives `SIGTERM`:
+//!
+//! ```no_run
+//! use futures_lite::StreamExt;
+//! use glommio::{signal::signal, sync::Gate, LocalExecutorPoolBuilder, PoolPlacement};
+//!
+//! LocalExecutorPoolBuilder::new(PoolPlacement::Unbound(4))
+//!     .on_all_shards(|| async move {
+//!         let gate = Gate::new();
+//!         // ... spawn the shard's tasks with `gate.spawn` ...
+//!         let mut sigterm = signal(libc::SIGTERM).unwrap();
+//!         sigterm.next().await;
+//!         gate.close().await.unwrap();
+//!     })
+//!     .unwrap()
+//!     .join_all();
+//! ```
+//!
+//! Each executor that subscribes to a signal blocks it in its thread and reads
+//! it from a `signalfd` polled by its reactor. Once any executor subscribed
+//! to a signal, a handler is installed for it that forwards the signal to the
+//! subscribed executors when it lands on any other thread, so the default
+//! action of the signal, like terminating the process for `SIGTERM`, no longer
+//! applies. The handler stays installed for the lifetime of the process.
+//!
+//! Only signals sent to the process are forwarded. A signal sent to a
+//! specific thread with `tgkill` or `pthread_kill` is only seen by that
+//! thread, if it subscribed to it.
+use crate::reactor::Reactor;
+use ahash::{AHashMap, AHashSet};
+use futures_lite::Stream;
+use std::{
+    cell::{Cell, RefCell},
+    io, mem,
+    os::unix::io::RawFd,
+    pin::Pin,
+    rc::{Rc, Weak},
+    sync::{
+        atomic::{AtomicI32, AtomicU64, Ordering},
+        Mutex, PoisonError,
+    },
+    task::{Context, Poll},
+};
+
+type Result<T> = crate::Result<T, ()>;
+
+/// How many threads can be subscribed to signals at the same time.
+const MAX_SUBSCRIBERS: usize = 1024;
+
+/// A thread subscribed to signals. Read from signal handlers, so it can only
+/// be made of atomics.
+struct Subscriber {
+    // The thread id, or zero if the slot is free
+    tid: AtomicI32,
+    // Bit `n - 1` is set if the thread is subscribed to signal `n`
+    signals: AtomicU64,
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const FREE_SUBSCRIBER: Subscriber = Subscriber {
+    tid: AtomicI32::new(0),
+    signals: AtomicU64::new(0),
+};
+
+static SUBSCRIBERS: [Subscriber; MAX_SUBSCRIBERS] = [FREE_SUBSCRIBER; MAX_SUBSCRIBERS];
+
+lazy_static! {
+    static ref HANDLERS: Mutex<AHashSet<libc::c_int>> = Mutex::new(AHashSet::new());
+}
+
+thread_local! {
+    static THREAD_SIGNALS: RefCell<ThreadSignals> = RefCell::new(ThreadSignals::default());
+}
+
+fn signal_bit(signal: libc::c_int) -> u64 {
+    1 << (signal - 1)
+}
+
+/// Sends `signal` to every subscribed thread but `except`. Async-signal-safe.
+fn broadcast(signal: libc::c_int, except: libc::pid_t) {
+    let pid = unsafe { libc::getpid() };
+    for subscriber in SUBSCRIBERS.iter() {
+        let tid = subscriber.tid.load(Ordering::Acquire);
+        if tid != 0
+            && tid != except
+            && subscriber.signals.load(Ordering::Acquire) & signal_bit(signal) != 0
+        {
+            unsafe { libc::syscall(libc::SYS_tgkill, pid, tid, signal) };
+        }
+    }
+}
+
+fn install_handler(signal: libc::c_int) -> io::Result<()> {
+    // The registry panics on these
+    if signal_hook_registry::FORBIDDEN.contains(&signal) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the signal can't be handled",
+        ));
+    }
+    // The set only grows after a handler was installed, so it is right even
+    // if a thread panicked while holding the lock
+    let mut handlers = HANDLERS.lock().unwrap_or_else(PoisonError::into_inner);
+    if !handlers.contains(&signal) {
+        unsafe {
+            signal_hook_registry::register_sigaction(signal, move |info| {
+                // Signals sent to a specific thread, like the ones the stall
+                // detector sends to the executor it watches, are only meant
+                // for that thread
+                if info.si_code != libc::SI_TKILL {
+                    broadcast(signal, 0);
+                }
+            })?
+        };
+        handlers.insert(signal);
+    }
+    Ok(())
+}
+
+fn mask_signal(how: libc::c_int, signal: libc::c_int) -> io::Result<libc::sigset_t> {
+    unsafe {
+        let mut set = mem::zeroed();
+        libc::sigemptyset(&mut set);
+        libc::sigaddset(&mut set, signal);
+        match libc::pthread_sigmask(how, &set, std::ptr::null_mut()) {
+            0 => Ok(set),
+            err => Err(io::Error::from_raw_os_error(err)),
+        }
+    }
+}
+
+/// The signals the current thread is subscribed to.
+#[derive(Default)]
+struct ThreadSignals {
+    slot: Option<usize>,
+    fds: AHashMap<libc::c_int, Weak<SignalFd>>,
+}
+
+impl ThreadSignals {
+    fn subscribe(&mut self, signal: libc::c_int) -> Result<Rc<SignalFd>> {
+        if let Some(fd) = self.fds.get(&signal).and_then(Weak::upgrade) {
+            return Ok(fd);
+        }
+        let slot = match self.slot {
+            Some(slot) => slot,
+            None => {
+                let tid = nix::unistd::gettid().as_raw();
+                let slot = SUBSCRIBERS
+                    .iter()
+                    .position(|x| {
+                        x.tid
+                            .compare_exchange(0, tid, Ordering::AcqRel, Ordering::Relaxed)
+                            .is_ok()
+                    })
+                    .ok_or_else(|| {
+                        io::Error::new(io::ErrorKind::Other, "too many threads handle signals")
+                    })?;
+                self.slot = Some(slot);
+                slot
+            }
+        };
+
+        match SignalFd::new(signal, slot) {
+            Ok(fd) => {
+                let fd = Rc::new(fd);
+                self.fds.insert(signal, Rc::downgrade(&fd));
+                Ok(fd)
+            }
+            Err(err) => {
+                self.unsubscribe(signal);
+                Err(err.into())
+            }
+        }
+    }
+
+    fn unsubscribe(&mut self, signal: libc::c_int) {
+        self.fds.remove(&signal);
+        if self.fds.is_empty() {
+            if let Some(slot) = self.slot.take() {
+                SUBSCRIBERS[slot].tid.store(0, Ordering::Release);
+            }
+        }
+    }
+}
+
+impl Drop for ThreadSignals {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot.take() {
+            SUBSCRIBERS[slot].signals.store(0, Ordering::Release);
+            SUBSCRIBERS[slot].tid.store(0, Ordering::Release);
+        }
+    }
+}
+
+/// A `signalfd` shared by all the [`Signal`] streams of a thread for the same
+/// signal.
+#[derive(Debug)]
+struct SignalFd {
+    signal: libc::c_int,
+    fd: RawFd,
+    slot: usize,
+    reactor: Weak<Reactor>,
+    source: RefCell<Option<crate::sys::Source>>,
+    received: Cell<u64>,
+}
+
+impl SignalFd {
+    fn new(signal: libc::c_int, slot: usize) -> io::Result<SignalFd> {
+        let set = mask_signal(libc::SIG_BLOCK, signal)?;
+        let fd = unsafe { libc::signalfd(-1, &set, libc::SFD_NONBLOCK | libc::SFD_CLOEXEC) };
+        if fd < 0 {
+            let err = io::Error::last_os_error();
+            let _ = mask_signal(libc::SIG_UNBLOCK, signal);
+            return Err(err);
+        }
+        SUBSCRIBERS[slot]
+            .signals
+            .fetch_or(signal_bit(signal), Ordering::AcqRel);
+        Ok(SignalFd {
+            signal,
+            fd,
+            slot,
+            reactor: Rc::downgrade(&crate::executor().reactor()),
+            source: RefCell::new(None),
+            received: Cell::new(0),
+        })
+    }
+
+    /// Reads the pending signals, forwarding those that didn't come from
+    /// another subscriber to the rest of them.
+    fn drain(&self) {
+        let tid = SUBSCRIBERS[self.slot].tid.load(Ordering::Relaxed);
+        loop {
+            let mut info: libc::signalfd_siginfo = unsafe { mem::zeroed() };
+            let size = mem::size_of::<libc::signalfd_siginfo>();
+            let read =
+                unsafe { libc::read(self.fd, &mut info as *mut _ as *mut libc::c_void, size) };
+            if read != size as isize {
+                return;
+            }
+            if info.ssi_code != libc::SI_TKILL || info.ssi_pid != std::process::id() {
+                broadcast(self.signal, tid);
+            }
+            self.received.set(self.received.get() + 1);
+        }
+    }
+
+    fn poll_received(&self, cx: &mut Context<'_>, seen: &mut u64) -> Poll<Option<()>> {
+        self.drain();
+        if self.received.get() != *seen {
+            *seen = self.received.get();
+            return Poll::Ready(Some(()));
+        }
+
+        let mut source = self.source.borrow_mut();
+        if source.as_ref().map_or(true, |x| x.result().is_some()) {
+            let reactor = self.reactor.upgrade().unwrap();
+            *source = Some(reactor.poll_read_ready(self.fd));
+        }
+        source.as_ref().unwrap().add_waiter_many(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for SignalFd {
+    fn drop(&mut self) {
+        SUBSCRIBERS[self.slot]
+            .signals
+            .fetch_and(!signal_bit(self.signal), Ordering::AcqRel);
+        // Consume what was forwarded to this thread, so it isn't handled again
+        // once unblocked
+        self.drain();
+        self.source.take();
+        let _ = nix::unistd::close(self.fd);
+        let _ = mask_signal(libc::SIG_UNBLOCK, self.signal);
+        let _ = THREAD_SIGNALS.try_with(|x| x.borrow_mut().unsubscribe(self.signal));
+    }
+}
+
+/// A stream that yields every time the process receives a signal.
+///
+/// Created by [`signal`]. Signals that arrive while the stream isn't polled
+/// are coalesced, so the stream yields at least once for any number of
+/// signals received since it was last polled.
+#[derive(Debug)]
+pub struct Signal {
+    fd: Rc<SignalFd>,
+    seen: u64,
+}
+
+impl Stream for Signal {
+    type Item = ();
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<()>> {
+        let this = self.get_mut();
+        this.fd.poll_received(cx, &mut this.seen)
+    }
+}
+
+/// Subscribes the current executor to `signal`, one of the `libc::SIG*`
+/// constants.
+///
+/// Every executor that subscribed to a signal gets it, no matter which
+/// thread the kernel delivered it to. Signals that can't be handled, like
+/// `SIGKILL` and `SIGSEGV`, are rejected.
+///
+/// # Examples
+///
+/// ```no_run
+/// use futures_lite::StreamExt;
+/// use glommio::{signal::signal, LocalExecutor};
+///
+/// let ex = LocalExecutor::default();
+/// ex.run(async {
+///     let mut sighup = signal(libc::SIGHUP).unwrap();
+///     while sighup.next().await.is_some() {
+///         println!("reloading configuration");
+///     }
+/// });
+/// ```
+pub fn signal(signal: libc::c_int) -> Result<Signal> {
+    if signal <= 0 || signal > 64 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid signal number").into());
+    }
+    install_handler(signal)?;
+    let fd = THREAD_SIGNALS.with(|x| x.borrow_mut().subscribe(signal))?;
+    let seen = fd.received.get();
+    Ok(Signal { fd, seen })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::timer::timeout;
+    use futures_lite::StreamExt;
+    use std::time::Duration;
+
+    #[test]
+    fn signal_reaches_all_streams() {
+        test_executor!(async move {
+            let mut first = signal(libc::SIGUSR2).unwrap();
+            let mut second = signal(libc::SIGUSR2).unwrap();
+
+            unsafe { libc::kill(libc::getpid(), libc::SIGUSR2) };
+            timeout(Duration::from_secs(5), async {
+                first.next().await;
+                second.next().await;
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+            for forbidden in [libc::SIGKILL, libc::SIGSTOP, libc::SIGSEGV] {
+                let err = io::Error::from(signal(forbidden).unwrap_err());
+                assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+            }
+            // the handlers are still usable after a rejected signal
+            signal(libc::SIGUSR2).unwrap();
+        });
+    }
+}
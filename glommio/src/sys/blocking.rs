@@ -1,6 +1,6 @@
 use crate::{
     executor::bind_to_cpu_set,
-    sys::{InnerSource, SleepNotifier},
+    sys::{InnerSource, SleepNotifier, SourceType},
     PoolPlacement,
 };
 use ahash::AHashMap;
@@ -59,8 +59,16 @@ macro_rules! c_str {
 pub(super) enum BlockingThreadOp {
     Rename(PathBuf, PathBuf),
     Remove(PathBuf),
+    RemoveDir(PathBuf),
     CreateDir(PathBuf, libc::c_int),
     Truncate(RawFd, i64),
+    ReadDir(RawFd, usize),
+    RenameAt(RawFd, CString, RawFd, CString, libc::c_uint),
+    LinkAt(RawFd, CString, RawFd, CString, libc::c_int),
+    SymlinkAt(CString, RawFd, CString),
+    ReadLink(RawFd, CString),
+    UnlinkAt(RawFd, CString, libc::c_int),
+    MkdirAt(RawFd, CString, libc::mode_t),
     Fn(Box<dyn FnOnce() + Send + 'static>),
 }
 
@@ -69,10 +77,34 @@ impl Debug for BlockingThreadOp {
         match self {
             BlockingThreadOp::Rename(from, to) => write!(f, "rename `{from:?}` -> `{to:?}`"),
             BlockingThreadOp::Remove(path) => write!(f, "remove `{path:?}`"),
+            BlockingThreadOp::RemoveDir(path) => write!(f, "remove dir `{path:?}`"),
             BlockingThreadOp::CreateDir(path, flags) => {
                 write!(f, "create dir `{path:?}` (`{flags:b}`)")
             }
             BlockingThreadOp::Truncate(fd, to) => write!(f, "truncate `{fd}` -> `{to}`"),
+            BlockingThreadOp::ReadDir(fd, size) => write!(f, "read dir `{fd}` (`{size}` bytes)"),
+            BlockingThreadOp::RenameAt(old_dir, from, new_dir, to, flags) => {
+                write!(
+                    f,
+                    "rename `{old_dir}`/`{from:?}` -> `{new_dir}`/`{to:?}` (`{flags:b}`)"
+                )
+            }
+            BlockingThreadOp::LinkAt(old_dir, from, new_dir, to, flags) => {
+                write!(
+                    f,
+                    "link `{old_dir}`/`{from:?}` -> `{new_dir}`/`{to:?}` (`{flags:b}`)"
+                )
+            }
+            BlockingThreadOp::SymlinkAt(target, dir, link) => {
+                write!(f, "symlink `{target:?}` -> `{dir}`/`{link:?}`")
+            }
+            BlockingThreadOp::ReadLink(dir, path) => write!(f, "read link `{dir}`/`{path:?}`"),
+            BlockingThreadOp::UnlinkAt(dir, path, flags) => {
+                write!(f, "unlink `{dir}`/`{path:?}` (`{flags:b}`)")
+            }
+            BlockingThreadOp::MkdirAt(dir, path, mode) => {
+                write!(f, "create dir `{dir}`/`{path:?}` (`{mode:o}`)")
+            }
             BlockingThreadOp::Fn(_) => write!(f, "user function"),
         }
     }
@@ -94,9 +126,60 @@ impl BlockingThreadOp {
                 let p = c_str!(&path);
                 raw_syscall!(unlink(p.as_ptr()))
             }
+            BlockingThreadOp::RemoveDir(path) => {
+                let p = c_str!(&path);
+                raw_syscall!(rmdir(p.as_ptr()))
+            }
             BlockingThreadOp::Truncate(fd, sz) => {
                 raw_syscall!(ftruncate(fd, sz))
             }
+            BlockingThreadOp::ReadDir(fd, size) => {
+                let mut buf = vec![0u8; size];
+                match raw_syscall!(syscall(libc::SYS_getdents64, fd, buf.as_mut_ptr(), size)) {
+                    BlockingThreadResult::Syscall(res) => {
+                        buf.truncate(res.max(0) as usize);
+                        BlockingThreadResult::Buffer(res, buf)
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            BlockingThreadOp::RenameAt(old_dir, from, new_dir, to, flags) => {
+                raw_syscall!(syscall(
+                    libc::SYS_renameat2,
+                    old_dir,
+                    from.as_ptr(),
+                    new_dir,
+                    to.as_ptr(),
+                    flags
+                ))
+            }
+            BlockingThreadOp::LinkAt(old_dir, from, new_dir, to, flags) => {
+                raw_syscall!(linkat(old_dir, from.as_ptr(), new_dir, to.as_ptr(), flags))
+            }
+            BlockingThreadOp::SymlinkAt(target, dir, link) => {
+                raw_syscall!(symlinkat(target.as_ptr(), dir, link.as_ptr()))
+            }
+            BlockingThreadOp::UnlinkAt(dir, path, flags) => {
+                raw_syscall!(unlinkat(dir, path.as_ptr(), flags))
+            }
+            BlockingThreadOp::MkdirAt(dir, path, mode) => {
+                raw_syscall!(mkdirat(dir, path.as_ptr(), mode))
+            }
+            BlockingThreadOp::ReadLink(dir, path) => {
+                let mut buf = vec![0u8; libc::PATH_MAX as usize];
+                match raw_syscall!(readlinkat(
+                    dir,
+                    path.as_ptr(),
+                    buf.as_mut_ptr() as _,
+                    buf.len()
+                )) {
+                    BlockingThreadResult::Syscall(res) => {
+                        buf.truncate(res.max(0) as usize);
+                        BlockingThreadResult::Buffer(res, buf)
+                    }
+                    _ => unreachable!(),
+                }
+            }
             BlockingThreadOp::Fn(f) => {
                 f();
                 BlockingThreadResult::Fn
@@ -108,6 +191,7 @@ impl BlockingThreadOp {
 #[derive(Debug)]
 pub(super) enum BlockingThreadResult {
     Syscall(i64),
+    Buffer(i64, Vec<u8>),
     Fn,
 }
 
@@ -117,6 +201,7 @@ impl TryFrom<BlockingThreadResult> for std::io::Result<usize> {
     fn try_from(value: BlockingThreadResult) -> Result<Self, Self::Error> {
         match value {
             BlockingThreadResult::Syscall(x) => Ok(to_result(x)),
+            BlockingThreadResult::Buffer(x, _) => Ok(to_result(x)),
             BlockingThreadResult::Fn => Ok(Ok(0)),
         }
     }
@@ -234,10 +319,20 @@ impl BlockingThreadPool {
         let mut waiters = self.sources.borrow_mut();
         for x in self.rx.try_iter() {
             let id = x.id;
-            let res = x.res;
+            let mut res = x.res;
 
             let src = waiters.remove(&id).unwrap();
             let mut inner_source = src.borrow_mut();
+            // Operations that produce data hand their buffer back through the
+            // source, so the consumer can extract it once the result is ready
+            if let BlockingThreadResult::Buffer(_, buf) = &mut res {
+                match &mut inner_source.source_type {
+                    SourceType::ReadDir(dst) | SourceType::ReadLink(dst) => {
+                        *dst = std::mem::take(buf)
+                    }
+                    src => unreachable!("{src:?} doesn't take a buffer"),
+                }
+            }
             inner_source.wakers.result.replace(
                 res.try_into()
                     .expect("not a valid blocking operation's result"),
@@ -10,7 +10,7 @@
 
 use std::ptr;
 
-use crate::sys::uring::UringBuffer;
+use crate::sys::uring::{ProvidedBuffer, UringBuffer};
 use alloc::alloc::Layout;
 
 #[derive(Debug)]
@@ -52,6 +52,7 @@ impl Drop for SysAlloc {
 pub(crate) enum BufferStorage {
     Sys(SysAlloc),
     Uring(UringBuffer),
+    Provided(ProvidedBuffer),
     EventFd(*mut u8),
 }
 
@@ -60,6 +61,7 @@ impl BufferStorage {
         match self {
             BufferStorage::Sys(x) => x.as_ptr(),
             BufferStorage::Uring(x) => x.as_ptr(),
+            BufferStorage::Provided(x) => x.as_ptr(),
             BufferStorage::EventFd(x) => *x as *const u8,
         }
     }
@@ -68,6 +70,7 @@ impl BufferStorage {
         match self {
             BufferStorage::Sys(x) => x.as_mut_ptr(),
             BufferStorage::Uring(x) => x.as_mut_ptr(),
+            BufferStorage::Provided(x) => x.as_mut_ptr(),
             BufferStorage::EventFd(x) => *x,
         }
     }
@@ -118,6 +121,12 @@ impl DmaBuffer {
         self.size = newsize;
     }
 
+    pub(crate) fn trim_front(&mut self, trim: usize) {
+        assert!(trim <= self.size);
+        self.trim += trim;
+        self.size -= trim;
+    }
+
     /// Returns a representation of the current addressable contents of this
     /// `DmaBuffer` as a byte slice
     pub fn as_bytes(&self) -> &[u8] {
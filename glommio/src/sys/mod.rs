@@ -122,6 +122,32 @@ pub(crate) unsafe fn ssptr_to_sockaddr(
     .map_err(|e| to_io_error!(e))
 }
 
+/// Splits a buffer filled by a multishot `recvmsg` into its payload and the
+/// address of the sender. The request must have been issued with room for a
+/// `sockaddr_storage` and no control data.
+pub(crate) fn parse_recvmsg_out(
+    mut buf: DmaBuffer,
+) -> io::Result<(DmaBuffer, nix::sys::socket::SockAddr)> {
+    let header = std::mem::size_of::<uring_sys::io_uring_recvmsg_out>();
+    let name = std::mem::size_of::<nix::sys::socket::sockaddr_storage>();
+    if buf.len() < header + name {
+        return Err(io::Error::from_raw_os_error(libc::EMSGSIZE));
+    }
+    let out =
+        unsafe { std::ptr::read_unaligned(buf.as_ptr() as *const uring_sys::io_uring_recvmsg_out) };
+    let mut storage = MaybeUninit::<nix::sys::socket::sockaddr_storage>::uninit();
+    let addr = unsafe {
+        std::ptr::copy_nonoverlapping(
+            buf.as_ptr().add(header),
+            storage.as_mut_ptr() as *mut u8,
+            name,
+        );
+        ssptr_to_sockaddr(storage, (out.namelen as usize).min(name))?
+    };
+    buf.trim_front(header + name);
+    Ok((buf, addr))
+}
+
 pub(crate) fn recvmsg_syscall(
     fd: RawFd,
     buf: *mut u8,
@@ -388,6 +414,33 @@ impl Deref for DmaSource {
     }
 }
 
+/// Memory lent to the kernel by a zero-copy send. The kernel may keep reading
+/// from it after the send completed, so it is only released once the kernel
+/// notified it is done with it.
+pub(crate) struct ZeroCopyBuffer(Box<dyn AsRef<[u8]>>);
+
+impl ZeroCopyBuffer {
+    pub(crate) fn new<B: AsRef<[u8]> + 'static>(buf: B) -> ZeroCopyBuffer {
+        ZeroCopyBuffer(Box::new(buf))
+    }
+}
+
+impl Deref for ZeroCopyBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        (*self.0).as_ref()
+    }
+}
+
+impl fmt::Debug for ZeroCopyBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ZeroCopyBuffer")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
 #[derive(Debug)]
 pub(crate) enum IoBuffer {
     DmaSink(DmaBuffer),
@@ -6,14 +6,15 @@
 use crate::{
     iou::sqe::{SockAddr, SockAddrStorage},
     sys::{
-        DmaBuffer, IoBuffer, OsResult, PollableStatus, ReactorQueue, SourceId, Statx, TimeSpec64,
-        Wakers,
+        DmaBuffer, IoBuffer, OsResult, PollableStatus, ProvidedBufferGroup, ReactorQueue, SourceId,
+        Statx, TimeSpec64, Wakers, ZeroCopyBuffer,
     },
     GlommioError, IoRequirements, ReactorErrorKind, RingIoStats, TaskQueueHandle,
 };
 use futures_lite::{future, io};
 use std::{
     cell::{Ref, RefCell, RefMut},
+    collections::VecDeque,
     convert::TryFrom,
     ffi::CString,
     fmt,
@@ -22,7 +23,7 @@ use std::{
     path::PathBuf,
     pin::Pin,
     rc::Rc,
-    task::{Poll, Waker},
+    task::{Context, Poll, Waker},
     time::Duration,
 };
 
@@ -30,9 +31,14 @@ use std::{
 pub(crate) enum SourceType {
     Write(PollableStatus, IoBuffer),
     Read(PollableStatus, Option<IoBuffer>),
+    WriteVectored(PollableStatus, Vec<IoBuffer>, Vec<libc::iovec>),
+    ReadVectored(PollableStatus, Vec<IoBuffer>, Vec<libc::iovec>),
     PollAdd,
     SockSend(DmaBuffer),
+    SockSendZc(ZeroCopyBuffer),
     SockRecv(Option<DmaBuffer>),
+    SockRecvSelect(Rc<ProvidedBufferGroup>, Option<DmaBuffer>),
+    ProvideBuffers(Rc<ProvidedBufferGroup>, u32),
     SockRecvMsg(
         Option<DmaBuffer>,
         libc::iovec,
@@ -47,7 +53,10 @@ pub(crate) enum SourceType {
     ),
     Open(CString),
     FdataSync,
+    Fsync,
+    SyncFileRange,
     Fallocate,
+    Advise,
     Truncate,
     Close,
     LinkRings,
@@ -59,6 +68,18 @@ pub(crate) enum SourceType {
     Rename(PathBuf, PathBuf),
     CreateDir(PathBuf),
     Remove(PathBuf),
+    ReadDir(Vec<u8>),
+    RenameAt(CString, CString),
+    LinkAt(CString, CString),
+    SymlinkAt(CString, CString),
+    ReadLink(Vec<u8>),
+    UnlinkAt(CString),
+    MkdirAt(CString),
+    AcceptMulti,
+    RecvMulti(Rc<ProvidedBufferGroup>),
+    RecvMsgMulti(Rc<ProvidedBufferGroup>, libc::msghdr),
+    Splice,
+    Tee,
     BlockingFn,
     Invalid,
     #[cfg(feature = "bench")]
@@ -110,6 +131,19 @@ pub(crate) struct StatsCollection {
     pub(crate) latency: Option<LatencyCollectionFn>,
 }
 
+/// Releases whatever an event of a multishot request holds, like an accepted
+/// file descriptor, once it is known that nobody will consume it.
+pub(crate) type DiscardFn = Box<dyn Fn(io::Result<usize>, u32)>;
+
+/// The completions of a request that keeps posting them until it is
+/// canceled or the kernel terminates it.
+pub(crate) struct Multishot {
+    /// Results of the completions not yet consumed, with their raw flags.
+    pub(crate) events: VecDeque<(io::Result<usize>, u32)>,
+
+    pub(crate) discard: DiscardFn,
+}
+
 /// A registered source of I/O events.
 pub(crate) struct InnerSource {
     /// Raw file descriptor on Unix platforms.
@@ -129,6 +163,14 @@ pub(crate) struct InnerSource {
     pub(crate) stats_collection: Option<StatsCollection>,
 
     pub(crate) task_queue: Option<TaskQueueHandle>,
+
+    /// Set for multishot requests. Their completions are queued here, and
+    /// `wakers.result` is only set once the request terminates.
+    pub(crate) multishot: Option<Multishot>,
+
+    /// Slot of `raw` in the fixed file table of the rings, if it was
+    /// registered there.
+    pub(crate) fixed_file: Option<u32>,
 }
 
 impl InnerSource {
@@ -172,10 +214,27 @@ impl Source {
                 timeout: None,
                 stats_collection,
                 task_queue,
+                multishot: None,
+                fixed_file: None,
             })),
         }
     }
 
+    /// Turns this source into a multishot source. Must be called before the
+    /// request is queued.
+    pub(crate) fn set_multishot(&self, discard: DiscardFn) {
+        self.inner.borrow_mut().multishot = Some(Multishot {
+            events: VecDeque::new(),
+            discard,
+        });
+    }
+
+    /// Makes the request refer to its file through the given slot of the
+    /// fixed file table. Must be called before the request is queued.
+    pub(crate) fn set_fixed_file(&self, slot: u32) {
+        self.inner.borrow_mut().fixed_file = Some(slot);
+    }
+
     pub(crate) fn set_timeout(&self, d: Duration) -> Option<Duration> {
         let mut inner = self.inner.borrow_mut();
         let t = &mut inner.timeout;
@@ -213,6 +272,15 @@ impl Source {
         }
     }
 
+    pub(crate) fn extract_buffers(self) -> Vec<IoBuffer> {
+        let stype = self.extract_source_type();
+        match stype {
+            SourceType::ReadVectored(_, buffers, _) => buffers,
+            SourceType::WriteVectored(_, buffers, _) => buffers,
+            x => panic!("Could not extract buffers. Source: {:?}", x),
+        }
+    }
+
     pub(crate) fn buffer(&self) -> Ref<'_, IoBuffer> {
         Ref::map(self.source_type(), |stype| match stype {
             SourceType::Read(_, Some(buffer)) => buffer,
@@ -284,6 +352,15 @@ impl Source {
         self.inner.borrow_mut().wakers.waiters.push(waker)
     }
 
+    // adds a waiter to the list, unless it will already be woken. Useful for
+    // sources that are polled again and again by the same futures
+    pub(crate) fn add_waiter_unique(&self, waker: &Waker) {
+        let waiters = &mut self.inner.borrow_mut().wakers.waiters;
+        if !waiters.iter().any(|w| w.will_wake(waker)) {
+            waiters.push(waker.clone());
+        }
+    }
+
     pub(super) fn is_installed(&self) -> Option<bool> {
         match &self.inner.borrow().source_type {
             SourceType::ForeignNotifier(_, installed) => Some(*installed),
@@ -299,6 +376,31 @@ impl Source {
         self.inner.borrow().stats_collection
     }
 
+    /// Returns the next completion of a multishot source, with its raw flags,
+    /// or `None` once the request terminated and all of its completions
+    /// were consumed.
+    pub(crate) fn poll_multishot(
+        &self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<(io::Result<usize>, u32)>> {
+        let mut inner = self.inner.borrow_mut();
+        let event = inner
+            .multishot
+            .as_mut()
+            .expect("not a multishot source")
+            .events
+            .pop_front();
+        match event {
+            Some(event) => Poll::Ready(Some(event)),
+            None if inner.wakers.result.is_some() => Poll::Ready(None),
+            None => {
+                drop(inner);
+                self.add_waiter_single(cx.waker());
+                Poll::Pending
+            }
+        }
+    }
+
     pub(crate) async fn collect_rw(&self) -> io::Result<usize> {
         future::poll_fn(|cx| {
             if let Some(result) = self.result() {
@@ -315,6 +417,11 @@ impl Source {
 impl Drop for Source {
     fn drop(&mut self) {
         let mut inner = self.inner.borrow_mut();
+        if let Some(multishot) = inner.multishot.as_mut() {
+            for (res, flags) in multishot.events.drain(..) {
+                (multishot.discard)(res, flags);
+            }
+        }
         let enqueued = inner.enqueued.as_mut();
         if let Some(EnqueuedSource { id, queue, status }) = enqueued {
             match status {
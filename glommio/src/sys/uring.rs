@@ -6,15 +6,16 @@
 use alloc::alloc::Layout;
 use log::warn;
 use nix::{
-    fcntl::{FallocateFlags, OFlag},
+    fcntl::{FallocateFlags, OFlag, PosixFadviseAdvice},
     poll::PollFlags,
 };
 use rlimit::Resource;
 use std::{
+    any::Any,
     cell::{Cell, Ref, RefCell, RefMut},
     collections::VecDeque,
     convert::TryFrom,
-    ffi::CStr,
+    ffi::{CStr, CString},
     fmt,
     future::Future,
     io,
@@ -23,7 +24,7 @@ use std::{
     panic,
     pin::Pin,
     ptr,
-    rc::Rc,
+    rc::{Rc, Weak},
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -31,7 +32,11 @@ use std::{
 use crate::{
     free_list::{FreeList, Idx},
     iou,
-    iou::sqe::{FsyncFlags, SockAddrStorage, StatxFlags, StatxMode, SubmissionFlags, TimeoutFlags},
+    iou::cqe::CompletionFlags,
+    iou::sqe::{
+        BufferGroupId, FsyncFlags, SockAddrStorage, SpliceFlags, StatxFlags, StatxMode,
+        SubmissionFlags, TimeoutFlags,
+    },
     sys::{
         self,
         blocking::{BlockingThreadOp, BlockingThreadPool},
@@ -40,12 +45,13 @@ use crate::{
         PollableStatus, Source, SourceType, Statx, TimeSpec64,
     },
     uring_sys::{self, IoRingOp},
-    GlommioError, IoRequirements, IoStats, PoolPlacement, ReactorErrorKind, RingIoStats,
-    TaskQueueHandle,
+    GlommioError, HugePageSize, IoRequirements, IoStats, PoolPlacement, ReactorErrorKind,
+    RingIoStats, TaskQueueHandle,
 };
 use ahash::AHashMap;
 use buddy_alloc::buddy_alloc::{BuddyAlloc, BuddyAllocParam};
 use nix::sys::{
+    mman::MmapAdvise,
     socket::{MsgFlags, SockAddr, SockFlag},
     stat::Mode as OpenMode,
 };
@@ -63,23 +69,73 @@ enum UringOpDescriptor {
     WriteFixed(*const u8, usize, u64, u32),
     ReadFixed(u64, usize),
     Read(u64, usize),
+    WriteVectored(*const libc::iovec, usize, u64),
+    ReadVectored(*mut libc::iovec, usize, u64),
     Open(*const u8, libc::c_int, u32),
     Close,
     FDataSync,
+    FSync,
+    SyncFileRange(u64, u32, u32),
     Connect(*const SockAddr),
     LinkTimeout(*const uring_sys::__kernel_timespec),
     Accept(*mut SockAddrStorage),
+    AcceptMulti,
+    RecvMulti(i32, u16),
+    RecvMsgMulti(i32, u16),
     Fallocate(u64, u64, libc::c_int),
+    Fadvise(u64, u64, PosixFadviseAdvice),
+    Madvise(*mut u8, usize, MmapAdvise),
     Statx(*const u8, *mut Statx),
+    RenameAt(*const u8, RawFd, *const u8, u32),
+    LinkAt(*const u8, RawFd, *const u8, i32),
+    SymlinkAt(*const u8, *const u8),
+    UnlinkAt(*const u8, i32),
+    MkdirAt(*const u8, u32),
+    ProvideBuffers(*mut u8, u32, u32, u16, u16),
+    RemoveBuffers(u32, u16),
+    SockRecvSelect(usize, i32, u16),
+    Splice(RawFd, i64, i64, u32),
+    Tee(RawFd, u32),
     Timeout(*const uring_sys::__kernel_timespec, u32),
     TimeoutRemove(u64),
     SockSend(*const u8, usize, i32),
+    SockSendZc(*const u8, usize, i32),
     SockSendMsg(*mut libc::msghdr, i32),
     SockRecv(usize, i32),
     SockRecvMsg(usize, i32),
     Nop,
 }
 
+impl UringOpDescriptor {
+    /// Whether the request may refer to its file through the fixed file
+    /// table.
+    fn supports_fixed_file(&self) -> bool {
+        matches!(
+            self,
+            UringOpDescriptor::PollAdd(_)
+                | UringOpDescriptor::Write(..)
+                | UringOpDescriptor::WriteFixed(..)
+                | UringOpDescriptor::ReadFixed(..)
+                | UringOpDescriptor::Read(..)
+                | UringOpDescriptor::WriteVectored(..)
+                | UringOpDescriptor::ReadVectored(..)
+                | UringOpDescriptor::FDataSync
+                | UringOpDescriptor::FSync
+                | UringOpDescriptor::SyncFileRange(..)
+                | UringOpDescriptor::Fallocate(..)
+                | UringOpDescriptor::Fadvise(..)
+                | UringOpDescriptor::SockSend(..)
+                | UringOpDescriptor::SockSendZc(..)
+                | UringOpDescriptor::SockSendMsg(..)
+                | UringOpDescriptor::SockRecv(..)
+                | UringOpDescriptor::SockRecvMsg(..)
+                | UringOpDescriptor::SockRecvSelect(..)
+                | UringOpDescriptor::RecvMulti(..)
+                | UringOpDescriptor::RecvMsgMulti(..)
+        )
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct UringDescriptor {
     fd: RawFd,
@@ -88,42 +144,128 @@ pub(crate) struct UringDescriptor {
     args: UringOpDescriptor,
 }
 
+/// Where the memory of a [`UringBufferAllocator`] comes from.
+enum ArenaMemory {
+    Heap(Layout),
+    // Anonymous mapping of the given length
+    Mapped(usize),
+    // Memory provided by the user, which is released when its owner is
+    // dropped
+    User { _owner: Box<dyn Any> },
+}
+
 pub(crate) struct UringBufferAllocator {
     data: ptr::NonNull<u8>,
     size: usize,
     allocator: RefCell<BuddyAlloc>,
-    layout: Layout,
+    memory: ArenaMemory,
     uring_buffer_id: Cell<Option<u32>>,
+    // Where the entry of the arena in the buffer tables goes once it is
+    // dropped, if it can be emptied
+    released_slots: RefCell<Weak<RefCell<Vec<u32>>>>,
+    page_size: usize,
+    in_use: Cell<usize>,
+    // Allocations that didn't fit in the arena and were served from the heap
+    fallbacks: Cell<(u64, u64)>,
 }
 
 impl fmt::Debug for UringBufferAllocator {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("UringBufferAllocator")
             .field("data", &self.data)
+            .field("size", &self.size)
+            .field("in_use", &self.in_use)
             .finish()
     }
 }
 
 impl UringBufferAllocator {
-    fn new(size: usize) -> Self {
+    pub(crate) fn new(size: usize) -> Self {
         let layout = Layout::from_size_align(size, 4096).unwrap();
-        let (data, allocator) = unsafe {
-            let data = alloc::alloc::alloc(layout) as *mut u8;
-            let data = std::ptr::NonNull::new(data).unwrap();
-            let allocator = BuddyAlloc::new(BuddyAllocParam::new(
-                data.as_ptr(),
-                layout.size(),
-                layout.align(),
-            ));
-            (data, RefCell::new(allocator))
+        let data = unsafe { alloc::alloc::alloc(layout) as *mut u8 };
+        let data = std::ptr::NonNull::new(data).unwrap();
+        unsafe { Self::with_memory(data, layout.size(), ArenaMemory::Heap(layout)) }
+    }
+
+    /// Maps an arena of at least `size` bytes backed by hugepages.
+    ///
+    /// Hugepages reserved in the hugetlb pool are used if there are enough of
+    /// them. Otherwise, 2 MiB arenas fall back to transparent hugepages, if
+    /// they are enabled. The kernel backs those with hugepages only when it
+    /// can, so the arena reports the base page size then.
+    pub(crate) fn with_hugepages(size: usize, page_size: HugePageSize) -> io::Result<Self> {
+        let page = page_size.bytes();
+        let size = align_up(std::cmp::max(size, page), page);
+        let huge_flag = match page_size {
+            HugePageSize::Size2M => libc::MAP_HUGE_2MB,
+            HugePageSize::Size1G => libc::MAP_HUGE_1GB,
+        };
+        let (data, page) = match mmap_anonymous(size, libc::MAP_HUGETLB | huge_flag) {
+            Ok(data) => (data, page),
+            Err(err) => match page_size {
+                HugePageSize::Size2M if transparent_hugepages_enabled() => {
+                    // Over-allocate to align the arena to a hugepage boundary,
+                    // since only aligned ranges can be backed by one
+                    let data = mmap_anonymous(size + page, 0)?;
+                    let aligned = align_up(data.as_ptr() as usize, page);
+                    let head = aligned - data.as_ptr() as usize;
+                    unsafe {
+                        if head > 0 {
+                            libc::munmap(data.as_ptr() as *mut _, head);
+                        }
+                        if page - head > 0 {
+                            libc::munmap((aligned + size) as *mut _, page - head);
+                        }
+                        if libc::madvise(aligned as *mut _, size, libc::MADV_HUGEPAGE) != 0 {
+                            libc::munmap(aligned as *mut _, size);
+                            return Err(err);
+                        }
+                    }
+                    (ptr::NonNull::new(aligned as *mut u8).unwrap(), 4096)
+                }
+                _ => return Err(err),
+            },
         };
+        let mut arena = unsafe { Self::with_memory(data, size, ArenaMemory::Mapped(size)) };
+        arena.page_size = page;
+        Ok(arena)
+    }
+
+    /// Carves buffers out of `size` bytes at `data`, which are kept alive by
+    /// `owner`.
+    ///
+    /// # Safety
+    ///
+    /// The memory must be valid for reads and writes for as long as `owner`
+    /// is alive, and must not be accessed through anything else.
+    pub(crate) unsafe fn from_memory(
+        data: ptr::NonNull<u8>,
+        size: usize,
+        owner: Box<dyn Any>,
+    ) -> io::Result<Self> {
+        // The allocator hands out buffers aligned to its smallest block
+        if data.as_ptr() as usize % 4096 != 0 || size % 4096 != 0 || size == 0 {
+            return Err(io::Error::from_raw_os_error(libc::EINVAL));
+        }
+        Ok(Self::with_memory(
+            data,
+            size,
+            ArenaMemory::User { _owner: owner },
+        ))
+    }
 
+    unsafe fn with_memory(data: ptr::NonNull<u8>, size: usize, memory: ArenaMemory) -> Self {
+        let allocator = BuddyAlloc::new(BuddyAllocParam::new(data.as_ptr(), size, 4096));
         UringBufferAllocator {
             data,
             size,
-            allocator,
-            layout,
+            allocator: RefCell::new(allocator),
+            memory,
             uring_buffer_id: Cell::new(None),
+            released_slots: RefCell::new(Weak::new()),
+            page_size: 4096,
+            in_use: Cell::new(0),
+            fallbacks: Cell::new((0, 0)),
         }
     }
 
@@ -131,42 +273,119 @@ impl UringBufferAllocator {
         self.uring_buffer_id.set(Some(idx))
     }
 
-    fn free(&self, ptr: ptr::NonNull<u8>) {
+    /// Hands the entry of the arena over to `released` once it is dropped.
+    fn release_to(&self, released: &Rc<RefCell<Vec<u32>>>) {
+        *self.released_slots.borrow_mut() = Rc::downgrade(released);
+    }
+
+    pub(crate) fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The size of the pages backing the arena.
+    pub(crate) fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// How many bytes are currently handed out in buffers.
+    pub(crate) fn in_use(&self) -> usize {
+        self.in_use.get()
+    }
+
+    fn free(&self, ptr: ptr::NonNull<u8>, size: usize) {
         let mut allocator = self.allocator.borrow_mut();
         allocator.free(ptr.as_ptr() as *mut u8);
+        self.in_use.set(self.in_use.get() - size);
     }
 
     fn as_bytes(&self) -> &[u8] {
         unsafe { std::slice::from_raw_parts(self.data.as_ptr(), self.size) }
     }
 
+    /// Returns the number and size of the allocations that fell back to the
+    /// heap since the last call.
+    fn take_fallbacks(&self) -> (u64, u64) {
+        self.fallbacks.take()
+    }
+
     fn new_buffer(self: &Rc<Self>, size: usize) -> Option<DmaBuffer> {
-        let mut alloc = self.allocator.borrow_mut();
-        match ptr::NonNull::new(alloc.malloc(size)) {
-            Some(data) => {
-                let ub = UringBuffer {
-                    allocator: self.clone(),
-                    data,
-                    uring_buffer_id: self.uring_buffer_id.get(),
-                };
-                Some(DmaBuffer::with_storage(size, BufferStorage::Uring(ub)))
+        match self.try_new_buffer(size) {
+            Some(buffer) => Some(buffer),
+            None => {
+                let (count, bytes) = self.fallbacks.get();
+                self.fallbacks.set((count + 1, bytes + size as u64));
+                DmaBuffer::new(size)
             }
-            None => DmaBuffer::new(size),
         }
     }
+
+    /// Allocates a buffer from the arena, or returns `None` if it's
+    /// exhausted.
+    pub(crate) fn try_new_buffer(self: &Rc<Self>, size: usize) -> Option<DmaBuffer> {
+        let mut alloc = self.allocator.borrow_mut();
+        let data = ptr::NonNull::new(alloc.malloc(size))?;
+        self.in_use.set(self.in_use.get() + size);
+        let ub = UringBuffer {
+            allocator: self.clone(),
+            data,
+            size,
+            uring_buffer_id: self.uring_buffer_id.get(),
+        };
+        Some(DmaBuffer::with_storage(size, BufferStorage::Uring(ub)))
+    }
 }
 
 impl Drop for UringBufferAllocator {
     fn drop(&mut self) {
-        unsafe {
-            alloc::alloc::dealloc(self.data.as_ptr(), self.layout);
+        // The rings may be in use, so the entry is emptied by the reactor
+        // later on. The kernel holds on to the pages until then.
+        if let (Some(idx), Some(released)) = (
+            self.uring_buffer_id.get(),
+            self.released_slots.get_mut().upgrade(),
+        ) {
+            released.borrow_mut().push(idx);
+        }
+        match self.memory {
+            ArenaMemory::Heap(layout) => unsafe {
+                alloc::alloc::dealloc(self.data.as_ptr(), layout);
+            },
+            ArenaMemory::Mapped(len) => unsafe {
+                libc::munmap(self.data.as_ptr() as *mut _, len);
+            },
+            ArenaMemory::User { .. } => {}
         }
     }
 }
 
+fn mmap_anonymous(len: usize, flags: libc::c_int) -> io::Result<ptr::NonNull<u8>> {
+    let data = unsafe {
+        libc::mmap(
+            ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | flags,
+            -1,
+            0,
+        )
+    };
+    if data == libc::MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(ptr::NonNull::new(data as *mut u8).unwrap())
+}
+
+fn transparent_hugepages_enabled() -> bool {
+    // The active mode is the one in brackets, like "always [madvise] never"
+    match std::fs::read_to_string("/sys/kernel/mm/transparent_hugepage/enabled") {
+        Ok(modes) => modes.contains("[always]") || modes.contains("[madvise]"),
+        Err(_) => false,
+    }
+}
+
 pub(crate) struct UringBuffer {
     allocator: Rc<UringBufferAllocator>,
     data: ptr::NonNull<u8>,
+    size: usize,
     uring_buffer_id: Option<u32>,
 }
 
@@ -196,7 +415,203 @@ impl UringBuffer {
 impl Drop for UringBuffer {
     fn drop(&mut self) {
         let ptr = self.data;
-        self.allocator.free(ptr);
+        self.allocator.free(ptr, self.size);
+    }
+}
+
+/// Requests that provide buffers to the kernel and haven't been reaped yet.
+///
+/// They are owned by the reactor rather than by their group: dropping their
+/// sources with the group would cancel those that were not submitted yet, and
+/// the group must stay alive until the kernel is done with them anyway.
+struct ProvideRequests {
+    source_map: Rc<RefCell<SourceMap>>,
+    in_flight: RefCell<Vec<Source>>,
+}
+
+impl ProvideRequests {
+    fn new(source_map: Rc<RefCell<SourceMap>>) -> Rc<Self> {
+        Rc::new(Self {
+            source_map,
+            in_flight: RefCell::new(Vec::new()),
+        })
+    }
+
+    /// Drops the requests that completed. Their results were already
+    /// accounted for by their group when the completion was processed.
+    fn reap(&self) {
+        self.in_flight
+            .borrow_mut()
+            .retain(|source| source.result().is_none());
+    }
+}
+
+/// Buffers handed over to the kernel, which picks one of them whenever a
+/// receive that selects its buffer from this group completes.
+///
+/// Buffers picked by the kernel come back as [`ProvidedBuffer`]s, and are
+/// provided again when those are dropped.
+pub(crate) struct ProvidedBufferGroup {
+    // Submission queue of the ring the buffers were provided to. Receives
+    // selecting from this group must go to the same ring.
+    queue: ReactorQueue,
+    // Weak, since the requests in flight hold on to their group
+    requests: Weak<ProvideRequests>,
+    // Ids of the groups that were dropped, shared by all the groups of a ring
+    free_ids: Rc<RefCell<Vec<(u16, u32)>>>,
+    id: u16,
+    data: ptr::NonNull<u8>,
+    layout: Layout,
+    buffer_size: usize,
+    count: u32,
+    // Buffers the kernel can't pick from: those handed out as
+    // `ProvidedBuffer`s and those that failed to be provided again
+    in_use: Cell<usize>,
+}
+
+impl fmt::Debug for ProvidedBufferGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProvidedBufferGroup")
+            .field("id", &self.id)
+            .field("buffer_size", &self.buffer_size)
+            .field("count", &self.count)
+            .field("in_use", &self.in_use)
+            .finish()
+    }
+}
+
+impl ProvidedBufferGroup {
+    pub(crate) fn id(&self) -> u16 {
+        self.id
+    }
+
+    pub(crate) fn buffer_size(&self) -> usize {
+        self.buffer_size
+    }
+
+    pub(crate) fn count(&self) -> usize {
+        self.count as usize
+    }
+
+    pub(crate) fn in_use(&self) -> usize {
+        self.in_use.get()
+    }
+
+    fn provide(self: &Rc<Self>, bid: u16, count: u32) {
+        // The reactor is gone, and so are the rings
+        let requests = match self.requests.upgrade() {
+            Some(requests) => requests,
+            None => return,
+        };
+        let source = Source::new(
+            IoRequirements::default(),
+            -1,
+            SourceType::ProvideBuffers(self.clone(), count),
+            None,
+            None,
+        );
+        let addr = unsafe { self.data.as_ptr().add(bid as usize * self.buffer_size) };
+        let id = requests
+            .source_map
+            .borrow_mut()
+            .add_source(&source, self.queue.clone());
+        self.queue
+            .borrow_mut()
+            .submissions
+            .push_back(UringDescriptor {
+                args: UringOpDescriptor::ProvideBuffers(
+                    addr,
+                    self.buffer_size as u32,
+                    count,
+                    self.id,
+                    bid,
+                ),
+                fd: -1,
+                flags: SubmissionFlags::empty(),
+                user_data: to_user_data(id),
+            });
+        requests.in_flight.borrow_mut().push(source);
+    }
+
+    /// Accounts for the completion of a request that provided `count`
+    /// buffers. Buffers that failed to be provided are in use for good, as
+    /// the kernel will never pick them.
+    pub(crate) fn provided(&self, count: u32, res: &io::Result<usize>) {
+        if let Err(err) = res {
+            warn!(
+                "failed to provide {} buffers to group {}: {}",
+                count, self.id, err
+            );
+            self.in_use.set(self.in_use.get() + count as usize);
+        }
+    }
+
+    /// Wraps the buffer the kernel picked for a completion with the given
+    /// raw flags, if it picked one.
+    pub(crate) fn take(self: &Rc<Self>, cqe_flags: u32, len: usize) -> Option<DmaBuffer> {
+        if cqe_flags & uring_sys::IORING_CQE_F_BUFFER == 0 {
+            return None;
+        }
+        let bid = (cqe_flags >> uring_sys::IORING_CQE_BUFFER_SHIFT) as u16;
+        self.in_use.set(self.in_use.get() + 1);
+        let buffer = ProvidedBuffer {
+            group: self.clone(),
+            bid,
+        };
+        Some(DmaBuffer::with_storage(
+            len,
+            BufferStorage::Provided(buffer),
+        ))
+    }
+}
+
+impl Drop for ProvidedBufferGroup {
+    fn drop(&mut self) {
+        // No request can select from this group anymore, and the requests
+        // providing its buffers all completed, since they hold on to it. So
+        // the kernel will not touch the memory. The buffers are only removed
+        // from the kernel when the id is reused.
+        self.free_ids.borrow_mut().push((self.id, self.count));
+        unsafe {
+            alloc::alloc::dealloc(self.data.as_ptr(), self.layout);
+        }
+    }
+}
+
+/// A buffer of a [`ProvidedBufferGroup`] picked by the kernel.
+pub(crate) struct ProvidedBuffer {
+    group: Rc<ProvidedBufferGroup>,
+    bid: u16,
+}
+
+impl fmt::Debug for ProvidedBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProvidedBuffer")
+            .field("group", &self.group.id)
+            .field("bid", &self.bid)
+            .finish()
+    }
+}
+
+impl ProvidedBuffer {
+    pub(crate) fn as_ptr(&self) -> *const u8 {
+        unsafe {
+            self.group
+                .data
+                .as_ptr()
+                .add(self.bid as usize * self.group.buffer_size)
+        }
+    }
+
+    pub(crate) fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.as_ptr() as *mut u8
+    }
+}
+
+impl Drop for ProvidedBuffer {
+    fn drop(&mut self) {
+        self.group.in_use.set(self.group.in_use.get() - 1);
+        self.group.provide(self.bid, 1);
     }
 }
 
@@ -257,8 +672,33 @@ static GLOMMIO_URING_OPS: &[IoRingOp] = &[
     IoRingOp::IORING_OP_RECV,
 ];
 
+/// Unlike [`check_supported_operations`], this is meant for operations that
+/// have a fallback when the kernel doesn't support them.
+fn is_operation_supported(op: IoRingOp) -> bool {
+    unsafe {
+        let probe = uring_sys::io_uring_get_probe();
+        if probe.is_null() {
+            return false;
+        }
+        let sup = uring_sys::io_uring_opcode_supported(probe, op as libc::c_int) > 0;
+        uring_sys::io_uring_free_probe(probe);
+        sup
+    }
+}
+
 lazy_static! {
     static ref IO_URING_RECENT_ENOUGH: bool = check_supported_operations(GLOMMIO_URING_OPS);
+    static ref URING_RENAMEAT: bool = is_operation_supported(IoRingOp::IORING_OP_RENAMEAT);
+    static ref URING_LINKAT: bool = is_operation_supported(IoRingOp::IORING_OP_LINKAT);
+    static ref URING_SYMLINKAT: bool = is_operation_supported(IoRingOp::IORING_OP_SYMLINKAT);
+    static ref URING_UNLINKAT: bool = is_operation_supported(IoRingOp::IORING_OP_UNLINKAT);
+    static ref URING_MKDIRAT: bool = is_operation_supported(IoRingOp::IORING_OP_MKDIRAT);
+    // Multishot accept is a flag, so it can't be probed. It came with the same
+    // kernel release (5.19) as IORING_OP_SOCKET, which can.
+    static ref URING_MULTISHOT_ACCEPT: bool = is_operation_supported(IoRingOp::IORING_OP_SOCKET);
+    // Same for multishot receives, which came with IORING_OP_SEND_ZC in 6.0.
+    static ref URING_MULTISHOT_RECV: bool = is_operation_supported(IoRingOp::IORING_OP_SEND_ZC);
+    static ref URING_SEND_ZC: bool = is_operation_supported(IoRingOp::IORING_OP_SEND_ZC);
 }
 
 fn fill_sqe<F>(
@@ -306,6 +746,14 @@ fn fill_sqe<F>(
                     }
                 });
             }
+            UringOpDescriptor::WriteVectored(iovs, len, pos) => {
+                let bufs = std::slice::from_raw_parts(iovs as *const io::IoSlice<'_>, len);
+                sqe.prep_write_vectored(op.fd, bufs, pos);
+            }
+            UringOpDescriptor::ReadVectored(iovs, len, pos) => {
+                let bufs = std::slice::from_raw_parts_mut(iovs as *mut io::IoSliceMut<'_>, len);
+                sqe.prep_read_vectored(op.fd, bufs, pos);
+            }
             UringOpDescriptor::Open(path, flags, mode) => {
                 let path = CStr::from_ptr(path as _);
                 sqe.prep_openat(
@@ -318,6 +766,12 @@ fn fill_sqe<F>(
             UringOpDescriptor::FDataSync => {
                 sqe.prep_fsync(op.fd, FsyncFlags::FSYNC_DATASYNC);
             }
+            UringOpDescriptor::FSync => {
+                sqe.prep_fsync(op.fd, FsyncFlags::empty());
+            }
+            UringOpDescriptor::SyncFileRange(offset, len, flags) => {
+                sqe.prep_sync_file_range(op.fd, offset, len, flags);
+            }
             UringOpDescriptor::Connect(addr) => {
                 sqe.prep_connect(op.fd, &*addr);
             }
@@ -330,16 +784,76 @@ fn fill_sqe<F>(
                 sqe.prep_accept(op.fd, Some(&mut *addr), SockFlag::SOCK_CLOEXEC);
             }
 
+            UringOpDescriptor::AcceptMulti => {
+                sqe.prep_multishot_accept(op.fd, SockFlag::SOCK_CLOEXEC);
+            }
+
             UringOpDescriptor::Fallocate(offset, size, flags) => {
                 let flags = FallocateFlags::from_bits_truncate(flags);
                 sqe.prep_fallocate(op.fd, offset, size, flags);
             }
+            UringOpDescriptor::Fadvise(offset, len, advice) => {
+                sqe.prep_fadvise(op.fd, offset, len, advice);
+            }
+            UringOpDescriptor::Madvise(addr, len, advice) => {
+                sqe.prep_madvise(addr, len, advice);
+            }
             UringOpDescriptor::Statx(path, statx_buf) => {
-                let flags = StatxFlags::AT_STATX_SYNC_AS_STAT | StatxFlags::AT_NO_AUTOMOUNT;
+                let mut flags = StatxFlags::AT_STATX_SYNC_AS_STAT | StatxFlags::AT_NO_AUTOMOUNT;
                 let mode = StatxMode::from_bits_truncate(0x7ff);
 
                 let path = CStr::from_ptr(path as _);
-                sqe.prep_statx(-1, path, flags, mode, &mut *statx_buf);
+                // An empty path stats the file descriptor itself, otherwise
+                // relative paths are resolved against it
+                if path.to_bytes().is_empty() {
+                    flags |= StatxFlags::AT_EMPTY_PATH;
+                }
+                sqe.prep_statx(op.fd, path, flags, mode, &mut *statx_buf);
+            }
+            UringOpDescriptor::RenameAt(old_path, new_dir, new_path, flags) => {
+                let old_path = CStr::from_ptr(old_path as _);
+                let new_path = CStr::from_ptr(new_path as _);
+                sqe.prep_renameat(op.fd, old_path, new_dir, new_path, flags);
+            }
+            UringOpDescriptor::LinkAt(old_path, new_dir, new_path, flags) => {
+                let old_path = CStr::from_ptr(old_path as _);
+                let new_path = CStr::from_ptr(new_path as _);
+                sqe.prep_linkat(op.fd, old_path, new_dir, new_path, flags);
+            }
+            UringOpDescriptor::SymlinkAt(target, link_path) => {
+                let target = CStr::from_ptr(target as _);
+                let link_path = CStr::from_ptr(link_path as _);
+                sqe.prep_symlinkat(target, op.fd, link_path);
+            }
+            UringOpDescriptor::UnlinkAt(path, flags) => {
+                let path = CStr::from_ptr(path as _);
+                sqe.prep_unlinkat(op.fd, path, flags);
+            }
+            UringOpDescriptor::MkdirAt(path, mode) => {
+                let path = CStr::from_ptr(path as _);
+                sqe.prep_mkdirat(op.fd, path, OpenMode::from_bits_truncate(mode));
+            }
+            UringOpDescriptor::ProvideBuffers(addr, size, count, group, bid) => {
+                let buffers = std::slice::from_raw_parts_mut(addr, (size * count) as usize);
+                let group = BufferGroupId { id: group as u32 };
+                sqe.prep_provide_buffers(buffers, count, group, bid as u32);
+            }
+            UringOpDescriptor::RemoveBuffers(count, group) => {
+                sqe.prep_remove_buffers(count, BufferGroupId { id: group as u32 });
+            }
+            UringOpDescriptor::SockRecvSelect(len, flags, group) => {
+                sqe.prep_recv_select(
+                    op.fd,
+                    len,
+                    BufferGroupId { id: group as u32 },
+                    MsgFlags::from_bits_unchecked(flags),
+                );
+            }
+            UringOpDescriptor::Splice(fd_in, off_in, off_out, len) => {
+                sqe.prep_splice(fd_in, off_in, op.fd, off_out, len, SpliceFlags::empty());
+            }
+            UringOpDescriptor::Tee(fd_in, len) => {
+                sqe.prep_tee(fd_in, op.fd, len, SpliceFlags::empty());
             }
             UringOpDescriptor::Timeout(timespec, events) => {
                 sqe.prep_timeout(&*timespec, events, TimeoutFlags::empty());
@@ -388,6 +902,11 @@ fn fill_sqe<F>(
                 );
             }
 
+            UringOpDescriptor::SockSendZc(ptr, len, flags) => {
+                let buf = std::slice::from_raw_parts(ptr, len);
+                sqe.prep_send_zc(op.fd, buf, MsgFlags::from_bits_unchecked(flags));
+            }
+
             UringOpDescriptor::SockSendMsg(hdr, flags) => {
                 sqe.prep_sendmsg(
                     op.fd,
@@ -441,6 +960,28 @@ fn fill_sqe<F>(
                     };
                 });
             }
+            UringOpDescriptor::RecvMulti(flags, group) => {
+                sqe.prep_multishot_recv_select(
+                    op.fd,
+                    BufferGroupId { id: group as u32 },
+                    MsgFlags::from_bits_unchecked(flags),
+                );
+            }
+            UringOpDescriptor::RecvMsgMulti(flags, group) => {
+                source_map.peek_source_mut(from_user_data(op.user_data), |mut src| {
+                    match &mut src.source_type {
+                        SourceType::RecvMsgMulti(_, hdr) => {
+                            sqe.prep_multishot_recvmsg_select(
+                                op.fd,
+                                hdr as *mut libc::msghdr,
+                                BufferGroupId { id: group as u32 },
+                                MsgFlags::from_bits_unchecked(flags),
+                            );
+                        }
+                        _ => unreachable!(),
+                    };
+                });
+            }
             UringOpDescriptor::Nop => sqe.prep_nop(),
         }
         sqe.set_user_data(user_data);
@@ -448,15 +989,16 @@ fn fill_sqe<F>(
     }
 }
 
-fn transmute_error(res: io::Result<u32>) -> io::Result<usize> {
+fn transmute_error(res: io::Result<u32>, has_timeout: bool) -> io::Result<usize> {
     res.map(|x| x as usize) // iou standardized on u32, which is good for low level but for higher layers usize is
         // better
         .map_err(|x| {
             // Convert CANCELED to TimedOut. This will be the case for linked `sqe`s with a
             // timeout, and if we wanted to be really strict we'd check. But if
-            // the operation is truly cancelled no one will check the result,
-            // and we have no other use case for cancel at the moment so keep it simple
-            if let Some(libc::ECANCELED) = x.raw_os_error() {
+            // the operation is truly cancelled no one will check the result.
+            // Sources without a timeout can be cancelled because an earlier
+            // request in their chain failed, so they keep the original error.
+            if has_timeout && x.raw_os_error() == Some(libc::ECANCELED) {
                 io::Error::from_raw_os_error(libc::ETIMEDOUT)
             } else {
                 x
@@ -549,7 +1091,7 @@ fn process_one_event<F, R>(
     try_process: F,
     post_process: R,
     source_map: Rc<RefCell<SourceMap>>,
-) -> Option<bool>
+) -> Option<(bool, bool)>
 where
     F: FnOnce(Ref<'_, InnerSource>) -> Option<()>,
     R: FnOnce(RefMut<'_, InnerSource>, io::Result<usize>) -> io::Result<usize>,
@@ -557,23 +1099,71 @@ where
     if let Some(value) = cqe {
         // No user data is `POLL_REMOVE` or `CANCEL`, we won't process.
         if value.user_data() == 0 {
-            return Some(false);
+            return Some((false, false));
         }
 
-        let src = source_map
-            .borrow_mut()
-            .consume_source(from_user_data(value.user_data()));
+        // Multishot requests stay in the map until their last completion
+        let more = value.flags().contains(CompletionFlags::MORE);
+        let (src, canceled) = {
+            let mut source_map = source_map.borrow_mut();
+            let id = from_user_data(value.user_data());
+            let canceled = source_map.peek_source_mut(
+                id,
+                |x| matches!(&x.enqueued, Some(e) if e.status == EnqueuedStatus::Canceled),
+            );
+            let src = if more {
+                source_map[id].clone()
+            } else {
+                source_map.consume_source(id)
+            };
+            (src, canceled)
+        };
+
+        // The kernel is done with the buffer of a zero-copy send, whose result
+        // was posted already. The buffer goes away with the source.
+        if value.flags().contains(CompletionFlags::NOTIF) {
+            return Some((false, more));
+        }
 
         let result = value.result();
 
         let mut woke = false;
         if try_process(src.borrow()).is_none() {
-            let res = Some(post_process(src.borrow_mut(), transmute_error(result)));
+            let has_timeout = src.borrow().timeout.is_some();
+            let res = post_process(src.borrow_mut(), transmute_error(result, has_timeout));
             let mut inner_source = src.borrow_mut();
-            inner_source.wakers.result = res;
+            match inner_source.multishot.as_mut() {
+                Some(multishot) => {
+                    if canceled {
+                        (multishot.discard)(res, value.raw_flags());
+                    } else {
+                        multishot.events.push_back((res, value.raw_flags()));
+                    }
+                    if !more {
+                        inner_source.wakers.result = Some(Ok(0));
+                    }
+                }
+                None => {
+                    match &mut inner_source.source_type {
+                        SourceType::SockRecvSelect(group, slot) => {
+                            if let Ok(len) = res {
+                                *slot = group.take(value.raw_flags(), len);
+                            }
+                        }
+                        SourceType::ProvideBuffers(group, count) => group.provided(*count, &res),
+                        _ => {}
+                    }
+                    if more {
+                        // Only the notification of a zero-copy send is left,
+                        // and there's nothing to cancel anymore.
+                        inner_source.enqueued.take();
+                    }
+                    inner_source.wakers.result = Some(res);
+                }
+            }
             woke = inner_source.wakers.wake_waiters();
         }
-        return Some(woke);
+        return Some((woke, more));
     }
     None
 }
@@ -859,9 +1449,11 @@ impl UringCommon for PollRing {
             },
             source_map,
         )
-        .map(|x| {
-            self.in_kernel -= 1;
-            x
+        .map(|(woke, more)| {
+            if !more {
+                self.in_kernel -= 1;
+            }
+            woke
         })
     }
 
@@ -1191,9 +1783,11 @@ impl UringCommon for SleepableRing {
             },
             source_map,
         )
-        .map(|x| {
-            self.in_kernel -= 1;
-            x
+        .map(|(woke, more)| {
+            if !more {
+                self.in_kernel -= 1;
+            }
+            woke
         })
     }
 
@@ -1250,6 +1844,28 @@ pub(crate) struct Reactor {
     blocking_thread: BlockingThreadPool,
 
     rings_depth: usize,
+
+    // Set while the requests of an `IoChain` are queued, so they all go to
+    // the same ring
+    chaining: Cell<bool>,
+
+    // Provided buffer groups, which all live in the latency ring
+    next_buffer_group: Cell<u16>,
+    free_buffer_groups: Rc<RefCell<Vec<(u16, u32)>>>,
+    provide_requests: Rc<ProvideRequests>,
+
+    fixed_files: RefCell<FixedFiles>,
+
+    // The default arena, used by all the rings
+    allocator: Rc<UringBufferAllocator>,
+    // Entries of the buffer tables of the rings that can be given to arenas:
+    // the first one that was never used, and those that were emptied
+    buffer_slots: RefCell<(u32, Vec<u32>)>,
+    // Entries of the arenas that were dropped, still to be emptied
+    released_buffer_slots: Rc<RefCell<Vec<u32>>>,
+    // Whether the buffer tables of the rings can be updated in place, which
+    // is needed to register arenas once the rings are running
+    buffer_table_updatable: bool,
 }
 
 pub(crate) fn common_flags() -> PollFlags {
@@ -1291,6 +1907,54 @@ macro_rules! flush_rings {
     }}
 }
 
+/// The fixed file table can't be larger than the open files limit, and there's
+/// no point in making it larger than this either.
+const MAX_FIXED_FILES: u64 = 1 << 15;
+
+/// Entries of the buffer table registered in every ring of a reactor, when it
+/// can be updated in place: one per registered arena.
+const BUFFER_TABLE_SLOTS: u32 = 64;
+
+/// Slots of the sparse fixed file table registered in every ring of a
+/// reactor. A registered file has the same slot in all rings.
+#[derive(Debug, Default)]
+struct FixedFiles {
+    // None until the first file is registered, as most executors never do.
+    // Zero if the table couldn't be registered
+    capacity: Option<u32>,
+    slots: AHashMap<RawFd, u32>,
+    free: Vec<u32>,
+    next: u32,
+}
+
+impl FixedFiles {
+    /// Registers an empty table in all `rings`, returning its size.
+    fn register_table(rings: &[&dyn UringCommon]) -> u32 {
+        let capacity = match Resource::NOFILE.get() {
+            Ok((limit, _)) => std::cmp::min(limit, MAX_FIXED_FILES) as u32,
+            Err(_) => 0,
+        };
+        if capacity == 0 {
+            return 0;
+        }
+
+        let table = vec![-1; capacity as usize];
+        for (idx, ring) in rings.iter().enumerate() {
+            if let Err(x) = ring.registrar().register_files(&table) {
+                warn!(
+                    "Error: registering files in the {} ring. Skipping{x:#?}",
+                    ring.name()
+                );
+                for ring in &rings[..idx] {
+                    ring.registrar().unregister_files().unwrap();
+                }
+                return 0;
+            }
+        }
+        capacity
+    }
+}
+
 fn align_up(v: usize, align: usize) -> usize {
     (v + align - 1) & !(align - 1)
 }
@@ -1299,6 +1963,7 @@ impl Reactor {
     pub(crate) fn new(
         notifier: Arc<sys::SleepNotifier>,
         mut io_memory: usize,
+        io_arena: Option<UringBufferAllocator>,
         ring_depth: usize,
         thread_pool_placement: PoolPlacement,
     ) -> crate::Result<Reactor, ()> {
@@ -1315,7 +1980,10 @@ impl Reactor {
         // always have at least some small amount of memory for the slab
         io_memory = std::cmp::max(align_up(io_memory, 4096), 65536);
 
-        let allocator = Rc::new(UringBufferAllocator::new(io_memory));
+        let allocator = Rc::new(match io_arena {
+            Some(arena) => arena,
+            None => UringBufferAllocator::new(io_memory),
+        });
         let registry = vec![allocator.as_bytes()];
 
         let main_ring =
@@ -1324,15 +1992,31 @@ impl Reactor {
         let mut latency_ring =
             SleepableRing::new(ring_depth, "latency", allocator.clone(), source_map.clone())?;
 
-        match main_ring.registrar().register_buffers_by_ref(&registry) {
+        // Tables that can be updated in place let more arenas be registered
+        // later on without waiting for the rings to be idle, which the latency
+        // ring never is: it always has a read of the eventfd in flight.
+        let updatable = [&main_ring as &dyn UringCommon, &poll_ring, &latency_ring]
+            .iter()
+            .all(|ring| ring.registrar().supports_buffer_updates());
+        let register = |ring: &dyn UringCommon| match updatable {
+            true => ring
+                .registrar()
+                .register_buffers_sparse(&registry, BUFFER_TABLE_SLOTS),
+            false => ring
+                .registrar()
+                .register_buffers_by_ref(&registry)
+                .map(|_| ()),
+        };
+        let mut buffer_table_updatable = false;
+        match register(&main_ring) {
             Err(x) => warn!("Error: registering buffers in the main ring. Skipping{x:#?}"),
-            Ok(_) => match poll_ring.registrar().register_buffers_by_ref(&registry) {
+            Ok(_) => match register(&poll_ring) {
                 Err(x) => {
                     warn!("Error: registering buffers in the poll ring. Skipping{x:#?}");
                     main_ring.registrar().unregister_buffers().unwrap();
                 }
                 Ok(_) => {
-                    match latency_ring.registrar().register_buffers_by_ref(&registry) {
+                    match register(&latency_ring) {
                         Err(x) => {
                             warn!("Error: registering buffers in the poll ring. Skipping{x:#?}");
                             poll_ring.registrar().unregister_buffers().unwrap();
@@ -1340,6 +2024,7 @@ impl Reactor {
                         }
                         Ok(_) => {
                             allocator.activate_registered_buffers(0);
+                            buffer_table_updatable = updatable;
                         }
                     };
                 }
@@ -1360,6 +2045,8 @@ impl Reactor {
             latency_ring.install_eventfd(&eventfd_src);
         }
 
+        let provide_requests = ProvideRequests::new(source_map.clone());
+
         Ok(Reactor {
             main_ring: RefCell::new(main_ring),
             latency_ring: RefCell::new(latency_ring),
@@ -1372,9 +2059,88 @@ impl Reactor {
             eventfd_src,
             source_map,
             rings_depth: ring_depth,
+            chaining: Cell::new(false),
+            next_buffer_group: Cell::new(0),
+            free_buffer_groups: Rc::new(RefCell::new(Vec::new())),
+            provide_requests,
+            fixed_files: RefCell::new(FixedFiles::default()),
+            allocator,
+            buffer_slots: RefCell::new((1, Vec::new())),
+            released_buffer_slots: Rc::new(RefCell::new(Vec::new())),
+            buffer_table_updatable,
         })
     }
 
+    /// Registers the memory of `arena` with all the rings, so the buffers
+    /// allocated from it are used in fixed-buffer requests.
+    ///
+    /// The arena takes an entry of the buffer tables of the rings, which is
+    /// updated in place. Kernels that can't do that (before 5.13) wait for the
+    /// rings to be idle to change their tables, which could block forever, so
+    /// arenas can't be registered on them once the rings are running.
+    pub(crate) fn register_buffer_arena(&self, arena: &Rc<UringBufferAllocator>) -> io::Result<()> {
+        if !self.buffer_table_updatable {
+            return Err(io::Error::from_raw_os_error(libc::EOPNOTSUPP));
+        }
+        self.release_buffer_slots();
+        let mut slots = self.buffer_slots.borrow_mut();
+        let idx = match slots.1.pop() {
+            Some(idx) => idx,
+            None if slots.0 < BUFFER_TABLE_SLOTS => {
+                slots.0 += 1;
+                slots.0 - 1
+            }
+            None => return Err(io::Error::from_raw_os_error(libc::ENOSPC)),
+        };
+
+        let main_ring = self.main_ring.borrow();
+        let poll_ring = self.poll_ring.borrow();
+        let latency_ring = self.latency_ring.borrow();
+        let rings: [&dyn UringCommon; 3] = [&*main_ring, &*poll_ring, &*latency_ring];
+        for (i, ring) in rings.iter().enumerate() {
+            if let Err(err) = ring
+                .registrar()
+                .update_registered_buffers(idx, &[arena.as_bytes()])
+            {
+                for ring in &rings[..i] {
+                    let _ = ring.registrar().update_registered_buffers(idx, &[&[]]);
+                }
+                slots.1.push(idx);
+                return Err(err);
+            }
+        }
+
+        arena.activate_registered_buffers(idx);
+        arena.release_to(&self.released_buffer_slots);
+        Ok(())
+    }
+
+    /// Empties the entries of the buffer tables of the arenas that were
+    /// dropped, which unpins their memory, so they can be given to other
+    /// arenas.
+    fn release_buffer_slots(&self) {
+        let released = std::mem::take(&mut *self.released_buffer_slots.borrow_mut());
+        if released.is_empty() {
+            return;
+        }
+        let main_ring = self.main_ring.borrow();
+        let poll_ring = self.poll_ring.borrow();
+        let latency_ring = self.latency_ring.borrow();
+        let rings: [&dyn UringCommon; 3] = [&*main_ring, &*poll_ring, &*latency_ring];
+        let mut slots = self.buffer_slots.borrow_mut();
+        for idx in released {
+            for ring in rings {
+                if let Err(x) = ring.registrar().update_registered_buffers(idx, &[&[]]) {
+                    warn!(
+                        "Error: emptying buffer slot {idx} in the {} ring: {x:#?}",
+                        ring.name()
+                    );
+                }
+            }
+            slots.1.push(idx);
+        }
+    }
+
     pub(crate) fn id(&self) -> usize {
         self.notifier.id()
     }
@@ -1395,6 +2161,10 @@ impl Reactor {
         self.notifier.process_foreign_wakes()
     }
 
+    pub(crate) fn io_memory_page_size(&self) -> usize {
+        self.allocator.page_size()
+    }
+
     pub(crate) fn alloc_dma_buffer(&self, size: usize) -> DmaBuffer {
         let mut poll_ring = self.poll_ring.borrow_mut();
         poll_ring.alloc_dma_buffer(size)
@@ -1436,7 +2206,58 @@ impl Reactor {
         );
     }
 
-    pub(crate) fn read_dma(&self, source: &Source, pos: u64, size: usize) {
+    pub(crate) fn write_vectored(&self, source: &Source, pos: u64) {
+        let op = match &mut *source.source_type_mut() {
+            SourceType::WriteVectored(_, bufs, iovs) => {
+                *iovs = bufs
+                    .iter()
+                    .map(|buf| libc::iovec {
+                        iov_base: buf.as_ptr() as *mut libc::c_void,
+                        iov_len: buf.len(),
+                    })
+                    .collect();
+                UringOpDescriptor::WriteVectored(iovs.as_ptr(), iovs.len(), pos)
+            }
+            x => panic!("Unexpected source type for vectored write: {:?}", x),
+        };
+        queue_request_into_ring(
+            &mut *self.ring_for_source(source),
+            source,
+            op,
+            &mut self.source_map.borrow_mut(),
+        );
+    }
+
+    pub(crate) fn read_vectored(&self, source: &Source, pos: u64) {
+        let op = match &mut *source.source_type_mut() {
+            SourceType::ReadVectored(_, bufs, iovs) => {
+                *iovs = bufs
+                    .iter_mut()
+                    .map(|buf| {
+                        let buf = match buf {
+                            IoBuffer::DmaSink(buf) => buf.as_bytes_mut(),
+                            IoBuffer::Buffered(buf) => buf.as_mut_slice(),
+                            IoBuffer::DmaSource(_) => unreachable!("read into a source buffer"),
+                        };
+                        libc::iovec {
+                            iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+                            iov_len: buf.len(),
+                        }
+                    })
+                    .collect();
+                UringOpDescriptor::ReadVectored(iovs.as_mut_ptr(), iovs.len(), pos)
+            }
+            x => panic!("Unexpected source type for vectored read: {:?}", x),
+        };
+        queue_request_into_ring(
+            &mut *self.ring_for_source(source),
+            source,
+            op,
+            &mut self.source_map.borrow_mut(),
+        );
+    }
+
+    pub(crate) fn read_dma(&self, source: &Source, pos: u64, size: usize) {
         let op = UringOpDescriptor::ReadFixed(pos, size);
         queue_request_into_ring(
             &mut *self.ring_for_source(source),
@@ -1481,6 +2302,25 @@ impl Reactor {
         );
     }
 
+    pub(crate) fn supports_send_zc(&self) -> bool {
+        *URING_SEND_ZC
+    }
+
+    pub(crate) fn send_zc(&self, source: &Source, flags: MsgFlags) {
+        let op = match &*source.source_type() {
+            SourceType::SockSendZc(buf) => {
+                UringOpDescriptor::SockSendZc(buf.as_ptr(), buf.len(), flags.bits())
+            }
+            _ => unreachable!(),
+        };
+        queue_request_into_ring(
+            &mut *self.ring_for_source(source),
+            source,
+            op,
+            &mut self.source_map.borrow_mut(),
+        );
+    }
+
     pub(crate) fn sendmsg(&self, source: &Source, flags: MsgFlags) {
         let op = match &mut *source.source_type_mut() {
             SourceType::SockSendMsg(_, iov, hdr, addr) => {
@@ -1504,6 +2344,83 @@ impl Reactor {
         );
     }
 
+    /// Hands `count` buffers of `buffer_size` bytes over to the latency ring,
+    /// for receives that let the kernel pick their buffer.
+    pub(crate) fn provided_buffer_group(
+        &self,
+        count: usize,
+        buffer_size: usize,
+    ) -> io::Result<Rc<ProvidedBufferGroup>> {
+        // buffer ids are 16 bits wide
+        if count == 0
+            || count > u16::MAX as usize
+            || buffer_size == 0
+            || buffer_size > u32::MAX as usize
+        {
+            return Err(io::Error::from_raw_os_error(libc::EINVAL));
+        }
+        let layout = count
+            .checked_mul(buffer_size)
+            .and_then(|size| Layout::from_size_align(size, 4096).ok())
+            .ok_or_else(|| io::Error::from_raw_os_error(libc::EINVAL))?;
+        let data = ptr::NonNull::new(unsafe { alloc::alloc::alloc(layout) })
+            .ok_or_else(|| io::Error::from_raw_os_error(libc::ENOMEM))?;
+
+        let queue = self.latency_ring.borrow_mut().submission_queue();
+        let id = match self.free_buffer_groups.borrow_mut().pop() {
+            Some((id, stale)) => {
+                // the buffers of the dropped group are still known to the kernel
+                queue.borrow_mut().submissions.push_back(UringDescriptor {
+                    args: UringOpDescriptor::RemoveBuffers(stale, id),
+                    fd: -1,
+                    flags: SubmissionFlags::empty(),
+                    user_data: 0,
+                });
+                id
+            }
+            None => {
+                let id = self.next_buffer_group.get();
+                match id.checked_add(1) {
+                    Some(next) => self.next_buffer_group.set(next),
+                    None => {
+                        unsafe { alloc::alloc::dealloc(data.as_ptr(), layout) };
+                        return Err(io::Error::from_raw_os_error(libc::ENOSPC));
+                    }
+                }
+                id
+            }
+        };
+
+        let group = Rc::new(ProvidedBufferGroup {
+            queue,
+            requests: Rc::downgrade(&self.provide_requests),
+            free_ids: self.free_buffer_groups.clone(),
+            id,
+            data,
+            layout,
+            buffer_size,
+            count: count as u32,
+            in_use: Cell::new(0),
+        });
+        group.provide(0, count as u32);
+        Ok(group)
+    }
+
+    pub(crate) fn recv_select(&self, source: &Source, len: usize, flags: MsgFlags) {
+        let op = match &*source.source_type() {
+            SourceType::SockRecvSelect(group, _) => {
+                UringOpDescriptor::SockRecvSelect(len, flags.bits(), group.id())
+            }
+            x => panic!("Unexpected source type for recv_select: {:?}", x),
+        };
+        queue_request_into_ring(
+            &mut *self.ring_for_source(source),
+            source,
+            op,
+            &mut self.source_map.borrow_mut(),
+        );
+    }
+
     pub(crate) fn recv(&self, source: &Source, len: usize, flags: MsgFlags) {
         let op = UringOpDescriptor::SockRecv(len, flags.bits());
         queue_request_into_ring(
@@ -1550,6 +2467,39 @@ impl Reactor {
         );
     }
 
+    pub(crate) fn supports_multishot_accept(&self) -> bool {
+        *URING_MULTISHOT_ACCEPT
+    }
+
+    pub(crate) fn accept_multi(&self, source: &Source) {
+        queue_request_into_ring(
+            &mut *self.ring_for_source(source),
+            source,
+            UringOpDescriptor::AcceptMulti,
+            &mut self.source_map.borrow_mut(),
+        );
+    }
+
+    pub(crate) fn supports_multishot_recv(&self) -> bool {
+        *URING_MULTISHOT_RECV
+    }
+
+    pub(crate) fn recv_multi(&self, source: &Source, flags: MsgFlags) {
+        let op = match &*source.source_type() {
+            SourceType::RecvMulti(group) => UringOpDescriptor::RecvMulti(flags.bits(), group.id()),
+            SourceType::RecvMsgMulti(group, _) => {
+                UringOpDescriptor::RecvMsgMulti(flags.bits(), group.id())
+            }
+            x => panic!("Unexpected source type for recv_multi: {:?}", x),
+        };
+        queue_request_into_ring(
+            &mut *self.ring_for_source(source),
+            source,
+            op,
+            &mut self.source_map.borrow_mut(),
+        );
+    }
+
     pub(crate) fn fdatasync(&self, source: &Source) {
         queue_request_into_ring(
             &mut *self.ring_for_source(source),
@@ -1559,6 +2509,25 @@ impl Reactor {
         );
     }
 
+    pub(crate) fn fsync(&self, source: &Source) {
+        queue_request_into_ring(
+            &mut *self.ring_for_source(source),
+            source,
+            UringOpDescriptor::FSync,
+            &mut self.source_map.borrow_mut(),
+        );
+    }
+
+    pub(crate) fn sync_file_range(&self, source: &Source, offset: u64, len: u32, flags: u32) {
+        let op = UringOpDescriptor::SyncFileRange(offset, len, flags);
+        queue_request_into_ring(
+            &mut *self.ring_for_source(source),
+            source,
+            op,
+            &mut self.source_map.borrow_mut(),
+        );
+    }
+
     pub(crate) fn fallocate(&self, source: &Source, offset: u64, size: u64, flags: libc::c_int) {
         let op = UringOpDescriptor::Fallocate(offset, size, flags);
         queue_request_into_ring(
@@ -1569,6 +2538,60 @@ impl Reactor {
         );
     }
 
+    pub(crate) fn fadvise(
+        &self,
+        source: &Source,
+        offset: u64,
+        len: u64,
+        advice: PosixFadviseAdvice,
+    ) {
+        let op = UringOpDescriptor::Fadvise(offset, len, advice);
+        queue_request_into_ring(
+            &mut *self.ring_for_source(source),
+            source,
+            op,
+            &mut self.source_map.borrow_mut(),
+        );
+    }
+
+    pub(crate) fn madvise(&self, source: &Source, addr: *mut u8, len: usize, advice: MmapAdvise) {
+        let op = UringOpDescriptor::Madvise(addr, len, advice);
+        queue_request_into_ring(
+            &mut *self.ring_for_source(source),
+            source,
+            op,
+            &mut self.source_map.borrow_mut(),
+        );
+    }
+
+    pub(crate) fn splice(
+        &self,
+        source: &Source,
+        fd_in: RawFd,
+        off_in: Option<u64>,
+        off_out: Option<u64>,
+        len: u32,
+    ) {
+        // -1 tells the kernel to use, and advance, the current file position
+        let off = |x: Option<u64>| x.map_or(-1, |x| x as i64);
+        let op = UringOpDescriptor::Splice(fd_in, off(off_in), off(off_out), len);
+        queue_request_into_ring(
+            &mut *self.ring_for_source(source),
+            source,
+            op,
+            &mut self.source_map.borrow_mut(),
+        );
+    }
+
+    pub(crate) fn tee(&self, source: &Source, fd_in: RawFd, len: u32) {
+        queue_request_into_ring(
+            &mut *self.ring_for_source(source),
+            source,
+            UringOpDescriptor::Tee(fd_in, len),
+            &mut self.source_map.borrow_mut(),
+        );
+    }
+
     fn enqueue_blocking_request(
         &self,
         source: Pin<Rc<RefCell<InnerSource>>>,
@@ -1582,6 +2605,11 @@ impl Reactor {
         self.enqueue_blocking_request(source.inner.clone(), op)
     }
 
+    pub(crate) fn read_dir(&self, source: &Source, size: usize) -> impl Future<Output = ()> {
+        let op = BlockingThreadOp::ReadDir(source.raw(), size);
+        self.enqueue_blocking_request(source.inner.clone(), op)
+    }
+
     pub(crate) fn rename(&self, source: &Source) -> impl Future<Output = ()> {
         let (old_path, new_path) = match &*source.source_type() {
             SourceType::Rename(o, n) => (o.clone(), n.clone()),
@@ -1592,6 +2620,199 @@ impl Reactor {
         self.enqueue_blocking_request(source.inner.clone(), op)
     }
 
+    pub(crate) fn rename_at(
+        &self,
+        source: &Source,
+        new_dir: RawFd,
+        flags: libc::c_uint,
+    ) -> impl Future<Output = ()> {
+        let waiter = if *URING_RENAMEAT {
+            let op = match &*source.source_type() {
+                SourceType::RenameAt(old_path, new_path) => UringOpDescriptor::RenameAt(
+                    old_path.as_ptr() as _,
+                    new_dir,
+                    new_path.as_ptr() as _,
+                    flags,
+                ),
+                _ => panic!("Unexpected source for rename operation"),
+            };
+            queue_request_into_ring(
+                &mut *self.ring_for_source(source),
+                source,
+                op,
+                &mut self.source_map.borrow_mut(),
+            );
+            None
+        } else {
+            let op = match &*source.source_type() {
+                SourceType::RenameAt(old_path, new_path) => BlockingThreadOp::RenameAt(
+                    source.raw(),
+                    old_path.clone(),
+                    new_dir,
+                    new_path.clone(),
+                    flags,
+                ),
+                _ => panic!("Unexpected source for rename operation"),
+            };
+            Some(self.enqueue_blocking_request(source.inner.clone(), op))
+        };
+
+        async move {
+            if let Some(waiter) = waiter {
+                waiter.await
+            }
+        }
+    }
+
+    pub(crate) fn link_at(
+        &self,
+        source: &Source,
+        new_dir: RawFd,
+        flags: libc::c_int,
+    ) -> impl Future<Output = ()> {
+        let waiter = if *URING_LINKAT {
+            let op = match &*source.source_type() {
+                SourceType::LinkAt(old_path, new_path) => UringOpDescriptor::LinkAt(
+                    old_path.as_ptr() as _,
+                    new_dir,
+                    new_path.as_ptr() as _,
+                    flags,
+                ),
+                _ => panic!("Unexpected source for link operation"),
+            };
+            queue_request_into_ring(
+                &mut *self.ring_for_source(source),
+                source,
+                op,
+                &mut self.source_map.borrow_mut(),
+            );
+            None
+        } else {
+            let op = match &*source.source_type() {
+                SourceType::LinkAt(old_path, new_path) => BlockingThreadOp::LinkAt(
+                    source.raw(),
+                    old_path.clone(),
+                    new_dir,
+                    new_path.clone(),
+                    flags,
+                ),
+                _ => panic!("Unexpected source for link operation"),
+            };
+            Some(self.enqueue_blocking_request(source.inner.clone(), op))
+        };
+
+        async move {
+            if let Some(waiter) = waiter {
+                waiter.await
+            }
+        }
+    }
+
+    pub(crate) fn symlink_at(&self, source: &Source) -> impl Future<Output = ()> {
+        let waiter = if *URING_SYMLINKAT {
+            let op = match &*source.source_type() {
+                SourceType::SymlinkAt(target, link_path) => {
+                    UringOpDescriptor::SymlinkAt(target.as_ptr() as _, link_path.as_ptr() as _)
+                }
+                _ => panic!("Unexpected source for symlink operation"),
+            };
+            queue_request_into_ring(
+                &mut *self.ring_for_source(source),
+                source,
+                op,
+                &mut self.source_map.borrow_mut(),
+            );
+            None
+        } else {
+            let op = match &*source.source_type() {
+                SourceType::SymlinkAt(target, link_path) => {
+                    BlockingThreadOp::SymlinkAt(target.clone(), source.raw(), link_path.clone())
+                }
+                _ => panic!("Unexpected source for symlink operation"),
+            };
+            Some(self.enqueue_blocking_request(source.inner.clone(), op))
+        };
+
+        async move {
+            if let Some(waiter) = waiter {
+                waiter.await
+            }
+        }
+    }
+
+    pub(crate) fn unlink_at(
+        &self,
+        source: &Source,
+        flags: libc::c_int,
+    ) -> impl Future<Output = ()> {
+        let waiter = if *URING_UNLINKAT {
+            let op = match &*source.source_type() {
+                SourceType::UnlinkAt(path) => {
+                    UringOpDescriptor::UnlinkAt(path.as_ptr() as _, flags as _)
+                }
+                _ => panic!("Unexpected source for unlink operation"),
+            };
+            queue_request_into_ring(
+                &mut *self.ring_for_source(source),
+                source,
+                op,
+                &mut self.source_map.borrow_mut(),
+            );
+            None
+        } else {
+            let op = match &*source.source_type() {
+                SourceType::UnlinkAt(path) => {
+                    BlockingThreadOp::UnlinkAt(source.raw(), path.clone(), flags)
+                }
+                _ => panic!("Unexpected source for unlink operation"),
+            };
+            Some(self.enqueue_blocking_request(source.inner.clone(), op))
+        };
+
+        async move {
+            if let Some(waiter) = waiter {
+                waiter.await
+            }
+        }
+    }
+
+    pub(crate) fn mkdir_at(&self, source: &Source, mode: libc::mode_t) -> impl Future<Output = ()> {
+        let waiter = if *URING_MKDIRAT {
+            let op = match &*source.source_type() {
+                SourceType::MkdirAt(path) => {
+                    UringOpDescriptor::MkdirAt(path.as_ptr() as _, mode as _)
+                }
+                _ => panic!("Unexpected source for mkdir operation"),
+            };
+            queue_request_into_ring(
+                &mut *self.ring_for_source(source),
+                source,
+                op,
+                &mut self.source_map.borrow_mut(),
+            );
+            None
+        } else {
+            let op = match &*source.source_type() {
+                SourceType::MkdirAt(path) => {
+                    BlockingThreadOp::MkdirAt(source.raw(), path.clone(), mode)
+                }
+                _ => panic!("Unexpected source for mkdir operation"),
+            };
+            Some(self.enqueue_blocking_request(source.inner.clone(), op))
+        };
+
+        async move {
+            if let Some(waiter) = waiter {
+                waiter.await
+            }
+        }
+    }
+
+    pub(crate) fn read_link(&self, source: &Source, path: CString) -> impl Future<Output = ()> {
+        let op = BlockingThreadOp::ReadLink(source.raw(), path);
+        self.enqueue_blocking_request(source.inner.clone(), op)
+    }
+
     pub(crate) fn create_dir(
         &self,
         source: &Source,
@@ -1616,6 +2837,16 @@ impl Reactor {
         self.enqueue_blocking_request(source.inner.clone(), op)
     }
 
+    pub(crate) fn remove_dir(&self, source: &Source) -> impl Future<Output = ()> {
+        let path = match &*source.source_type() {
+            SourceType::Remove(path) => path.clone(),
+            _ => panic!("Unexpected source for remove dir operation"),
+        };
+
+        let op = BlockingThreadOp::RemoveDir(path);
+        self.enqueue_blocking_request(source.inner.clone(), op)
+    }
+
     pub(crate) fn run_blocking(
         &self,
         source: &Source,
@@ -1628,6 +2859,7 @@ impl Reactor {
     }
 
     pub(crate) fn close(&self, source: &Source) {
+        self.unregister_file(source.raw());
         let op = UringOpDescriptor::Close;
         queue_request_into_ring(
             &mut *self.ring_for_source(source),
@@ -1734,7 +2966,51 @@ impl Reactor {
             .map(|_| {})
     }
 
+    /// While chaining, every request is queued into the main ring, so that
+    /// consecutive requests can be linked with [`Reactor::link_chained`].
+    pub(crate) fn set_chaining(&self, chaining: bool) {
+        self.chaining.set(chaining);
+    }
+
+    /// Links `source`, which must be the last request queued while chaining,
+    /// to the request that is queued next using `link`. If a timeout is
+    /// given, it bounds how long `source` may take, and the rest of the
+    /// chain is linked to it instead.
+    pub(crate) fn link_chained(
+        &self,
+        source: &Source,
+        timeout: Option<Duration>,
+        link: SubmissionFlags,
+    ) {
+        assert!(self.chaining.get(), "linking requests outside of a chain");
+        let queue = self.main_ring.borrow_mut().submission_queue();
+        let mut queue = queue.borrow_mut();
+        let id = source.inner.borrow().enqueued.as_ref().map(|x| x.id);
+        let op = queue
+            .submissions
+            .back_mut()
+            .filter(|op| id.map(to_user_data) == Some(op.user_data))
+            .expect("the chained request must be the last one queued");
+
+        match timeout {
+            Some(timeout) => {
+                op.flags |= SubmissionFlags::IO_LINK;
+                source.set_timeout(timeout);
+                let ts = &source.timeout_ref().unwrap().raw as *const _;
+                queue.submissions.push_back(UringDescriptor {
+                    args: UringOpDescriptor::LinkTimeout(ts),
+                    flags: link,
+                    fd: -1,
+                    user_data: 0,
+                });
+            }
+            None => op.flags |= link,
+        }
+    }
+
     pub(crate) fn poll_io(&self, woke: &mut usize) -> io::Result<()> {
+        self.provide_requests.reap();
+        self.release_buffer_slots();
         self.poll_ring.borrow_mut().poll(woke)?;
         self.main_ring.borrow_mut().poll(woke)?;
         self.latency_ring.borrow_mut().poll(woke)?;
@@ -1804,6 +3080,8 @@ impl Reactor {
         F: Fn() -> usize,
     {
         woke += self.flush_syscall_thread();
+        self.provide_requests.reap();
+        self.release_buffer_slots();
 
         let mut poll_ring = self.poll_ring.borrow_mut();
         let mut main_ring = self.main_ring.borrow_mut();
@@ -1914,6 +3192,7 @@ impl Reactor {
     /// We can't do this through a Source, because the Source will be dropped
     /// when the file is dropped.
     pub(crate) fn async_close(&self, fd: RawFd) {
+        self.unregister_file(fd);
         let q = self.main_ring.borrow_mut().submission_queue();
         let mut queue = q.borrow_mut();
         queue.submissions.push_back(UringDescriptor {
@@ -1924,7 +3203,88 @@ impl Reactor {
         });
     }
 
+    /// Puts `fd` in the fixed file table of the rings, so requests on it can
+    /// skip looking it up. The table is registered on first use, and if that
+    /// fails `fd` is left out of it, so requests on it keep using `fd`.
+    pub(crate) fn register_file(&self, fd: RawFd) -> io::Result<()> {
+        let mut files = self.fixed_files.borrow_mut();
+        if files.slots.contains_key(&fd) {
+            return Ok(());
+        }
+        let capacity = match files.capacity {
+            Some(capacity) => capacity,
+            None => {
+                let main_ring = self.main_ring.borrow();
+                let poll_ring = self.poll_ring.borrow();
+                let latency_ring = self.latency_ring.borrow();
+                let capacity =
+                    FixedFiles::register_table(&[&*main_ring, &*poll_ring, &*latency_ring]);
+                files.capacity = Some(capacity);
+                capacity
+            }
+        };
+        if capacity == 0 {
+            return Ok(());
+        }
+        let slot = match files.free.pop() {
+            Some(slot) => slot,
+            None if files.next < capacity => {
+                files.next += 1;
+                files.next - 1
+            }
+            None => return Err(io::Error::from_raw_os_error(libc::ENFILE)),
+        };
+        if let Err(err) = self.update_fixed_file(slot, fd) {
+            files.free.push(slot);
+            return Err(err);
+        }
+        files.slots.insert(fd, slot);
+        Ok(())
+    }
+
+    /// Removes `fd` from the fixed file table, if it is there.
+    pub(crate) fn unregister_file(&self, fd: RawFd) {
+        let mut files = self.fixed_files.borrow_mut();
+        if let Some(slot) = files.slots.remove(&fd) {
+            if let Err(x) = self.update_fixed_file(slot, -1) {
+                warn!("Error: unregistering file {fd} from slot {slot}: {x:#?}");
+            }
+            files.free.push(slot);
+        }
+    }
+
+    pub(crate) fn fixed_file(&self, fd: RawFd) -> Option<u32> {
+        self.fixed_files.borrow().slots.get(&fd).copied()
+    }
+
+    fn update_fixed_file(&self, slot: u32, fd: RawFd) -> io::Result<()> {
+        let main_ring = self.main_ring.borrow();
+        let poll_ring = self.poll_ring.borrow();
+        let latency_ring = self.latency_ring.borrow();
+        let rings: [&dyn UringCommon; 3] = [&*main_ring, &*poll_ring, &*latency_ring];
+        for (idx, ring) in rings.iter().enumerate() {
+            if let Err(err) = ring
+                .registrar()
+                .update_registered_files(slot as usize, &[fd])
+            {
+                for ring in &rings[..idx] {
+                    let _ = ring
+                        .registrar()
+                        .update_registered_files(slot as usize, &[-1]);
+                }
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn ring_for_source(&self, source: &Source) -> RefMut<'_, dyn UringCommon> {
+        // Linked requests must be in the same ring. The main ring is the only
+        // one that can take every kind of request.
+        if self.chaining.get() {
+            return self.main_ring.borrow_mut();
+        }
+
         // Dispatch requests according to the following rules:
         // * Disk reads/writes go to the poll ring if possible, or the main ring
         //   otherwise;
@@ -1934,13 +3294,20 @@ impl Reactor {
         // because the more request we issue there, the less effective it becomes.
 
         match &*source.source_type() {
-            SourceType::Read(p, _) | SourceType::Write(p, _) => match p {
+            SourceType::Read(p, _)
+            | SourceType::Write(p, _)
+            | SourceType::ReadVectored(p, ..)
+            | SourceType::WriteVectored(p, ..) => match p {
                 PollableStatus::Pollable => self.poll_ring.borrow_mut(),
                 PollableStatus::NonPollable(_) => self.main_ring.borrow_mut(),
             },
             SourceType::SockRecv(_)
+            | SourceType::SockRecvSelect(..)
             | SourceType::SockRecvMsg(_, _, _, _)
             | SourceType::Accept(_)
+            | SourceType::AcceptMulti
+            | SourceType::RecvMulti(_)
+            | SourceType::RecvMsgMulti(..)
             | SourceType::Connect(_) => self.latency_ring.borrow_mut(),
             SourceType::Invalid => {
                 unreachable!("called ring_for_source on invalid source")
@@ -1954,6 +3321,7 @@ impl Reactor {
             std::mem::take(&mut self.main_ring.borrow_mut().stats),
             std::mem::take(&mut self.latency_ring.borrow_mut().stats),
             std::mem::take(&mut self.poll_ring.borrow_mut().stats),
+            self.allocator.take_fallbacks(),
         )
     }
 
@@ -1984,6 +3352,7 @@ impl Reactor {
                 main.unwrap_or_default(),
                 lat.unwrap_or_default(),
                 poll.unwrap_or_default(),
+                (0, 0),
             ))
         }
     }
@@ -1999,15 +3368,23 @@ fn queue_request_into_ring(
     let q = ring.submission_queue();
     let id = source_map.add_source(source, Rc::clone(&q));
 
-    let flags = match &*source.timeout_ref() {
+    let mut flags = match &*source.timeout_ref() {
         Some(_) => SubmissionFlags::IO_LINK,
         _ => SubmissionFlags::empty(),
     };
 
+    let fd = match source.inner.borrow().fixed_file {
+        Some(slot) if descriptor.supports_fixed_file() => {
+            flags |= SubmissionFlags::FIXED_FILE;
+            slot as RawFd
+        }
+        _ => source.raw(),
+    };
+
     let mut queue = q.borrow_mut();
     queue.submissions.push_back(UringDescriptor {
         args: descriptor,
-        fd: source.raw(),
+        fd,
         flags,
         user_data: to_user_data(id),
     });
@@ -2035,7 +3412,7 @@ mod tests {
     #[test]
     fn timeout_smoke_test() {
         let notifier = sys::new_sleep_notifier().unwrap();
-        let reactor = Reactor::new(notifier, 0, 128, PoolPlacement::Unbound(1)).unwrap();
+        let reactor = Reactor::new(notifier, 0, None, 128, PoolPlacement::Unbound(1)).unwrap();
 
         fn timeout_source(millis: u64) -> (Source, UringOpDescriptor) {
             let source = Source::new(
@@ -44,6 +44,20 @@ pub enum IoRingOp {
     IORING_OP_PROVIDE_BUFFERS,
     IORING_OP_REMOVE_BUFFERS,
     IORING_OP_TEE,
+    IORING_OP_SHUTDOWN,
+    IORING_OP_RENAMEAT,
+    IORING_OP_UNLINKAT,
+    IORING_OP_MKDIRAT,
+    IORING_OP_SYMLINKAT,
+    IORING_OP_LINKAT,
+    IORING_OP_MSG_RING,
+    IORING_OP_FSETXATTR,
+    IORING_OP_SETXATTR,
+    IORING_OP_FGETXATTR,
+    IORING_OP_GETXATTR,
+    IORING_OP_SOCKET,
+    IORING_OP_URING_CMD,
+    IORING_OP_SEND_ZC,
 }
 
 // sqe.flags
@@ -60,6 +74,12 @@ pub const IORING_FSYNC_DATASYNC: libc::__u32 = 1 << 0;
 // sqe.cmd_flags.timeout_flags
 pub const IORING_TIMEOUT_ABS: libc::__u32 = 1 << 0;
 
+// sqe.ioprio for IORING_OP_ACCEPT
+pub const IORING_ACCEPT_MULTISHOT: libc::__u16 = 1 << 0;
+
+// sqe.ioprio for IORING_OP_RECV and IORING_OP_RECVMSG
+pub const IORING_RECV_MULTISHOT: libc::__u16 = 1 << 1;
+
 // sqe.cmd_flags.splice_flags
 pub const SPLICE_F_FD_IN_FIXED: libc::__u32 = 1 << 31;
 
@@ -72,7 +92,10 @@ pub const IORING_SETUP_CLAMP: libc::c_uint = 1 << 4; /* clamp SQ/CQ ring sizes *
 pub const IORING_SETUP_ATTACH_WQ: libc::c_uint = 1 << 5; /* attach to existing wq */
 
 // cqe.flags
-pub const IORING_CQE_BUFFER_SHIFT: libc::c_uint = 1 << 0;
+pub const IORING_CQE_F_BUFFER: libc::c_uint = 1 << 0;
+pub const IORING_CQE_F_MORE: libc::c_uint = 1 << 1;
+pub const IORING_CQE_F_NOTIF: libc::c_uint = 1 << 3;
+pub const IORING_CQE_BUFFER_SHIFT: libc::c_uint = 16;
 
 // Magic offsets for the application to mmap the data it needs
 pub const IORING_OFF_SQ_RING: libc::__u64 = 0;
@@ -98,6 +121,7 @@ pub const IORING_FEAT_RW_CUR_POS: libc::__u32 = 1 << 3;
 pub const IORING_FEAT_CUR_PERSONALITY: libc::__u32 = 1 << 4;
 pub const IORING_FEAT_FAST_POLL: libc::__u32 = 1 << 5;
 pub const IORING_FEAT_POLL_32BITS: libc::__u32 = 1 << 6;
+pub const IORING_FEAT_RSRC_TAGS: libc::__u32 = 1 << 10;
 
 // io_uring_register opcodes and arguments
 pub const IORING_REGISTER_BUFFERS: libc::c_uint = 0;
@@ -111,6 +135,8 @@ pub const IORING_REGISTER_EVENTFD_ASYNC: libc::c_uint = 7;
 pub const IORING_REGISTER_PROBE: libc::c_uint = 8;
 pub const IORING_REGISTER_PERSONALITY: libc::c_uint = 9;
 pub const IORING_UNREGISTER_PERSONALITY: libc::c_uint = 10;
+pub const IORING_REGISTER_BUFFERS2: libc::c_uint = 15;
+pub const IORING_REGISTER_BUFFERS_UPDATE: libc::c_uint = 16;
 
 #[derive(Debug)]
 #[repr(C)]
@@ -196,6 +222,9 @@ pub union cmd_flags {
     pub statx_flags: libc::__u32,
     pub fadvise_advice: libc::__u32,
     pub splice_flags: libc::__u32,
+    pub rename_flags: libc::__u32,
+    pub unlink_flags: libc::__u32,
+    pub hardlink_flags: libc::__u32,
 }
 
 #[allow(non_camel_case_types)]
@@ -222,6 +251,15 @@ pub struct io_uring_cqe {
     pub flags: libc::__u32,
 }
 
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct io_uring_recvmsg_out {
+    pub namelen: libc::__u32,
+    pub controllen: libc::__u32,
+    pub payloadlen: libc::__u32,
+    pub flags: libc::__u32,
+}
+
 #[repr(C)]
 pub struct io_uring_params {
     pub sq_entries: libc::__u32,
@@ -269,6 +307,25 @@ pub struct io_uring_probe {
     ops: [io_uring_probe_op; 0],
 }
 
+#[repr(C)]
+pub struct io_uring_rsrc_register {
+    pub nr: libc::__u32,
+    pub resv: libc::__u32,
+    pub resv2: libc::__u64,
+    pub data: libc::__u64,
+    pub tags: libc::__u64,
+}
+
+#[repr(C)]
+pub struct io_uring_rsrc_update2 {
+    pub offset: libc::__u32,
+    pub resv: libc::__u32,
+    pub data: libc::__u64,
+    pub tags: libc::__u64,
+    pub nr: libc::__u32,
+    pub resv2: libc::__u32,
+}
+
 #[repr(C)]
 pub struct io_uring_probe_op {
     op: libc::__u8,